@@ -0,0 +1,84 @@
+use std::sync::Arc;
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+
+use rustamoz::{
+    classic_game::{
+        action::ClassicAction, cell_state::ClassicCellState, game_state::ClassicGameState,
+        rules::ClassicRules,
+    },
+    generic_game::{
+        game_rule::GameRule,
+        game_state::GameState,
+        player::{PlayerInfo, PlayerRage},
+    },
+    geometry::{
+        terrain::{CellMaterial, CellType, Terrain},
+        vector::Position,
+        volume::Volume,
+    },
+};
+
+/// Sizes of the boards (x and y, z is a quarter of them)
+const SIZES: [i32; 3] = [8, 16, 32];
+
+/// Create a board where all cells of first player are one atom short of exploding,
+/// but the opposite corner owned by second player
+fn loaded_board(rules: &ClassicRules, size: i32) -> (ClassicGameState, ClassicAction) {
+    let corner = Position::new(size - 1, size - 1, size / 4 - 1);
+    let vol = Volume::new(&Position::zero(), &corner).unwrap();
+    let mut terrain = Terrain::new(&vol);
+    for p in &vol {
+        terrain.set_cell_at(&p, CellType::Fill(CellMaterial::Ground));
+    }
+    let players = [
+        PlayerInfo::new("A", PlayerRage::Red, true),
+        PlayerInfo::new("B", PlayerRage::Blue, true),
+    ];
+    let terrain = Arc::new(terrain);
+    let mut gs = ClassicGameState::new(Arc::clone(&terrain), &players).unwrap();
+    for p in &vol {
+        let atoms = rules.critical_mass(&terrain, &p) - 1;
+        gs.set_cell_state(&p, &ClassicCellState::Occupied(players[0].id(), atoms));
+    }
+    gs.set_cell_state(&corner, &ClassicCellState::Occupied(players[1].id(), 1));
+
+    let action = ClassicAction::place_atom(players[0].id(), 0, Position::zero());
+    (gs, action)
+}
+
+/// Placing an atom that makes the whole board explode
+fn cascade(c: &mut Criterion) {
+    let rules = ClassicRules::default();
+    let mut group = c.benchmark_group("cascade");
+    group.sample_size(10);
+    for size in SIZES.iter() {
+        let (gs, action) = loaded_board(&rules, *size);
+        let (gs, action) = (Arc::new(gs), Arc::new(action));
+        group.bench_with_input(BenchmarkId::from_parameter(size), size, |b, _| {
+            b.iter(|| rules.execute(Arc::clone(&gs), Arc::clone(&action)))
+        });
+    }
+    group.finish();
+}
+
+/// Taking a snapshot of a state, then changing a single cell (as each substep does)
+fn snapshot(c: &mut Criterion) {
+    let rules = ClassicRules::default();
+    let mut group = c.benchmark_group("snapshot");
+    for size in SIZES.iter() {
+        let (gs, _) = loaded_board(&rules, *size);
+        let state = ClassicCellState::Empty;
+        group.bench_with_input(BenchmarkId::from_parameter(size), size, |b, _| {
+            b.iter(|| {
+                let mut snapshot = gs.clone();
+                snapshot.set_cell_state(&Position::zero(), &state);
+                snapshot
+            })
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, cascade, snapshot);
+criterion_main!(benches);
@@ -0,0 +1,11 @@
+// Imports AiProfile, Difficulty, Personality
+pub mod profile;
+
+// Imports Elo and Glicko ratings
+pub mod rating;
+
+// Imports Tournament, MatchRunner
+pub mod tournament;
+
+// Imports ClassicRunner
+pub mod classic_runner;
@@ -0,0 +1,419 @@
+use std::{sync::Arc, time::Instant};
+
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+use crate::{
+    classic_game::{
+        action::ClassicAction, basic_rules::ClassicBasicRules, cell_state::ClassicCellState,
+        controller::ClassicController, game_state::ClassicGameState, rules::ClassicRules,
+        rules_config::ClassicRulesConfig,
+    },
+    generic_game::{
+        action::TurnId,
+        cell_state::CellState,
+        game_rule::{BasicRules, GameOutcome, GameRule},
+        game_state::GameState,
+        player::{PlayerId, PlayerInfo, PlayerRage, PlayerState},
+        transposition_table::Depth,
+    },
+    geometry::{terrain::Terrain, vector::Position},
+};
+
+use super::{
+    profile::{AiProfile, EvaluatorWeights},
+    tournament::{BotConfig, MatchOutcome, MatchRunner, Seed},
+};
+
+/// Number of turns after which a game ends in a draw, if rules do not set a limit
+pub const DEFAULT_MAX_TURNS: TurnId = 500;
+
+/// Evaluation of a won game (a lost game is evaluated `-WIN_SCORE`)
+const WIN_SCORE: f32 = 1.0e6;
+
+/// Runner playing headless classic games with a `ClassicController`.
+///
+/// Each bot searches the moves of the next turns (see `choose`), or plays a random legal
+/// move with the blunder probability of its profile (random moves are drawn from the seed
+/// of the match).
+#[derive(Debug, Clone)]
+pub struct ClassicRunner {
+    config: ClassicRulesConfig,
+}
+
+impl Default for ClassicRunner {
+    /// Default rules
+    fn default() -> Self {
+        Self::new(ClassicRulesConfig::default())
+    }
+}
+
+impl ClassicRunner {
+    /// Create a runner playing with provided rules.
+    ///
+    /// If rules have no turn limit, games end in a draw after `DEFAULT_MAX_TURNS` turns.
+    pub fn new(mut config: ClassicRulesConfig) -> Self {
+        if config.max_turns().is_none() {
+            config.set_max_turns(Some(DEFAULT_MAX_TURNS));
+        }
+        Self { config }
+    }
+
+    /// Returns rules of the games
+    pub fn config(&self) -> &ClassicRulesConfig {
+        &self.config
+    }
+
+    /// Returns the action chosen by a bot for the current player
+    fn choose(
+        &self,
+        controller: &ClassicController,
+        bot: &BotConfig,
+        rng: &mut StdRng,
+    ) -> Option<ClassicAction> {
+        choose(
+            controller.rules(),
+            &controller.game_state(),
+            &bot.profile(),
+            rng,
+        )
+    }
+}
+
+impl MatchRunner for ClassicRunner {
+    /// Play a game on the provided terrain and returns its outcome
+    fn play(&mut self, seats: &[&BotConfig], terrain: &Arc<Terrain>, seed: Seed) -> MatchOutcome {
+        let players: Vec<_> = seats
+            .iter()
+            .zip(PlayerRage::all().iter().cycle())
+            .map(|(bot, rage)| PlayerInfo::new(bot.name(), *rage, false))
+            .collect();
+        let game_state = match ClassicGameState::new(Arc::clone(terrain), &players) {
+            Some(game_state) => game_state,
+            None => return MatchOutcome::Draw,
+        };
+        let basic_rules = ClassicBasicRules::new(self.config);
+        let mut controller = ClassicController::new(ClassicRules::new(self.config), game_state);
+        let mut rng = StdRng::seed_from_u64(seed);
+
+        let outcome = loop {
+            let game_state = controller.game_state();
+            if let Some(outcome) = basic_rules.outcome(&game_state) {
+                break outcome;
+            }
+            let seat = game_state
+                .seat(game_state.current_player())
+                .expect("Current player shall be seated");
+            match self.choose(&controller, seats[seat], &mut rng) {
+                Some(action) if controller.play(action).is_some() => {}
+                _ => return MatchOutcome::Draw,
+            }
+        };
+
+        match outcome {
+            GameOutcome::Winner(id) => players
+                .iter()
+                .position(|p| p.id() == id)
+                .map_or(MatchOutcome::Draw, MatchOutcome::Winner),
+            GameOutcome::TeamWinner(_) | GameOutcome::Draw(_) => MatchOutcome::Draw,
+        }
+    }
+}
+
+/// Returns the action chosen by a CPU player with provided profile for the current player
+/// (`None` if there are no legal moves).
+///
+/// Player searches the moves of the next `search_depth()` turns, assuming its opponents play
+/// against it and its allies with it, and plays the move leading to the best evaluation
+/// (see `evaluate`). Search is deepened one turn at a time while it fits its `time_budget()`
+/// (the first turn is always searched). With its blunder probability it plays a random
+/// legal move instead.
+pub fn choose<R: Rng>(
+    rules: &ClassicRules,
+    game_state: &Arc<ClassicGameState>,
+    profile: &AiProfile,
+    rng: &mut R,
+) -> Option<ClassicAction> {
+    let player = game_state.current_player();
+    let turn = game_state.current_turn();
+    let legal = rules.legal_positions(game_state);
+    if legal.is_empty() {
+        return None;
+    }
+    if rng.gen::<f32>() < profile.blunder_probability() {
+        let position = legal[rng.gen_range(0, legal.len())];
+        return Some(ClassicAction::place_atom(player, turn, position));
+    }
+
+    let search = Search {
+        rules,
+        basic_rules: ClassicBasicRules::new(*rules.config()),
+        root: game_state,
+        player,
+        weights: profile.evaluator_weights(),
+        deadline: Instant::now() + profile.time_budget(),
+    };
+    let mut best = legal[0];
+    for depth in 1..=profile.search_depth() {
+        match search.best_position(&legal, depth) {
+            Some((position, value)) => {
+                best = position;
+                // No need to search deeper for a sure win
+                if value >= WIN_SCORE {
+                    break;
+                }
+            }
+            None => break,
+        }
+    }
+    Some(ClassicAction::place_atom(player, turn, best))
+}
+
+/// Depth-limited search of the best move of a player (paranoid alpha-beta)
+struct Search<'a> {
+    rules: &'a ClassicRules,
+    basic_rules: ClassicBasicRules,
+    /// State the search starts from
+    root: &'a Arc<ClassicGameState>,
+    /// Player searching its move
+    player: PlayerId,
+    weights: EvaluatorWeights,
+    /// Time after which the search is interrupted
+    deadline: Instant,
+}
+
+impl Search<'_> {
+    /// Returns the position with the best value searching `depth` turns, and its value
+    /// (`None` if the deadline is reached before the search is over)
+    fn best_position(&self, legal: &[Position], depth: Depth) -> Option<(Position, f32)> {
+        let mut best = None;
+        let mut alpha = f32::MIN;
+        for position in legal {
+            let next = self.play(self.root, *position);
+            let value = self.value(&next, depth - 1, alpha, f32::MAX)?;
+            if best.is_none_or(|(_, v)| value > v) {
+                best = Some((*position, value));
+                alpha = alpha.max(value);
+            }
+        }
+        best
+    }
+
+    /// Returns the value of a state for the player searching `depth` more turns
+    /// (`None` if the deadline is reached)
+    fn value(
+        &self,
+        game_state: &Arc<ClassicGameState>,
+        depth: Depth,
+        mut alpha: f32,
+        mut beta: f32,
+    ) -> Option<f32> {
+        if let Some(outcome) = self.basic_rules.outcome(game_state) {
+            return Some(self.outcome_value(game_state, &outcome, depth));
+        }
+        let legal = self.rules.legal_positions(game_state);
+        if depth == 0 || legal.is_empty() {
+            return Some(evaluate(
+                self.rules,
+                self.root,
+                game_state,
+                self.player,
+                &self.weights,
+            ));
+        }
+        if Instant::now() >= self.deadline {
+            return None;
+        }
+
+        // Allies play for the player, opponents against it
+        let maximizing = game_state.are_allies(self.player, game_state.current_player());
+        let mut best = if maximizing { f32::MIN } else { f32::MAX };
+        for position in legal {
+            let next = self.play(game_state, position);
+            let value = self.value(&next, depth - 1, alpha, beta)?;
+            if maximizing {
+                best = best.max(value);
+                alpha = alpha.max(value);
+            } else {
+                best = best.min(value);
+                beta = beta.min(value);
+            }
+            if alpha >= beta {
+                break;
+            }
+        }
+        Some(best)
+    }
+
+    /// Returns the value of a game over, sooner wins (and later losses) being better
+    fn outcome_value(
+        &self,
+        game_state: &ClassicGameState,
+        outcome: &GameOutcome,
+        depth: Depth,
+    ) -> f32 {
+        let won = match outcome {
+            GameOutcome::Winner(id) => game_state.are_allies(self.player, *id),
+            GameOutcome::TeamWinner(team) => game_state
+                .teams()
+                .team_of(self.player)
+                .is_some_and(|t| t.id() == *team),
+            GameOutcome::Draw(_) => return 0.0,
+        };
+        let score = WIN_SCORE + depth as f32;
+        if won {
+            score
+        } else {
+            -score
+        }
+    }
+
+    /// Returns the state reached when current player places an atom
+    fn play(
+        &self,
+        game_state: &Arc<ClassicGameState>,
+        position: Position,
+    ) -> Arc<ClassicGameState> {
+        let player = game_state.current_player();
+        let action = ClassicAction::place_atom(player, game_state.current_turn(), position);
+        self.rules
+            .execute(Arc::clone(game_state), Arc::new(action))
+            .0
+    }
+}
+
+/// Evaluate a state reached by `player` from `before`, with provided weights
+fn evaluate(
+    rules: &ClassicRules,
+    before: &ClassicGameState,
+    after: &ClassicGameState,
+    player: PlayerId,
+    weights: &EvaluatorWeights,
+) -> f32 {
+    let terrain = after.terrain();
+    let opponent_atoms = |game_state: &ClassicGameState| -> usize {
+        game_state
+            .players()
+            .iter()
+            .filter(|p| !after.are_allies(player, p.id()))
+            .map(|p| game_state.player_atoms(p.id()))
+            .sum()
+    };
+
+    let mut exposed = 0.0;
+    let mut corners = 0.0;
+    for (position, cell) in after.occupied_cells() {
+        if cell.player_occupation(player).is_none() {
+            continue;
+        }
+        let critical_mass = rules.critical_mass(&terrain, &position);
+        corners += 1.0 / f32::from(critical_mass.max(1));
+        let threatened = rules.neighbours(&terrain, &position).iter().any(|n| {
+            matches!(after.cell_state(n), Some(ClassicCellState::Occupied(owner, occupation))
+                if !after.are_allies(player, owner)
+                    && occupation + 1 >= rules.critical_mass(&terrain, n))
+        });
+        if threatened {
+            exposed += 1.0;
+        }
+    }
+    let removed = opponent_atoms(before) as f32 - opponent_atoms(after) as f32;
+
+    weights.material() * after.player_atoms(player) as f32
+        + weights.territory() * after.player_cells(player) as f32
+        + weights.aggression() * removed
+        - weights.safety() * exposed
+        + weights.corner_preference() * corners
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        ai::{
+            profile::{AiProfile, Difficulty, Personality},
+            tournament::{Pairing, Tournament},
+        },
+        generic_game::{action::Action, actor::Actor},
+        geometry::volume::Volume,
+        test_utilities::game::ground_terrain,
+    };
+
+    /// Create a 3x3 board
+    fn terrain() -> Arc<Terrain> {
+        let vol = Volume::new(&Position::zero(), &Position::new(2, 2, 0)).unwrap();
+        Arc::new(ground_terrain(&vol))
+    }
+
+    #[test]
+    /// Check if real games are played to the end and can be reproduced with the same seed
+    fn play_test() {
+        let easy = BotConfig::new(
+            "easy",
+            AiProfile::new(Difficulty::Easy, Personality::Balanced),
+        );
+        let hard = BotConfig::new(
+            "hard",
+            AiProfile::new(Difficulty::Hard, Personality::Aggressive),
+        );
+        let mut runner = ClassicRunner::default();
+        assert_eq!(runner.config().max_turns(), Some(DEFAULT_MAX_TURNS));
+
+        for seed in 0..4 {
+            let outcome = runner.play(&[&easy, &hard], &terrain(), seed);
+            assert!(matches!(outcome, MatchOutcome::Winner(_)));
+            assert_eq!(runner.play(&[&easy, &hard], &terrain(), seed), outcome);
+        }
+    }
+
+    #[test]
+    /// Check if the search finds a winning move within the time budget
+    fn choose_test() {
+        let rules = ClassicRules::default();
+        let players = [
+            PlayerInfo::new("a", PlayerRage::Red, false),
+            PlayerInfo::new("b", PlayerRage::Green, false),
+        ];
+        let mut controller =
+            ClassicController::new(rules, ClassicGameState::new(terrain(), &players).unwrap());
+        for position in &[Position::zero(), Position::new(1, 0, 0)] {
+            let game_state = controller.game_state();
+            let action = ClassicAction::place_atom(
+                game_state.current_player(),
+                game_state.current_turn(),
+                *position,
+            );
+            assert!(controller.play(action).is_some());
+        }
+
+        // Corner explodes capturing the only opponent cell
+        let profile = AiProfile::new(Difficulty::Insane, Personality::Defensive);
+        let start = Instant::now();
+        let mut rng = StdRng::seed_from_u64(0);
+        let action = choose(
+            controller.rules(),
+            &controller.game_state(),
+            &profile,
+            &mut rng,
+        );
+        assert!(start.elapsed() < profile.time_budget());
+        let action = action.unwrap();
+        assert_eq!(action.source(), Actor::Player(players[0].id()));
+        assert!(controller.play(action).is_some());
+        assert_eq!(controller.game_state().player_atoms(players[1].id()), 0);
+    }
+
+    #[test]
+    /// Check if a tournament is run with real games
+    fn tournament_test() {
+        let bots: Vec<_> = [Personality::Balanced, Personality::Aggressive]
+            .iter()
+            .map(|p| BotConfig::new(&format!("{:?}", p), AiProfile::new(Difficulty::Normal, *p)))
+            .collect();
+        let t = Tournament::new(bots, vec![terrain()], vec![1, 2], Pairing::RoundRobin).unwrap();
+        let report = t.run(&mut ClassicRunner::default());
+
+        assert_eq!(report.matches().len(), 4);
+        assert!(report.matches().iter().all(|m| m.winner().is_some()));
+        assert!(report.standings().iter().all(|s| s.played() == 4));
+    }
+}
@@ -0,0 +1,241 @@
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{common::serializable::Serializable, generic_game::transposition_table::Depth};
+
+/// Strength of a CPU player
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub enum Difficulty {
+    Easy,
+    Normal,
+    Hard,
+    Insane,
+}
+
+/// Play style of a CPU player
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub enum Personality {
+    /// No particular preference
+    Balanced,
+    /// Looks for captures and chain reactions
+    Aggressive,
+    /// Avoids leaving its cells exposed to enemy explosions
+    Defensive,
+    /// Grows slowly from safe cells with low critical mass (corners, edges)
+    Turtling,
+}
+
+/// Weights used by the evaluator to score a game state from the point of view of a player
+#[derive(Clone, Copy, Debug, Serialize, PartialEq)]
+pub struct EvaluatorWeights {
+    /// Weight of the atoms owned by the player
+    material: f32,
+    /// Weight of the cells owned by the player
+    territory: f32,
+    /// Weight of the atoms removed to opponents
+    aggression: f32,
+    /// Weight (penalty) of owned cells that can be captured by an adjacent enemy explosion
+    safety: f32,
+    /// Weight of owned cells with low critical mass
+    corner_preference: f32,
+}
+
+impl EvaluatorWeights {
+    /// Create a new set of weights
+    pub fn new(
+        material: f32,
+        territory: f32,
+        aggression: f32,
+        safety: f32,
+        corner_preference: f32,
+    ) -> Self {
+        Self {
+            material,
+            territory,
+            aggression,
+            safety,
+            corner_preference,
+        }
+    }
+
+    /// Returns weight of the atoms owned by the player
+    pub fn material(&self) -> f32 {
+        self.material
+    }
+
+    /// Returns weight of the cells owned by the player
+    pub fn territory(&self) -> f32 {
+        self.territory
+    }
+
+    /// Returns weight of the atoms removed to opponents
+    pub fn aggression(&self) -> f32 {
+        self.aggression
+    }
+
+    /// Returns weight (penalty) of owned cells that can be captured by an adjacent enemy explosion
+    pub fn safety(&self) -> f32 {
+        self.safety
+    }
+
+    /// Returns weight of owned cells with low critical mass
+    pub fn corner_preference(&self) -> f32 {
+        self.corner_preference
+    }
+}
+
+/// Configuration of a CPU player.
+///
+/// Difficulty defines how deep and how long the player searches and how often
+/// it deliberately plays a bad move, personality defines the evaluator weights.
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub struct AiProfile {
+    difficulty: Difficulty,
+    personality: Personality,
+}
+
+impl Default for AiProfile {
+    /// Normal and balanced CPU player
+    fn default() -> Self {
+        Self::new(Difficulty::Normal, Personality::Balanced)
+    }
+}
+
+impl AiProfile {
+    /// Create a new profile
+    pub fn new(difficulty: Difficulty, personality: Personality) -> Self {
+        Self {
+            difficulty,
+            personality,
+        }
+    }
+
+    /// Returns difficulty of this profile
+    pub fn difficulty(&self) -> Difficulty {
+        self.difficulty
+    }
+
+    /// Returns personality of this profile
+    pub fn personality(&self) -> Personality {
+        self.personality
+    }
+
+    /// Returns max depth (in turns) of the search
+    pub fn search_depth(&self) -> Depth {
+        match self.difficulty {
+            Difficulty::Easy => 1,
+            Difficulty::Normal => 2,
+            Difficulty::Hard => 3,
+            Difficulty::Insane => 5,
+        }
+    }
+
+    /// Returns max time that the search can spend for a move
+    pub fn time_budget(&self) -> Duration {
+        match self.difficulty {
+            Difficulty::Easy => Duration::from_millis(250),
+            Difficulty::Normal => Duration::from_secs(1),
+            Difficulty::Hard => Duration::from_secs(3),
+            Difficulty::Insane => Duration::from_secs(10),
+        }
+    }
+
+    /// Returns probability (in range [0, 1]) of playing a random move instead of the best one
+    pub fn blunder_probability(&self) -> f32 {
+        match self.difficulty {
+            Difficulty::Easy => 0.25,
+            Difficulty::Normal => 0.1,
+            Difficulty::Hard => 0.02,
+            Difficulty::Insane => 0.0,
+        }
+    }
+
+    /// Returns the weights used by the evaluator
+    pub fn evaluator_weights(&self) -> EvaluatorWeights {
+        match self.personality {
+            Personality::Balanced => EvaluatorWeights::new(1.0, 1.0, 1.0, 1.0, 0.5),
+            Personality::Aggressive => EvaluatorWeights::new(0.5, 1.0, 3.0, 0.25, 0.0),
+            Personality::Defensive => EvaluatorWeights::new(1.0, 1.0, 0.5, 3.0, 0.5),
+            Personality::Turtling => EvaluatorWeights::new(1.5, 0.5, 0.25, 2.0, 2.0),
+        }
+    }
+}
+
+impl Serializable for AiProfile {
+    type Data = Self;
+
+    /// Return data to be serialized (itself)
+    fn data_to_be_serialized(&self) -> &Self::Data {
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DIFFICULTIES: [Difficulty; 4] = [
+        Difficulty::Easy,
+        Difficulty::Normal,
+        Difficulty::Hard,
+        Difficulty::Insane,
+    ];
+
+    const PERSONALITIES: [Personality; 4] = [
+        Personality::Balanced,
+        Personality::Aggressive,
+        Personality::Defensive,
+        Personality::Turtling,
+    ];
+
+    #[test]
+    /// Check if harder difficulties search deeper and longer, and blunder less
+    fn difficulty_test() {
+        for pair in DIFFICULTIES.windows(2) {
+            let easier = AiProfile::new(pair[0], Personality::Balanced);
+            let harder = AiProfile::new(pair[1], Personality::Balanced);
+
+            assert!(easier.search_depth() < harder.search_depth());
+            assert!(easier.time_budget() < harder.time_budget());
+            assert!(easier.blunder_probability() > harder.blunder_probability());
+        }
+
+        for difficulty in &DIFFICULTIES {
+            let p = AiProfile::new(*difficulty, Personality::Balanced).blunder_probability();
+            assert!((0.0..=1.0).contains(&p));
+        }
+    }
+
+    #[test]
+    /// Check if personalities affects only evaluator weights
+    fn personality_test() {
+        for difficulty in &DIFFICULTIES {
+            let balanced = AiProfile::new(*difficulty, Personality::Balanced);
+            for personality in &PERSONALITIES {
+                let profile = AiProfile::new(*difficulty, *personality);
+                assert_eq!(profile.difficulty(), *difficulty);
+                assert_eq!(profile.personality(), *personality);
+                assert_eq!(profile.search_depth(), balanced.search_depth());
+                assert_eq!(profile.time_budget(), balanced.time_budget());
+            }
+        }
+
+        let balanced = AiProfile::default().evaluator_weights();
+        let aggressive = AiProfile::new(Difficulty::Normal, Personality::Aggressive);
+        let defensive = AiProfile::new(Difficulty::Normal, Personality::Defensive);
+        let turtling = AiProfile::new(Difficulty::Normal, Personality::Turtling);
+        assert!(aggressive.evaluator_weights().aggression() > balanced.aggression());
+        assert!(defensive.evaluator_weights().safety() > balanced.safety());
+        assert!(turtling.evaluator_weights().corner_preference() > balanced.corner_preference());
+    }
+
+    #[test]
+    /// Check if profile can be serialized
+    fn serialize_test() {
+        let profile = AiProfile::new(Difficulty::Insane, Personality::Turtling);
+        let serialized = serde_json::to_string(&profile).expect("Cannot serialize");
+        assert!(serialized.contains("Insane"));
+        assert!(serialized.contains("Turtling"));
+    }
+}
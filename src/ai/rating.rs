@@ -0,0 +1,200 @@
+use std::f64::consts::{LN_10, PI};
+
+use serde::Serialize;
+
+/// Type used for rating points
+pub type Rating = f64;
+
+/// Rating assigned to a new player
+pub const INITIAL_RATING: Rating = 1500.0;
+
+/// Rating deviation assigned to a new player (Glicko)
+pub const INITIAL_DEVIATION: Rating = 350.0;
+
+/// Elo K-factor (max points exchanged in a single game)
+pub const ELO_K_FACTOR: Rating = 32.0;
+
+/// Z value for a 95% confidence interval
+const CONFIDENCE_Z: Rating = 1.96;
+
+/// Score of a game from the point of view of a player
+#[derive(Clone, Copy, Debug, Serialize, PartialEq)]
+pub enum GameScore {
+    Win,
+    Draw,
+    Loss,
+}
+
+impl GameScore {
+    /// Returns numeric value of the score (1 win, 0.5 draw, 0 loss)
+    pub fn value(&self) -> Rating {
+        match self {
+            GameScore::Win => 1.0,
+            GameScore::Draw => 0.5,
+            GameScore::Loss => 0.0,
+        }
+    }
+
+    /// Returns the score of the opponent
+    pub fn opposite(&self) -> Self {
+        match self {
+            GameScore::Win => GameScore::Loss,
+            GameScore::Draw => GameScore::Draw,
+            GameScore::Loss => GameScore::Win,
+        }
+    }
+}
+
+/// Expected score of a player rated `rating` against a player rated `opponent` (Elo)
+pub fn elo_expected_score(rating: Rating, opponent: Rating) -> Rating {
+    1.0 / (1.0 + 10f64.powf((opponent - rating) / 400.0))
+}
+
+/// Returns the new Elo ratings of two players after a game
+///
+/// * `score` - Score of the first player
+pub fn elo_update(rating: Rating, opponent: Rating, score: GameScore) -> (Rating, Rating) {
+    let expected = elo_expected_score(rating, opponent);
+    let delta = ELO_K_FACTOR * (score.value() - expected);
+
+    (rating + delta, opponent - delta)
+}
+
+/// Rating and rating deviation of a player according to Glicko system
+#[derive(Clone, Copy, Debug, Serialize, PartialEq)]
+pub struct GlickoRating {
+    rating: Rating,
+    deviation: Rating,
+}
+
+impl Default for GlickoRating {
+    /// Rating of a new player
+    fn default() -> Self {
+        Self::new(INITIAL_RATING, INITIAL_DEVIATION)
+    }
+}
+
+/// Glicko constant q
+const Q: Rating = LN_10 / 400.0;
+
+/// Glicko function g(RD)
+fn g(deviation: Rating) -> Rating {
+    1.0 / (1.0 + 3.0 * Q * Q * deviation * deviation / (PI * PI)).sqrt()
+}
+
+impl GlickoRating {
+    /// Create a new rating
+    pub fn new(rating: Rating, deviation: Rating) -> Self {
+        Self { rating, deviation }
+    }
+
+    /// Returns rating
+    pub fn rating(&self) -> Rating {
+        self.rating
+    }
+
+    /// Returns rating deviation
+    pub fn deviation(&self) -> Rating {
+        self.deviation
+    }
+
+    /// Returns the 95% confidence interval (lower, upper) of the rating
+    pub fn confidence_interval(&self) -> (Rating, Rating) {
+        (
+            self.rating - CONFIDENCE_Z * self.deviation,
+            self.rating + CONFIDENCE_Z * self.deviation,
+        )
+    }
+
+    /// Returns the rating after a rating period where the player has played the
+    /// provided games (opponent rating at the beginning of the period and score).
+    ///
+    /// If no game has been played, rating does not change.
+    pub fn update(&self, games: &[(GlickoRating, GameScore)]) -> Self {
+        if games.is_empty() {
+            return *self;
+        }
+
+        let mut d_inv = 0.0;
+        let mut sum = 0.0;
+        for (opponent, score) in games {
+            let g_rd = g(opponent.deviation);
+            let expected =
+                1.0 / (1.0 + 10f64.powf(-g_rd * (self.rating - opponent.rating) / 400.0));
+            d_inv += Q * Q * g_rd * g_rd * expected * (1.0 - expected);
+            sum += g_rd * (score.value() - expected);
+        }
+
+        let precision = 1.0 / (self.deviation * self.deviation) + d_inv;
+        Self {
+            rating: self.rating + Q / precision * sum,
+            deviation: (1.0 / precision).sqrt(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utilities::{
+        constants::NUMBER_OF_LOOPS_FOR_NORMAL_TEST, random_generator::random_number,
+    };
+
+    #[test]
+    /// Check if Elo exchange is zero-sum and favours the underdog
+    fn elo_test() {
+        for _ in 0..NUMBER_OF_LOOPS_FOR_NORMAL_TEST {
+            let r0 = random_number(800.0, 2400.0);
+            let r1 = random_number(800.0, 2400.0);
+
+            let e0 = elo_expected_score(r0, r1);
+            let e1 = elo_expected_score(r1, r0);
+            assert!((e0 + e1 - 1.0).abs() < 1e-9);
+
+            for score in &[GameScore::Win, GameScore::Draw, GameScore::Loss] {
+                let (n0, n1) = elo_update(r0, r1, *score);
+                assert!((n0 + n1 - r0 - r1).abs() < 1e-9);
+                assert!((n0 - r0).abs() <= ELO_K_FACTOR);
+            }
+        }
+
+        // Equal players: win gives K/2 points
+        let (n0, n1) = elo_update(INITIAL_RATING, INITIAL_RATING, GameScore::Win);
+        assert!((n0 - INITIAL_RATING - ELO_K_FACTOR / 2.0).abs() < 1e-9);
+        assert!((INITIAL_RATING - n1 - ELO_K_FACTOR / 2.0).abs() < 1e-9);
+    }
+
+    #[test]
+    /// Check Glicko update against the example of Glickman's paper
+    fn glicko_test() {
+        let player = GlickoRating::new(1500.0, 200.0);
+        let games = [
+            (GlickoRating::new(1400.0, 30.0), GameScore::Win),
+            (GlickoRating::new(1550.0, 100.0), GameScore::Loss),
+            (GlickoRating::new(1700.0, 300.0), GameScore::Loss),
+        ];
+        let updated = player.update(&games);
+
+        assert!((updated.rating() - 1464.0).abs() < 1.0, "{:?}", updated);
+        assert!((updated.deviation() - 151.4).abs() < 1.0, "{:?}", updated);
+
+        // No games, no changes
+        assert_eq!(player.update(&[]), player);
+    }
+
+    #[test]
+    /// Check if confidence interval shrinks with games played
+    fn confidence_interval_test() {
+        let mut player = GlickoRating::default();
+        let opponent = GlickoRating::default();
+        let mut width = Rating::MAX;
+
+        for _ in 0..10 {
+            player = player.update(&[(opponent, GameScore::Draw)]);
+            let (low, high) = player.confidence_interval();
+            assert!(low < player.rating() && player.rating() < high);
+            assert!(high - low < width);
+            width = high - low;
+        }
+    }
+}
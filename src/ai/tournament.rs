@@ -0,0 +1,630 @@
+use std::{
+    io::{self, Write},
+    sync::Arc,
+};
+
+use log::warn;
+use serde::Serialize;
+
+use crate::geometry::terrain::Terrain;
+
+use super::{
+    profile::AiProfile,
+    rating::{elo_update, GameScore, GlickoRating, Rating, INITIAL_RATING},
+};
+
+/// Type used for the seed of random generators of a match
+pub type Seed = u64;
+
+/// Configuration of a bot taking part to a tournament
+#[derive(Clone, Debug, Serialize, PartialEq)]
+pub struct BotConfig {
+    /// Unique name of the bot (e.g. version of the AI)
+    name: String,
+    /// Profile used by the bot
+    profile: AiProfile,
+}
+
+impl BotConfig {
+    /// Create a new bot configuration
+    pub fn new(name: &str, profile: AiProfile) -> Self {
+        Self {
+            name: String::from(name),
+            profile,
+        }
+    }
+
+    /// Returns name of the bot
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns profile used by the bot
+    pub fn profile(&self) -> AiProfile {
+        self.profile
+    }
+}
+
+/// How bots are paired
+#[derive(Clone, Copy, Debug, Serialize, PartialEq, Eq)]
+pub enum Pairing {
+    /// Every bot meets every other bot once
+    RoundRobin,
+    /// Given number of rounds, bots with similar points meet each other
+    Swiss(usize),
+}
+
+/// Outcome of a single match
+#[derive(Clone, Copy, Debug, Serialize, PartialEq, Eq)]
+pub enum MatchOutcome {
+    /// Index of the winning seat
+    Winner(usize),
+    /// Nobody won
+    Draw,
+}
+
+/// Plays a single headless game between bots.
+///
+/// Implemented by the driver of the game controller (e.g. `ClassicRunner`), so the
+/// tournament does not depend on a particular game.
+pub trait MatchRunner {
+    /// Play a game on the provided terrain and returns its outcome.
+    ///
+    /// * `seats` - Bots playing the game, in turn order
+    /// * `seed` - Seed for all random generators of the game (match must be reproducible)
+    fn play(&mut self, seats: &[&BotConfig], terrain: &Arc<Terrain>, seed: Seed) -> MatchOutcome;
+}
+
+/// Result of a match played in a tournament
+#[derive(Clone, Debug, Serialize, PartialEq)]
+pub struct MatchRecord {
+    /// Round of the tournament (starting from 0)
+    round: usize,
+    /// Index of the terrain used
+    terrain: usize,
+    /// Seed used
+    seed: Seed,
+    /// Name of the bots, in seat order
+    seats: Vec<String>,
+    /// Name of the winner (`None` in case of draw)
+    winner: Option<String>,
+}
+
+impl MatchRecord {
+    /// Returns round of the tournament (starting from 0)
+    pub fn round(&self) -> usize {
+        self.round
+    }
+
+    /// Returns index of the terrain used
+    pub fn terrain(&self) -> usize {
+        self.terrain
+    }
+
+    /// Returns seed used
+    pub fn seed(&self) -> Seed {
+        self.seed
+    }
+
+    /// Returns name of the bots, in seat order
+    pub fn seats(&self) -> &[String] {
+        &self.seats
+    }
+
+    /// Returns name of the winner (`None` in case of draw)
+    pub fn winner(&self) -> Option<&str> {
+        self.winner.as_deref()
+    }
+}
+
+/// Final result of a bot in a tournament
+#[derive(Clone, Debug, Serialize, PartialEq)]
+pub struct Standing {
+    bot: String,
+    played: usize,
+    wins: usize,
+    draws: usize,
+    losses: usize,
+    /// Elo rating (updated after every match)
+    elo: Rating,
+    /// Glicko rating (updated after every round)
+    glicko: GlickoRating,
+}
+
+impl Standing {
+    /// Create the standing of a bot that has not played yet
+    fn new(bot: &str) -> Self {
+        Self {
+            bot: String::from(bot),
+            played: 0,
+            wins: 0,
+            draws: 0,
+            losses: 0,
+            elo: INITIAL_RATING,
+            glicko: GlickoRating::default(),
+        }
+    }
+
+    /// Returns name of the bot
+    pub fn bot(&self) -> &str {
+        &self.bot
+    }
+
+    /// Returns number of matches played
+    pub fn played(&self) -> usize {
+        self.played
+    }
+
+    /// Returns number of matches won
+    pub fn wins(&self) -> usize {
+        self.wins
+    }
+
+    /// Returns number of matches drawn
+    pub fn draws(&self) -> usize {
+        self.draws
+    }
+
+    /// Returns number of matches lost
+    pub fn losses(&self) -> usize {
+        self.losses
+    }
+
+    /// Returns tournament points (1 for win, 0.5 for draw)
+    pub fn points(&self) -> Rating {
+        self.wins as Rating + self.draws as Rating * 0.5
+    }
+
+    /// Returns Elo rating
+    pub fn elo(&self) -> Rating {
+        self.elo
+    }
+
+    /// Returns Glicko rating (with its confidence interval)
+    pub fn glicko(&self) -> GlickoRating {
+        self.glicko
+    }
+
+    /// Update counters with the score of a match
+    fn record(&mut self, score: GameScore) {
+        self.played += 1;
+        match score {
+            GameScore::Win => self.wins += 1,
+            GameScore::Draw => self.draws += 1,
+            GameScore::Loss => self.losses += 1,
+        }
+    }
+}
+
+/// Report of a tournament
+#[derive(Clone, Debug, Serialize, PartialEq)]
+pub struct TournamentReport {
+    /// All matches played, in order
+    matches: Vec<MatchRecord>,
+    /// Standings sorted by Glicko rating (best first)
+    standings: Vec<Standing>,
+}
+
+impl TournamentReport {
+    /// Returns all matches played, in order
+    pub fn matches(&self) -> &[MatchRecord] {
+        &self.matches
+    }
+
+    /// Returns standings sorted by Glicko rating (best first)
+    pub fn standings(&self) -> &[Standing] {
+        &self.standings
+    }
+
+    /// Write standings as CSV (one line per bot, with header), e.g. to a file
+    pub fn write_csv<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        writeln!(
+            writer,
+            "rank,bot,played,wins,draws,losses,points,elo,glicko,glicko_rd,ci_low,ci_high"
+        )?;
+        for (rank, s) in self.standings.iter().enumerate() {
+            let (low, high) = s.glicko.confidence_interval();
+            writeln!(
+                writer,
+                "{},{},{},{},{},{},{},{:.1},{:.1},{:.1},{:.1},{:.1}",
+                rank + 1,
+                csv_field(&s.bot),
+                s.played,
+                s.wins,
+                s.draws,
+                s.losses,
+                s.points(),
+                s.elo,
+                s.glicko.rating(),
+                s.glicko.deviation(),
+                low,
+                high
+            )?;
+        }
+        writer.flush()
+    }
+
+    /// Returns standings as CSV (one line per bot, with header)
+    pub fn to_csv(&self) -> String {
+        let mut csv = vec![];
+        self.write_csv(&mut csv)
+            .expect("Writing to a vector cannot fail");
+        String::from_utf8(csv).expect("CSV shall be valid UTF-8")
+    }
+
+    /// Write the whole report (matches and standings) as JSON, e.g. to a file
+    pub fn write_json<W: Write>(&self, writer: &mut W) -> serde_json::Result<()> {
+        serde_json::to_writer_pretty(writer, self)
+    }
+
+    /// Returns the whole report (matches and standings) as JSON
+    pub fn to_json(&self) -> serde_json::Result<String> {
+        serde_json::to_string_pretty(self)
+    }
+}
+
+/// Quote a CSV field if needed
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        String::from(value)
+    }
+}
+
+/// Tournament between bot configurations.
+///
+/// Two bots _meet_ playing a match for each terrain and seed, twice:
+/// the second time with swapped seats, so no bot takes advantage of playing first.
+#[derive(Clone, Debug)]
+pub struct Tournament {
+    bots: Vec<BotConfig>,
+    terrains: Vec<Arc<Terrain>>,
+    seeds: Vec<Seed>,
+    pairing: Pairing,
+}
+
+impl Tournament {
+    /// Create a new tournament.
+    ///
+    /// Returns `None` if there are less than 2 bots, bot names are not unique,
+    /// or there are no terrains or seeds.
+    pub fn new(
+        bots: Vec<BotConfig>,
+        terrains: Vec<Arc<Terrain>>,
+        seeds: Vec<Seed>,
+        pairing: Pairing,
+    ) -> Option<Self> {
+        let unique_names = bots
+            .iter()
+            .enumerate()
+            .all(|(i, b)| bots[..i].iter().all(|o| o.name != b.name));
+
+        if bots.len() < 2 || !unique_names || terrains.is_empty() || seeds.is_empty() {
+            warn!("Invalid tournament configuration");
+            None
+        } else {
+            Some(Self {
+                bots,
+                terrains,
+                seeds,
+                pairing,
+            })
+        }
+    }
+
+    /// Returns bots taking part to the tournament
+    pub fn bots(&self) -> &[BotConfig] {
+        &self.bots
+    }
+
+    /// Play all matches of the tournament using provided runner
+    pub fn run<R: MatchRunner>(&self, runner: &mut R) -> TournamentReport {
+        let mut standings: Vec<_> = self.bots.iter().map(|b| Standing::new(&b.name)).collect();
+        let mut met = vec![vec![false; self.bots.len()]; self.bots.len()];
+        let mut had_bye = vec![false; self.bots.len()];
+        let mut matches = vec![];
+
+        let rounds = match self.pairing {
+            Pairing::RoundRobin => 1,
+            Pairing::Swiss(rounds) => rounds,
+        };
+
+        for round in 0..rounds {
+            let pairs = match self.pairing {
+                Pairing::RoundRobin => self.round_robin_pairs(),
+                Pairing::Swiss(_) => Self::swiss_pairs(&standings, &met, &had_bye),
+            };
+            for (bot, bye) in had_bye.iter_mut().enumerate() {
+                *bye |= pairs.iter().all(|(a, b)| *a != bot && *b != bot);
+            }
+
+            // Games of the round for each bot, used for Glicko rating period
+            let mut round_games = vec![vec![]; self.bots.len()];
+            let start_of_round: Vec<_> = standings.iter().map(|s| s.glicko).collect();
+
+            for (a, b) in pairs {
+                met[a][b] = true;
+                met[b][a] = true;
+                for (terrain_index, terrain) in self.terrains.iter().enumerate() {
+                    for seed in &self.seeds {
+                        for &(first, second) in &[(a, b), (b, a)] {
+                            let seats = [&self.bots[first], &self.bots[second]];
+                            let outcome = runner.play(&seats, terrain, *seed);
+                            let score = match outcome {
+                                MatchOutcome::Winner(0) => GameScore::Win,
+                                MatchOutcome::Winner(_) => GameScore::Loss,
+                                MatchOutcome::Draw => GameScore::Draw,
+                            };
+
+                            let (elo_first, elo_second) =
+                                elo_update(standings[first].elo, standings[second].elo, score);
+                            standings[first].elo = elo_first;
+                            standings[second].elo = elo_second;
+                            standings[first].record(score);
+                            standings[second].record(score.opposite());
+                            round_games[first].push((start_of_round[second], score));
+                            round_games[second].push((start_of_round[first], score.opposite()));
+
+                            matches.push(MatchRecord {
+                                round,
+                                terrain: terrain_index,
+                                seed: *seed,
+                                seats: seats.iter().map(|b| b.name.clone()).collect(),
+                                winner: match outcome {
+                                    MatchOutcome::Winner(seat) => {
+                                        seats.get(seat).map(|b| b.name.clone())
+                                    }
+                                    MatchOutcome::Draw => None,
+                                },
+                            });
+                        }
+                    }
+                }
+            }
+
+            for (standing, games) in standings.iter_mut().zip(round_games.iter()) {
+                standing.glicko = standing.glicko.update(games);
+            }
+        }
+
+        standings.sort_by(|a, b| {
+            b.glicko
+                .rating()
+                .partial_cmp(&a.glicko.rating())
+                .unwrap_or(std::cmp::Ordering::Equal)
+        });
+
+        TournamentReport { matches, standings }
+    }
+
+    /// Returns all pairs of bots
+    fn round_robin_pairs(&self) -> Vec<(usize, usize)> {
+        let n = self.bots.len();
+        (0..n)
+            .flat_map(|a| (a + 1..n).map(move |b| (a, b)))
+            .collect()
+    }
+
+    /// Pair bots with similar points, avoiding rematches when possible.
+    ///
+    /// With an odd number of bots, the lowest ranked bot that allows a pairing without
+    /// rematches does not play this round (bots that already skipped a round are the last choice).
+    fn swiss_pairs(
+        standings: &[Standing],
+        met: &[Vec<bool>],
+        had_bye: &[bool],
+    ) -> Vec<(usize, usize)> {
+        let mut order: Vec<_> = (0..standings.len()).collect();
+        // Stable sort: with same points, bots keep registration order
+        order.sort_by(|a, b| {
+            standings[*b]
+                .points()
+                .partial_cmp(&standings[*a].points())
+                .unwrap_or(std::cmp::Ordering::Equal)
+        });
+
+        // Candidates for the bye (from the lowest ranked)
+        let byes: Vec<Option<usize>> = if order.len() % 2 == 1 {
+            let mut candidates: Vec<_> = (0..order.len()).rev().collect();
+            // Stable sort: bots without bye first
+            candidates.sort_by_key(|i| had_bye[order[*i]]);
+            candidates.into_iter().map(Some).collect()
+        } else {
+            vec![None]
+        };
+
+        for bye in &byes {
+            let mut playing = order.clone();
+            if let Some(index) = bye {
+                playing.remove(*index);
+            }
+            if let Some(pairs) = Self::pairs_without_rematch(&playing, met) {
+                return pairs;
+            }
+        }
+
+        // Rematches cannot be avoided: pair adjacent bots
+        if let Some(Some(index)) = byes.first() {
+            order.remove(*index);
+        }
+        order.chunks_exact(2).map(|c| (c[0], c[1])).collect()
+    }
+
+    /// Pair bots (even number) in order, backtracking to avoid rematches.
+    ///
+    /// `None` if rematches cannot be avoided.
+    fn pairs_without_rematch(order: &[usize], met: &[Vec<bool>]) -> Option<Vec<(usize, usize)>> {
+        if order.is_empty() {
+            return Some(vec![]);
+        }
+
+        let a = order[0];
+        for (i, b) in order.iter().enumerate().skip(1) {
+            if met[a][*b] {
+                continue;
+            }
+            let rest: Vec<_> = order[1..]
+                .iter()
+                .enumerate()
+                .filter(|(j, _)| j + 1 != i)
+                .map(|(_, c)| *c)
+                .collect();
+            if let Some(mut pairs) = Self::pairs_without_rematch(&rest, met) {
+                pairs.insert(0, (a, *b));
+                return Some(pairs);
+            }
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        ai::profile::{Difficulty, Personality},
+        geometry::{vector::Position, volume::Volume},
+    };
+
+    /// Runner where the bot with lower index in the list always wins, and
+    /// same bots always draw
+    struct RankRunner {
+        ranking: Vec<String>,
+        played: Vec<(Vec<String>, Seed)>,
+    }
+
+    impl MatchRunner for RankRunner {
+        fn play(&mut self, seats: &[&BotConfig], _: &Arc<Terrain>, seed: Seed) -> MatchOutcome {
+            self.played
+                .push((seats.iter().map(|b| b.name.clone()).collect(), seed));
+            let rank = |b: &BotConfig| self.ranking.iter().position(|n| *n == b.name);
+            let r0 = rank(seats[0]);
+            let r1 = rank(seats[1]);
+            if r0 == r1 {
+                MatchOutcome::Draw
+            } else if r0 < r1 {
+                MatchOutcome::Winner(0)
+            } else {
+                MatchOutcome::Winner(1)
+            }
+        }
+    }
+
+    fn bots(n: usize) -> Vec<BotConfig> {
+        (0..n)
+            .map(|i| {
+                BotConfig::new(
+                    &format!("bot{}", i),
+                    AiProfile::new(Difficulty::Normal, Personality::Balanced),
+                )
+            })
+            .collect()
+    }
+
+    fn terrains(n: usize) -> Vec<Arc<Terrain>> {
+        let vol = Volume::new(&Position::zero(), &Position::new(3, 3, 3)).unwrap();
+        (0..n).map(|_| Arc::new(Terrain::new(&vol))).collect()
+    }
+
+    fn runner(n: usize) -> RankRunner {
+        RankRunner {
+            ranking: (0..n).map(|i| format!("bot{}", i)).collect(),
+            played: vec![],
+        }
+    }
+
+    #[test]
+    /// Check if invalid configurations are rejected
+    fn new_test() {
+        assert!(Tournament::new(bots(1), terrains(1), vec![0], Pairing::RoundRobin).is_none());
+        assert!(Tournament::new(bots(2), terrains(0), vec![0], Pairing::RoundRobin).is_none());
+        assert!(Tournament::new(bots(2), terrains(1), vec![], Pairing::RoundRobin).is_none());
+        let mut duplicated = bots(2);
+        duplicated.push(duplicated[0].clone());
+        assert!(Tournament::new(duplicated, terrains(1), vec![0], Pairing::RoundRobin).is_none());
+        assert!(Tournament::new(bots(2), terrains(1), vec![0], Pairing::RoundRobin).is_some());
+    }
+
+    #[test]
+    /// Check if round robin plays every pair on every terrain and seed, rotating seats
+    fn round_robin_test() {
+        let n = 4;
+        let seeds = vec![1, 2, 3];
+        let t = Tournament::new(bots(n), terrains(2), seeds.clone(), Pairing::RoundRobin).unwrap();
+        let mut runner = runner(n);
+        let report = t.run(&mut runner);
+
+        let expected = n * (n - 1) / 2 * 2 * seeds.len() * 2;
+        assert_eq!(report.matches().len(), expected);
+        assert_eq!(runner.played.len(), expected);
+
+        // Each bot plays first as many times as it plays second
+        for b in t.bots() {
+            let first = runner.played.iter().filter(|(s, _)| s[0] == b.name).count();
+            let second = runner.played.iter().filter(|(s, _)| s[1] == b.name).count();
+            assert_eq!(first, second);
+        }
+
+        // Ranking follows strength
+        let names: Vec<_> = report.standings().iter().map(|s| s.bot()).collect();
+        assert_eq!(names, vec!["bot0", "bot1", "bot2", "bot3"]);
+        assert_eq!(report.standings()[0].losses(), 0);
+        assert!(report.standings()[0].elo() > report.standings()[3].elo());
+        for s in report.standings() {
+            assert_eq!(s.played(), s.wins() + s.draws() + s.losses());
+            let (low, high) = s.glicko().confidence_interval();
+            assert!(low < s.glicko().rating() && s.glicko().rating() < high);
+        }
+    }
+
+    #[test]
+    /// Check if swiss pairing avoids rematches and handles odd number of bots
+    fn swiss_test() {
+        let n = 5;
+        let rounds = 4;
+        let t = Tournament::new(bots(n), terrains(1), vec![7], Pairing::Swiss(rounds)).unwrap();
+        let mut runner = runner(n);
+        let report = t.run(&mut runner);
+
+        // Each round has 2 pairs, each pair plays 2 matches
+        assert_eq!(report.matches().len(), rounds * 2 * 2);
+
+        let mut pairs = vec![];
+        for m in report.matches().iter().step_by(2) {
+            let mut pair = m.seats().to_vec();
+            pair.sort();
+            assert!(!pairs.contains(&pair), "Rematch {:?}", pair);
+            pairs.push(pair);
+        }
+        // Bye rotates: every bot has played
+        assert!(report.standings().iter().all(|s| s.played() > 0));
+        assert_eq!(report.standings()[0].bot(), "bot0");
+    }
+
+    #[test]
+    /// Check CSV and JSON reports
+    fn report_test() {
+        let mut bots = bots(2);
+        bots.push(BotConfig::new("v2, \"beta\"", AiProfile::default()));
+        let t = Tournament::new(bots, terrains(1), vec![0], Pairing::RoundRobin).unwrap();
+        let report = t.run(&mut runner(2));
+
+        let csv = report.to_csv();
+        assert_eq!(csv.lines().count(), 4);
+        assert!(csv.starts_with("rank,bot,"));
+        assert!(csv.contains("\"v2, \"\"beta\"\"\""));
+
+        let json = report.to_json().expect("Cannot serialize");
+        assert!(json.contains("\"standings\""));
+        assert!(json.contains("\"matches\""));
+
+        // Writers produce the same reports
+        let mut written = vec![];
+        report.write_csv(&mut written).unwrap();
+        assert_eq!(String::from_utf8(written).unwrap(), csv);
+        let mut written = vec![];
+        report.write_json(&mut written).unwrap();
+        assert_eq!(String::from_utf8(written).unwrap(), json);
+    }
+}
@@ -1,2 +1,14 @@
+pub mod action;
+pub mod basic_rules;
 pub mod cell_state;
+pub mod classic_2d;
+pub mod controller;
+pub mod event;
+pub mod game_state;
 pub mod player;
+pub mod rules;
+pub mod rules_config;
+pub mod scoring;
+pub mod statistics;
+pub mod timeline;
+pub mod victory;
@@ -0,0 +1,189 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    common::{clone_arc::Clonable, serializable::Serializable},
+    generic_game::{
+        action::{Action, TurnId},
+        actor::Actor,
+        player::PlayerId,
+    },
+    geometry::vector::Position,
+};
+
+/// What happens in a classic game action
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub enum ClassicActionKind {
+    /// Player adds an atom to a cell
+    PlaceAtom(Position),
+    /// Cell reached its critical mass and spreads its atoms to neighbour cells
+    Explode {
+        position: Position,
+        player: PlayerId,
+    },
+    /// Cell changes owner because of an explosion
+    Capture {
+        position: Position,
+        from: PlayerId,
+        to: PlayerId,
+    },
+    /// Atoms of a cell fall down because the cell below them got empty
+    Fall {
+        from: Position,
+        to: Position,
+        player: PlayerId,
+    },
+    /// Player has no more atoms and cannot play anymore
+    Eliminate(PlayerId),
+    /// Player ran out of time (see `TimeoutPolicy` for the consequences)
+    Timeout(PlayerId),
+}
+
+/// Action of classic atomz game
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub struct ClassicAction {
+    source: Actor,
+    destination: Actor,
+    turn: TurnId,
+    turn_substep: TurnId,
+    kind: ClassicActionKind,
+}
+
+impl ClassicAction {
+    /// Create a new action
+    pub fn new(
+        source: Actor,
+        destination: Actor,
+        turn: TurnId,
+        turn_substep: TurnId,
+        kind: ClassicActionKind,
+    ) -> Self {
+        Self {
+            source,
+            destination,
+            turn,
+            turn_substep,
+            kind,
+        }
+    }
+
+    /// Create the action of a player placing an atom at provided position
+    pub fn place_atom(player: PlayerId, turn: TurnId, position: Position) -> Self {
+        Self::new(
+            Actor::Player(player),
+            Actor::Controller,
+            turn,
+            0,
+            ClassicActionKind::PlaceAtom(position),
+        )
+    }
+
+    /// Create the action of the controller notifying a player ran out of time
+    pub fn timeout(player: PlayerId, turn: TurnId) -> Self {
+        Self::new(
+            Actor::Controller,
+            Actor::Player(player),
+            turn,
+            0,
+            ClassicActionKind::Timeout(player),
+        )
+    }
+
+    /// Create an action generated by the controller as a reaction to another action
+    pub fn reaction(turn: TurnId, turn_substep: TurnId, kind: ClassicActionKind) -> Self {
+        Self::new(Actor::Controller, Actor::UI, turn, turn_substep, kind)
+    }
+
+    /// Returns a copy of this action addressed to `Actor::UI` (e.g. to be shown to spectators)
+    pub fn broadcast(&self) -> Self {
+        Self {
+            destination: Actor::UI,
+            ..*self
+        }
+    }
+
+    /// Returns what happens in this action
+    pub fn kind(&self) -> ClassicActionKind {
+        self.kind
+    }
+}
+
+impl Clonable for ClassicAction {}
+
+impl Serializable for ClassicAction {
+    type Data = Self;
+
+    /// Return data to be serialized (itself)
+    fn data_to_be_serialized(&self) -> &Self::Data {
+        self
+    }
+}
+
+impl Action for ClassicAction {
+    type Data = Self;
+
+    /// Returns source (who has generated) of this action.
+    fn source(&self) -> Actor {
+        self.source
+    }
+
+    /// Returns destination (target) of this action.
+    fn destination(&self) -> Actor {
+        self.destination
+    }
+
+    /// Turn when this action has been created.
+    fn turn(&self) -> TurnId {
+        self.turn
+    }
+
+    /// Substep of turn when thid action has been created.
+    fn turn_substep(&self) -> TurnId {
+        self.turn_substep
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utilities::{
+        constants::NUMBER_OF_LOOPS_FOR_NORMAL_TEST,
+        random_generator::{random_number, random_vector},
+    };
+
+    #[test]
+    /// Check if constructors are not messing up data
+    fn new_test() {
+        for _ in 0..NUMBER_OF_LOOPS_FOR_NORMAL_TEST {
+            let id = random_number(0, usize::MAX);
+            let turn = random_number(0, usize::MAX);
+            let substep = random_number(0, usize::MAX);
+            let pos = random_vector(-100, 100);
+
+            let place = ClassicAction::place_atom(id, turn, pos);
+            assert_eq!(place.source(), Actor::Player(id));
+            assert_eq!(place.destination(), Actor::Controller);
+            assert_eq!(place.turn(), turn);
+            assert_eq!(place.turn_substep(), 0);
+            assert_eq!(place.kind(), ClassicActionKind::PlaceAtom(pos));
+
+            let kind = ClassicActionKind::Explode {
+                position: pos,
+                player: id,
+            };
+            let reaction = ClassicAction::reaction(turn, substep, kind);
+            assert_eq!(reaction.source(), Actor::Controller);
+            assert_eq!(reaction.destination(), Actor::UI);
+            assert_eq!(reaction.turn(), turn);
+            assert_eq!(reaction.turn_substep(), substep);
+            assert_eq!(reaction.kind(), kind);
+        }
+    }
+
+    #[test]
+    /// Check if action can be serialized
+    fn serialize_test() {
+        let action = ClassicAction::place_atom(1, 2, Position::new(3, 4, 5));
+        let serialized = serde_json::to_string(&action).expect("Cannot serialize");
+        assert!(serialized.contains("PlaceAtom"));
+    }
+}
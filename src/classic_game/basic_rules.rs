@@ -0,0 +1,367 @@
+use std::sync::Arc;
+
+use crate::generic_game::{
+    game_rule::{BasicRules, GameOutcome},
+    game_state::GameState,
+    player::{PlayerId, PlayerState},
+};
+
+use super::{
+    game_state::ClassicGameState, player::ClassicPlayerState, rules::ClassicRules,
+    rules_config::ClassicRulesConfig, victory::VictoryCondition,
+};
+
+/// Turn order and end of game criteria of the classic atomz game.
+///
+/// - Players play in turn order, eliminated players are skipped.
+///   A player without atoms is not eliminated until it makes its first move.
+/// - Game ends when one of the `VictoryCondition` is satisfied (checked in order).
+///   By default the game is won by the only player (or team) left with atoms, once every
+///   player has moved at least once.
+/// - Game ends in a draw if no player is left with atoms (tie) or when the turn limit is reached.
+/// - Players are ranked by outcome, then alive ones before eliminated ones, then by score.
+#[derive(Debug, Clone)]
+pub struct ClassicBasicRules {
+    config: ClassicRulesConfig,
+    victory_conditions: Vec<VictoryCondition>,
+}
+
+impl Default for ClassicBasicRules {
+    /// Default configuration, last player standing wins
+    fn default() -> Self {
+        Self::new(ClassicRulesConfig::default())
+    }
+}
+
+impl ClassicBasicRules {
+    /// Create basic rules with provided configuration, last player standing wins
+    pub fn new(config: ClassicRulesConfig) -> Self {
+        Self::with_victory_conditions(config, vec![VictoryCondition::LastStanding])
+    }
+
+    /// Create basic rules with provided configuration and victory conditions
+    /// (checked in order)
+    pub fn with_victory_conditions(
+        config: ClassicRulesConfig,
+        victory_conditions: Vec<VictoryCondition>,
+    ) -> Self {
+        Self {
+            config,
+            victory_conditions,
+        }
+    }
+
+    /// Returns victory conditions (checked in order)
+    pub fn victory_conditions(&self) -> &[VictoryCondition] {
+        &self.victory_conditions
+    }
+
+    /// Returns the list of players of a game state, as expected by `next_player`
+    pub fn players_of(
+        game_state: &ClassicGameState,
+    ) -> Vec<Arc<dyn PlayerState<Data = ClassicPlayerState>>> {
+        game_state
+            .players()
+            .iter()
+            .map(|p| Arc::new(*p) as Arc<dyn PlayerState<Data = ClassicPlayerState>>)
+            .collect()
+    }
+}
+
+impl BasicRules for ClassicBasicRules {
+    type GS = ClassicGameState;
+    type PS = ClassicPlayerState;
+
+    /// Given a `GameState` returns the outcome of the game or `None` if game is not over
+    fn outcome(&self, game_state: &Self::GS) -> Option<GameOutcome> {
+        let rules = ClassicRules::new(self.config);
+        if let Some(outcome) = self
+            .victory_conditions
+            .iter()
+            .find_map(|c| c.evaluate(game_state, &rules))
+        {
+            return Some(outcome);
+        }
+
+        let alive: Vec<_> = game_state
+            .players()
+            .iter()
+            .filter(|p| p.is_alive())
+            .collect();
+        let everybody_played = alive.iter().all(|p| p.has_played());
+        let nobody_has_atoms = alive.iter().all(|p| game_state.player_atoms(p.id()) == 0);
+
+        if (everybody_played && nobody_has_atoms)
+            || self
+                .config
+                .max_turns()
+                .is_some_and(|max| game_state.current_turn() >= max)
+        {
+            Some(GameOutcome::Draw(alive.iter().map(|p| p.id()).collect()))
+        } else {
+            None
+        }
+    }
+
+    /// Given a `GameState` returns groups of players, best first (players in the same group
+    /// are tied).
+    ///
+    /// Winners (or players sharing a draw) come first, then alive players and then eliminated
+    /// ones. Players with the same outcome are sorted by score.
+    fn ranking(&self, game_state: &Self::GS) -> Vec<Vec<PlayerId>> {
+        let winners = match self.outcome(game_state) {
+            Some(GameOutcome::Winner(id)) => vec![id],
+            Some(GameOutcome::TeamWinner(team)) => game_state
+                .teams()
+                .teams()
+                .iter()
+                .find(|t| t.id() == team)
+                .map(|t| t.members().to_vec())
+                .unwrap_or_default(),
+            Some(GameOutcome::Draw(ids)) => ids,
+            None => vec![],
+        };
+        let key = |p: &ClassicPlayerState| (winners.contains(&p.id()), p.is_alive(), p.score());
+
+        let mut players = game_state.players().to_vec();
+        players.sort_by_key(|p| std::cmp::Reverse(key(p)));
+
+        let mut ranking: Vec<Vec<PlayerId>> = vec![];
+        for (i, player) in players.iter().enumerate() {
+            if i > 0 && key(&players[i - 1]) == key(player) {
+                if let Some(group) = ranking.last_mut() {
+                    group.push(player.id());
+                }
+            } else {
+                ranking.push(vec![player.id()]);
+            }
+        }
+        ranking
+    }
+
+    /// Given current state and list of players (in turn order) returns the first alive player
+    /// after the current one.
+    ///
+    /// `None` if no player is alive.
+    fn next_player(
+        &self,
+        game_state: &Self::GS,
+        players: Vec<Arc<dyn PlayerState<Data = Self::PS>>>,
+    ) -> Option<PlayerId> {
+        let current = players
+            .iter()
+            .position(|p| p.id() == game_state.current_player())
+            .unwrap_or(players.len().saturating_sub(1));
+
+        (1..=players.len())
+            .map(|i| &players[(current + i) % players.len()])
+            .find(|p| p.is_alive())
+            .map(|p| p.id())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        classic_game::cell_state::ClassicCellState,
+        generic_game::team::{Team, Teams},
+        geometry::{terrain::Terrain, vector::Position, volume::Volume},
+        test_utilities::game::{ground_game, ids, players, update_player},
+    };
+
+    fn game_state(n: usize) -> ClassicGameState {
+        ground_game(Position::new(3, 3, 3), n)
+    }
+
+    #[test]
+    /// Check if next player rotates skipping eliminated players
+    fn next_player_test() {
+        let rules = ClassicBasicRules::default();
+        let mut gs = game_state(4);
+        let ids = ids(&gs);
+
+        let next = |gs: &ClassicGameState| rules.next_player(gs, ClassicBasicRules::players_of(gs));
+        assert_eq!(next(&gs), Some(ids[1]));
+        gs.set_current_player(ids[3]);
+        assert_eq!(next(&gs), Some(ids[0]));
+
+        // Players that have not played yet are not skipped, even without atoms
+        update_player(&mut gs, ids[0], |p| p.set_has_played(true));
+        gs.set_cell_state(&Position::zero(), &ClassicCellState::Occupied(ids[0], 1));
+        gs.set_current_player(ids[0]);
+        assert!(!gs.player_state(ids[1]).unwrap().has_played());
+        assert_eq!(gs.player_atoms(ids[1]), 0);
+        assert_eq!(next(&gs), Some(ids[1]));
+        gs.set_current_player(ids[3]);
+
+        // Eliminated players are skipped
+        update_player(&mut gs, ids[0], |p| p.set_is_alive(false));
+        assert_eq!(next(&gs), Some(ids[1]));
+        gs.set_current_player(ids[1]);
+        update_player(&mut gs, ids[2], |p| p.set_is_alive(false));
+        assert_eq!(next(&gs), Some(ids[3]));
+        gs.set_current_player(ids[3]);
+        assert_eq!(next(&gs), Some(ids[1]));
+
+        // Only one left: plays again
+        update_player(&mut gs, ids[3], |p| p.set_is_alive(false));
+        gs.set_current_player(ids[1]);
+        assert_eq!(next(&gs), Some(ids[1]));
+
+        update_player(&mut gs, ids[1], |p| p.set_is_alive(false));
+        assert_eq!(next(&gs), None);
+    }
+
+    #[test]
+    /// Check classic winning rule
+    fn winner_test() {
+        let rules = ClassicBasicRules::default();
+        let mut gs = game_state(3);
+        let ids = ids(&gs);
+
+        // First player alone on the board, but others have not played yet
+        gs.set_cell_state(&Position::zero(), &ClassicCellState::Occupied(ids[0], 1));
+        update_player(&mut gs, ids[0], |p| p.set_has_played(true));
+        assert_eq!(rules.outcome(&gs), None);
+        assert_eq!(rules.winner(&gs), None);
+
+        update_player(&mut gs, ids[1], |p| p.set_has_played(true));
+        gs.set_cell_state(
+            &Position::new(1, 0, 0),
+            &ClassicCellState::Occupied(ids[1], 1),
+        );
+        update_player(&mut gs, ids[2], |p| p.set_has_played(true));
+        gs.set_cell_state(
+            &Position::new(2, 0, 0),
+            &ClassicCellState::Occupied(ids[2], 1),
+        );
+        assert_eq!(rules.outcome(&gs), None);
+
+        // Others lose their atoms and are eliminated
+        for (id, pos) in &[
+            (ids[1], Position::new(1, 0, 0)),
+            (ids[2], Position::new(2, 0, 0)),
+        ] {
+            gs.set_cell_state(pos, &ClassicCellState::Occupied(ids[0], 1));
+            update_player(&mut gs, *id, |p| p.set_is_alive(false));
+        }
+        assert_eq!(rules.outcome(&gs), Some(GameOutcome::Winner(ids[0])));
+        assert_eq!(rules.winner(&gs), Some(ids[0]));
+    }
+
+    #[test]
+    /// Check if a team wins when only its members have atoms
+    fn team_winner_test() {
+        let vol = Volume::new(&Position::zero(), &Position::new(3, 3, 3)).unwrap();
+        let players = players(4);
+        let ids: Vec<_> = players.iter().map(|p| p.id()).collect();
+        let teams = Teams::new(vec![
+            Team::new("A", &[ids[0], ids[1]]).unwrap(),
+            Team::new("B", &[ids[2], ids[3]]).unwrap(),
+        ])
+        .unwrap();
+        let team_a = teams.teams()[0].id();
+        let mut gs =
+            ClassicGameState::new_with_teams(Arc::new(Terrain::new(&vol)), &players, teams)
+                .unwrap();
+        let rules = ClassicBasicRules::default();
+
+        for (i, id) in ids.iter().enumerate() {
+            update_player(&mut gs, *id, |p| p.set_has_played(true));
+            gs.set_cell_state(
+                &Position::new(i as i32, 0, 0),
+                &ClassicCellState::Occupied(*id, 1),
+            );
+        }
+        assert_eq!(rules.outcome(&gs), None);
+
+        // Team B loses all its atoms
+        gs.set_cell_state(&Position::new(2, 0, 0), &ClassicCellState::Empty);
+        assert_eq!(rules.outcome(&gs), None);
+        gs.set_cell_state(&Position::new(3, 0, 0), &ClassicCellState::Empty);
+        assert_eq!(rules.outcome(&gs), Some(GameOutcome::TeamWinner(team_a)));
+        assert_eq!(rules.winning_team(&gs), Some(team_a));
+        assert_eq!(rules.winner(&gs), None);
+    }
+
+    #[test]
+    /// Check if victory conditions are checked in order and results are ranked
+    fn result_test() {
+        let mut gs = game_state(3);
+        let ids = ids(&gs);
+        let rules = ClassicBasicRules::with_victory_conditions(
+            ClassicRulesConfig::default(),
+            vec![
+                VictoryCondition::ScoreThreshold(100),
+                VictoryCondition::MostAtoms(50),
+            ],
+        );
+        assert_eq!(rules.victory_conditions().len(), 2);
+
+        for (id, score) in ids.iter().zip(&[30, 50, 30]) {
+            update_player(&mut gs, *id, |p| p.set_score(*score));
+        }
+        gs.set_cell_state(&Position::zero(), &ClassicCellState::Occupied(ids[0], 2));
+        gs.set_cell_state(
+            &Position::new(1, 0, 0),
+            &ClassicCellState::Occupied(ids[1], 1),
+        );
+        update_player(&mut gs, ids[2], |p| p.set_is_alive(false));
+        assert_eq!(rules.result(&gs), None);
+
+        gs.set_current_turn(50);
+        let result = rules.result(&gs).unwrap();
+        assert_eq!(result.outcome(), &GameOutcome::Winner(ids[0]));
+        assert_eq!(
+            result.ranking(),
+            &[vec![ids[0]], vec![ids[1]], vec![ids[2]]]
+        );
+
+        // Score threshold comes first
+        update_player(&mut gs, ids[1], |p| p.set_score(100));
+        assert_eq!(rules.outcome(&gs), Some(GameOutcome::Winner(ids[1])));
+
+        // Draw: tied players share the first rank
+        update_player(&mut gs, ids[1], |p| p.set_score(30));
+        gs.set_cell_state(
+            &Position::new(1, 0, 0),
+            &ClassicCellState::Occupied(ids[1], 2),
+        );
+        let result = rules.result(&gs).unwrap();
+        assert_eq!(result.outcome(), &GameOutcome::Draw(vec![ids[0], ids[1]]));
+        assert_eq!(result.ranking(), &[vec![ids[0], ids[1]], vec![ids[2]]]);
+        assert_eq!(result.rank_of(ids[1]), Some(0));
+        assert_eq!(result.rank_of(ids[2]), Some(1));
+    }
+
+    #[test]
+    /// Check draws: no atoms left and turn limit
+    fn draw_test() {
+        let mut gs = game_state(2);
+        let ids = ids(&gs);
+        for id in &ids {
+            update_player(&mut gs, *id, |p| p.set_has_played(true));
+        }
+
+        // Nobody has atoms
+        let rules = ClassicBasicRules::default();
+        assert_eq!(rules.outcome(&gs), Some(GameOutcome::Draw(ids.clone())));
+        assert_eq!(rules.winner(&gs), None);
+
+        // Turn limit
+        gs.set_cell_state(&Position::zero(), &ClassicCellState::Occupied(ids[0], 1));
+        gs.set_cell_state(
+            &Position::new(1, 0, 0),
+            &ClassicCellState::Occupied(ids[1], 1),
+        );
+        let mut config = ClassicRulesConfig::default();
+        config.set_max_turns(Some(10));
+        let rules = ClassicBasicRules::new(config);
+
+        gs.set_current_turn(9);
+        assert_eq!(rules.outcome(&gs), None);
+        gs.set_current_turn(10);
+        assert_eq!(rules.outcome(&gs), Some(GameOutcome::Draw(ids)));
+    }
+}
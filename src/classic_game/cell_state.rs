@@ -5,10 +5,10 @@ use crate::{
         player::PlayerId,
     },
 };
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 
 /// State of cell in classi game
-#[derive(Debug, Clone, Serialize, PartialEq, Eq)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
 pub enum ClassicCellState {
     /// Empty cell
     Empty,
@@ -59,7 +59,7 @@ impl Serializable for ClassicCellState {
 
     /// Return data to be serialized (itself)
     fn data_to_be_serialized(&self) -> &Self::Data {
-        &self
+        self
     }
 }
 
@@ -67,7 +67,7 @@ impl Serializable for ClassicCellState {
 mod tests {
     use super::ClassicCellState;
     use crate::{
-        common::clone_arc::{ArcClone, Clonable},
+        common::clone_arc::ArcClone,
         generic_game::cell_state::CellState,
         test_utilities::{
             constants::NUMBER_OF_LOOPS_FOR_NORMAL_TEST,
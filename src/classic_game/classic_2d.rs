@@ -0,0 +1,251 @@
+use std::sync::Arc;
+
+use log::warn;
+use serde::Serialize;
+
+use crate::{
+    generic_game::{cell_state::CellOccupation, player::PlayerInfo},
+    geometry::{
+        terrain::{CellMaterial, CellType, Terrain},
+        topology::Topology,
+        vector::{Position, Scalar},
+        volume::Volume,
+    },
+};
+
+use super::{
+    game_state::ClassicGameState,
+    rules::ClassicRules,
+    rules_config::{ClassicRulesConfig, Placement},
+};
+
+/// Board sizes of the classic 2D game
+#[derive(Debug, Clone, Copy, Serialize, PartialEq, Eq)]
+pub enum BoardSize {
+    /// 6x4 board
+    Small,
+    /// 10x6 board
+    Classic,
+    /// 15x10 board
+    Large,
+    /// Board with custom width and height
+    Custom { width: Scalar, height: Scalar },
+}
+
+impl BoardSize {
+    /// Returns number of columns of the board
+    pub fn width(&self) -> Scalar {
+        match self {
+            BoardSize::Small => 6,
+            BoardSize::Classic => 10,
+            BoardSize::Large => 15,
+            BoardSize::Custom { width, .. } => *width,
+        }
+    }
+
+    /// Returns number of rows of the board
+    pub fn height(&self) -> Scalar {
+        match self {
+            BoardSize::Small => 4,
+            BoardSize::Classic => 6,
+            BoardSize::Large => 10,
+            BoardSize::Custom { height, .. } => *height,
+        }
+    }
+}
+
+impl Default for BoardSize {
+    /// Classic board size
+    fn default() -> Self {
+        BoardSize::Classic
+    }
+}
+
+/// Preset of the classic 2D atoms game, played on a one-layer volume.
+///
+/// Each cell is connected to the cells on its sides (up to 4), thus critical mass is
+/// 2 in corners, 3 on edges and 4 in the centre of the board.
+#[derive(Debug, Clone, Copy, Default, Serialize, PartialEq, Eq)]
+pub struct Classic2d {
+    size: BoardSize,
+}
+
+impl Classic2d {
+    /// Create a preset with provided board size.
+    ///
+    /// Returns `None` if board has no cells.
+    pub fn new(size: BoardSize) -> Option<Self> {
+        if size.width() > 0 && size.height() > 0 {
+            Some(Self { size })
+        } else {
+            warn!("Invalid board size {:?}", size);
+            None
+        }
+    }
+
+    /// Returns board size
+    pub fn size(&self) -> BoardSize {
+        self.size
+    }
+
+    /// Returns the one-layer volume of the board (bottom left corner is the origin)
+    pub fn volume(&self) -> Volume {
+        let trc = Position::new(self.size.width() - 1, self.size.height() - 1, 0);
+        Volume::new(&Position::zero(), &trc).expect("Board size shall be a valid volume")
+    }
+
+    /// Returns a terrain with a cell in each position of the board
+    pub fn terrain(&self) -> Terrain {
+        let volume = self.volume();
+        let mut terrain = Terrain::new(&volume);
+        for p in &volume {
+            terrain.set_cell_at(&p, CellType::Fill(CellMaterial::Ground));
+        }
+        terrain
+    }
+
+    /// Returns rules configuration of the classic game: atoms inside cells and
+    /// no wrapping axes
+    pub fn rules_config(&self) -> ClassicRulesConfig {
+        let mut config = ClassicRulesConfig::default();
+        config.set_placement(Placement::InsideCells);
+        config.set_topology(Topology::bounded());
+        config
+    }
+
+    /// Returns rules of the classic game
+    pub fn rules(&self) -> ClassicRules {
+        ClassicRules::new(self.rules_config())
+    }
+
+    /// Returns critical mass of a cell as defined by the classic 2D game
+    /// (number of cells on its sides).
+    ///
+    /// `None` if position is not on the board.
+    pub fn critical_mass(&self, position: &Position) -> Option<CellOccupation> {
+        if !self.volume().is_inside(position) {
+            return None;
+        }
+        let sides = |value: Scalar, size: Scalar| {
+            (value > 0) as CellOccupation + (value < size - 1) as CellOccupation
+        };
+
+        Some(sides(position.x(), self.size.width()) + sides(position.y(), self.size.height()))
+    }
+
+    /// Create a new game on this board
+    pub fn new_game(&self, players: &[PlayerInfo]) -> Option<ClassicGameState> {
+        ClassicGameState::new(Arc::new(self.terrain()), players)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        classic_game::action::ClassicAction,
+        generic_game::{
+            game_rule::GameRule,
+            game_state::GameState,
+            player::{PlayerRage, PlayerState},
+        },
+        test_utilities::{constants::NUMBER_OF_LOOPS_FOR_SMALL_TEST, random_generator::*},
+    };
+
+    #[test]
+    /// Check board sizes and invalid boards
+    fn new_test() {
+        for size in &[BoardSize::Small, BoardSize::Classic, BoardSize::Large] {
+            let preset = Classic2d::new(*size).unwrap();
+            let volume = preset.volume();
+            assert_eq!(volume.volume(), size.width() * size.height());
+            assert_eq!(volume.diagonal().z(), 0);
+            assert_eq!(
+                preset.terrain().into_iter().count(),
+                volume.volume() as usize
+            );
+        }
+        assert_eq!(Classic2d::default().size(), BoardSize::Classic);
+        assert!(Classic2d::new(BoardSize::Custom {
+            width: 0,
+            height: 3
+        })
+        .is_none());
+        assert!(Classic2d::new(BoardSize::Custom {
+            width: 3,
+            height: -1
+        })
+        .is_none());
+    }
+
+    #[test]
+    /// Check if 3D engine computes the classic 2D critical masses
+    fn critical_mass_test() {
+        let preset = Classic2d::default();
+        let terrain = preset.terrain();
+        let rules = preset.rules();
+
+        assert_eq!(preset.critical_mass(&Position::new(0, 0, 0)), Some(2));
+        assert_eq!(preset.critical_mass(&Position::new(9, 5, 0)), Some(2));
+        assert_eq!(preset.critical_mass(&Position::new(4, 0, 0)), Some(3));
+        assert_eq!(preset.critical_mass(&Position::new(0, 3, 0)), Some(3));
+        assert_eq!(preset.critical_mass(&Position::new(4, 3, 0)), Some(4));
+        assert_eq!(preset.critical_mass(&Position::new(4, 3, 1)), None);
+
+        for _ in 0..NUMBER_OF_LOOPS_FOR_SMALL_TEST {
+            let size = BoardSize::Custom {
+                width: random_number(1, 12),
+                height: random_number(1, 12),
+            };
+            let preset = Classic2d::new(size).unwrap();
+            let terrain = preset.terrain();
+            for p in &preset.volume() {
+                assert_eq!(
+                    Some(rules.critical_mass(&terrain, &p)),
+                    preset.critical_mass(&p),
+                    "{:?} {:?}",
+                    size,
+                    p
+                );
+                assert!(rules.neighbours(&terrain, &p).iter().all(|n| n.z() == 0));
+            }
+        }
+
+        for p in &preset.volume() {
+            assert_eq!(
+                Some(rules.critical_mass(&terrain, &p)),
+                preset.critical_mass(&p)
+            );
+        }
+    }
+
+    #[test]
+    /// Check a known 2D sequence: a corner explodes with 2 atoms capturing both sides
+    fn corner_explosion_test() {
+        let preset = Classic2d::new(BoardSize::Small).unwrap();
+        let rules = preset.rules();
+        let players = [
+            PlayerInfo::new("A", PlayerRage::Red, true),
+            PlayerInfo::new("B", PlayerRage::Blue, true),
+        ];
+        let (a, b) = (players[0].id(), players[1].id());
+        let mut gs = Arc::new(preset.new_game(&players).unwrap());
+
+        let moves = [
+            Position::new(0, 0, 0),
+            Position::new(1, 0, 0),
+            Position::new(0, 0, 0),
+        ];
+        for position in &moves {
+            let action =
+                ClassicAction::place_atom(gs.current_player(), gs.current_turn(), *position);
+            assert!(rules.is_valid(&gs, &action));
+            gs = rules.execute(gs, Arc::new(action)).0;
+        }
+
+        assert_eq!(gs.player_atoms(a), 3);
+        assert_eq!(gs.player_atoms(b), 0);
+        assert_eq!(gs.player_cells(a), 2);
+        assert!(!gs.player_state(b).unwrap().is_alive());
+    }
+}
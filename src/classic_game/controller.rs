@@ -0,0 +1,530 @@
+use std::sync::Arc;
+
+use log::warn;
+
+use crate::generic_game::{clock::Millis, game_rule::GameRule, game_state::GameState};
+
+use super::{
+    action::ClassicAction,
+    basic_rules::ClassicBasicRules,
+    event::{GameEvent, ListenerId, Listeners},
+    game_state::ClassicGameState,
+    rules::ClassicRules,
+    timeline::Timeline,
+};
+
+/// Snapshots of the game states around current one, used to undo and redo turns
+#[derive(Debug, Clone, Default)]
+struct History {
+    /// States at the start of the turns played so far (last one is the previous turn)
+    undo: Vec<Arc<ClassicGameState>>,
+    /// States of the turns undone (last one is the next turn)
+    redo: Vec<Arc<ClassicGameState>>,
+}
+
+/// Controller of a classic game.
+///
+/// It owns the current game state, executes the actions of players and keeps time,
+/// emitting a timeout action (from `Actor::Controller`) when current player runs out of time.
+/// For casual play, it can keep a history of the game states to undo and redo turns.
+/// Listeners can subscribe to the events of each change of the game state.
+#[derive(Debug, Clone)]
+pub struct ClassicController {
+    /// Rules of the game
+    rules: ClassicRules,
+    /// Current game state
+    game_state: Arc<ClassicGameState>,
+    /// History of the game states (`None` if undo is disabled)
+    history: Option<History>,
+    /// Listeners of the game events
+    listeners: Listeners,
+}
+
+impl ClassicController {
+    /// Create a controller for a game starting from provided state (undo is disabled)
+    pub fn new(rules: ClassicRules, game_state: ClassicGameState) -> Self {
+        Self {
+            rules,
+            game_state: Arc::new(game_state),
+            history: None,
+            listeners: Listeners::default(),
+        }
+    }
+
+    /// Returns rules of the game
+    pub fn rules(&self) -> &ClassicRules {
+        &self.rules
+    }
+
+    /// Returns current game state
+    pub fn game_state(&self) -> Arc<ClassicGameState> {
+        Arc::clone(&self.game_state)
+    }
+
+    /// Execute an action.
+    ///
+    /// Returns the reactions to the action, or `None` if action is not valid
+    /// (game state does not change).
+    pub fn play(&mut self, action: ClassicAction) -> Option<Vec<Arc<ClassicAction>>> {
+        self.play_with_timeline(action)
+            .map(|(reactions, _)| reactions)
+    }
+
+    /// Execute an action (see `play`), also returning the animation timeline
+    /// of the chain reaction it causes
+    pub fn play_with_timeline(
+        &mut self,
+        action: ClassicAction,
+    ) -> Option<(Vec<Arc<ClassicAction>>, Timeline)> {
+        if !self.rules.can_handle(&action) || !self.rules.is_valid(&self.game_state, &action) {
+            warn!("Action {:?} refused", action);
+            return None;
+        }
+
+        // State of each substep is needed only to notify listeners
+        let (game_state, reactions, timeline, substeps) = if self.listeners.is_empty() {
+            let (game_state, reactions, timeline) = self
+                .rules
+                .execute_with_timeline(Arc::clone(&self.game_state), Arc::new(action));
+            (game_state, reactions, timeline, vec![])
+        } else {
+            self.rules
+                .execute_with_substeps(Arc::clone(&self.game_state), Arc::new(action))
+        };
+        let previous = std::mem::replace(&mut self.game_state, game_state);
+        self.notify(&previous, &substeps);
+        if let Some(history) = &mut self.history {
+            // A new move after undoing starts a new branch
+            history.undo.push(previous);
+            history.redo.clear();
+        }
+        Some((reactions, timeline))
+    }
+
+    /// Register a listener of the game events, returning its ID.
+    ///
+    /// It receives the events of each change of the game state (see `GameEvent::between`),
+    /// when actions are played and turns are undone or redone.
+    pub fn subscribe<F: FnMut(&GameEvent) + Send + 'static>(&mut self, listener: F) -> ListenerId {
+        self.listeners.add(Box::new(listener))
+    }
+
+    /// Remove a listener. Returns `false` if there is no listener with provided ID
+    pub fn unsubscribe(&mut self, id: ListenerId) -> bool {
+        self.listeners.remove(id)
+    }
+
+    /// Send the events leading from a previous state to current one to all listeners
+    /// (through the state of each substep, if any)
+    fn notify(&mut self, previous: &ClassicGameState, substeps: &[Arc<ClassicGameState>]) {
+        if self.listeners.is_empty() {
+            return;
+        }
+        let basic_rules = ClassicBasicRules::new(*self.rules.config());
+        let events = GameEvent::between(&basic_rules, previous, substeps, &self.game_state);
+        self.listeners.emit(&events);
+    }
+
+    /// Returns whether turns can be undone
+    pub fn undo_enabled(&self) -> bool {
+        self.history.is_some()
+    }
+
+    /// Set whether turns can be undone (history is cleared when disabled).
+    ///
+    /// It shall be enabled only for casual games (e.g. hot-seat), never for ranked or
+    /// network ones.
+    pub fn set_undo_enabled(&mut self, enabled: bool) {
+        match (enabled, &self.history) {
+            (true, None) => self.history = Some(History::default()),
+            (false, Some(_)) => self.history = None,
+            _ => {}
+        }
+    }
+
+    /// Returns whether a turn can be undone
+    pub fn can_undo(&self) -> bool {
+        self.history.as_ref().is_some_and(|h| !h.undo.is_empty())
+    }
+
+    /// Returns whether an undone turn can be redone
+    pub fn can_redo(&self) -> bool {
+        self.history.as_ref().is_some_and(|h| !h.redo.is_empty())
+    }
+
+    /// Go back to the state at the start of the previous turn
+    /// (reverting its whole explosion cascade, clocks included).
+    ///
+    /// Returns `false` if there is nothing to undo, or undo is disabled.
+    pub fn undo(&mut self) -> bool {
+        let history = match &mut self.history {
+            Some(history) => history,
+            None => return false,
+        };
+        match history.undo.pop() {
+            Some(game_state) => {
+                let previous = std::mem::replace(&mut self.game_state, game_state);
+                history.redo.push(Arc::clone(&previous));
+                self.notify(&previous, &[]);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Play again the last undone turn.
+    ///
+    /// Returns `false` if there is nothing to redo (e.g. a new move has been played since undo).
+    pub fn redo(&mut self) -> bool {
+        let history = match &mut self.history {
+            Some(history) => history,
+            None => return false,
+        };
+        match history.redo.pop() {
+            Some(game_state) => {
+                let previous = std::mem::replace(&mut self.game_state, game_state);
+                history.undo.push(Arc::clone(&previous));
+                self.notify(&previous, &[]);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Consume time on the clock of current player.
+    ///
+    /// If current player runs out of time, returns the timeout action followed by its reactions,
+    /// otherwise an empty list. Nothing happens if the game has no time control.
+    pub fn tick(&mut self, elapsed: Millis) -> Vec<Arc<ClassicAction>> {
+        if self.game_state.time_control().is_none() {
+            return vec![];
+        }
+
+        let mut game_state = (*self.game_state).clone();
+        let timed_out = game_state.tick_clock(elapsed);
+        self.game_state = Arc::new(game_state);
+        if !timed_out {
+            return vec![];
+        }
+
+        let timeout = ClassicAction::timeout(
+            self.game_state.current_player(),
+            self.game_state.current_turn(),
+        );
+        match self.play(timeout) {
+            Some(reactions) => std::iter::once(Arc::new(timeout))
+                .chain(reactions)
+                .collect(),
+            None => vec![],
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Mutex;
+
+    use super::*;
+    use crate::{
+        classic_game::{action::ClassicActionKind, cell_state::ClassicCellState, event::Change},
+        generic_game::{
+            action::Action,
+            actor::Actor,
+            clock::{TimeControl, TimeoutPolicy},
+            game_rule::GameOutcome,
+            player::{PlayerId, PlayerState},
+        },
+        geometry::vector::Position,
+        test_utilities::game::{ground_game, ids},
+    };
+
+    fn controller(policy: TimeoutPolicy) -> (ClassicController, Vec<PlayerId>) {
+        let mut gs = ground_game(Position::new(2, 2, 0), 2);
+        gs.set_time_control(Some(TimeControl::new(Some(1000), 5000, 100, policy)));
+        let ids = ids(&gs);
+
+        (ClassicController::new(ClassicRules::default(), gs), ids)
+    }
+
+    #[test]
+    /// Check if clocks are kept in the game state
+    fn clock_test() {
+        let (mut controller, ids) = controller(TimeoutPolicy::Skip);
+
+        assert!(controller.tick(400).is_empty());
+        let clock = controller
+            .game_state()
+            .player_state(ids[0])
+            .unwrap()
+            .clock();
+        assert_eq!((clock.bank(), clock.move_elapsed()), (4600, 400));
+
+        let action = ClassicAction::place_atom(ids[0], 0, Position::zero());
+        assert!(controller.play(action).is_some());
+        let clock = controller
+            .game_state()
+            .player_state(ids[0])
+            .unwrap()
+            .clock();
+        assert_eq!((clock.bank(), clock.move_elapsed()), (4700, 0));
+        assert_eq!(controller.game_state().current_player(), ids[1]);
+
+        // Invalid actions are refused
+        assert!(controller.play(action).is_none());
+        assert!(controller.play(ClassicAction::timeout(ids[1], 1)).is_none());
+    }
+
+    #[test]
+    /// Check each timeout policy
+    fn timeout_test() {
+        for policy in &[
+            TimeoutPolicy::RandomMove,
+            TimeoutPolicy::Skip,
+            TimeoutPolicy::Forfeit,
+        ] {
+            let (mut controller, ids) = controller(*policy);
+
+            assert!(controller.tick(999).is_empty());
+            let actions = controller.tick(1);
+            let timeout = &actions[0];
+            assert_eq!(timeout.kind(), ClassicActionKind::Timeout(ids[0]));
+            assert_eq!(timeout.source(), Actor::Controller);
+
+            let gs = controller.game_state();
+            let a = gs.player_state(ids[0]).unwrap();
+            assert_eq!(gs.current_player(), ids[1]);
+            assert_eq!(gs.current_turn(), 1);
+            assert_eq!(a.clock().move_elapsed(), 0);
+            match policy {
+                TimeoutPolicy::RandomMove => {
+                    assert_eq!(gs.player_atoms(ids[0]), 1);
+                    assert!(actions
+                        .iter()
+                        .any(|a| matches!(a.kind(), ClassicActionKind::PlaceAtom(_))));
+                    assert_eq!(a.clock().bank(), 4100);
+                }
+                TimeoutPolicy::Skip => {
+                    assert_eq!(gs.occupied_cells().count(), 0);
+                    assert!(a.is_alive());
+                    assert_eq!(a.clock().bank(), 4100);
+                }
+                TimeoutPolicy::Forfeit => {
+                    assert!(!a.is_alive());
+                    assert!(actions
+                        .iter()
+                        .any(|a| a.kind() == ClassicActionKind::Eliminate(ids[0])));
+                }
+            }
+        }
+    }
+
+    #[test]
+    /// Check if a skipped player with an empty bank can play with the increment
+    fn skip_empty_bank_test() {
+        let mut gs = ground_game(Position::new(2, 2, 0), 2);
+        gs.set_time_control(Some(TimeControl::new(None, 500, 100, TimeoutPolicy::Skip)));
+        let ids = ids(&gs);
+        let mut controller = ClassicController::new(ClassicRules::default(), gs);
+
+        assert_eq!(controller.tick(500).len(), 1);
+        let clock = controller
+            .game_state()
+            .player_state(ids[0])
+            .unwrap()
+            .clock();
+        assert_eq!(clock.bank(), 100);
+
+        assert!(controller
+            .play(ClassicAction::place_atom(ids[1], 1, Position::zero()))
+            .is_some());
+        assert!(controller.tick(99).is_empty());
+        let position = Position::new(2, 2, 0);
+        assert!(controller
+            .play(ClassicAction::place_atom(ids[0], 2, position))
+            .is_some());
+        assert_eq!(controller.game_state().player_atoms(ids[0]), 1);
+    }
+
+    #[test]
+    /// Check if whole turns are undone and redone, branching on new moves
+    fn undo_redo_test() {
+        let (mut controller, ids) = controller(TimeoutPolicy::Skip);
+        assert!(!controller.undo_enabled());
+        controller.play(ClassicAction::place_atom(ids[0], 0, Position::zero()));
+        assert!(!controller.undo());
+
+        controller.set_undo_enabled(true);
+        assert!(!controller.can_undo());
+        let start = controller.game_state();
+
+        // Corner cell explodes with 2 atoms, conquering its neighbours
+        let turns = [
+            ClassicAction::place_atom(ids[1], 1, Position::new(1, 0, 0)),
+            ClassicAction::place_atom(ids[0], 2, Position::zero()),
+        ];
+        let mut states = vec![start.clone()];
+        for action in &turns {
+            assert!(controller.play(*action).is_some());
+            states.push(controller.game_state());
+        }
+        assert_eq!(states[2].player_atoms(ids[1]), 0);
+
+        assert!(controller.undo());
+        assert_eq!(controller.game_state().state_hash(), states[1].state_hash());
+        assert_eq!(controller.game_state().player_atoms(ids[1]), 1);
+        assert!(controller.undo());
+        assert!(Arc::ptr_eq(&controller.game_state(), &start));
+        assert!(!controller.undo());
+
+        assert!(controller.redo());
+        assert!(controller.redo());
+        assert!(Arc::ptr_eq(&controller.game_state(), &states[2]));
+        assert!(!controller.redo());
+
+        // A new move after undoing discards the undone turns
+        assert!(controller.undo());
+        assert!(controller.can_redo());
+        let branch = ClassicAction::place_atom(ids[0], 2, Position::new(1, 1, 0));
+        assert!(controller.play(branch).is_some());
+        assert!(!controller.can_redo());
+        assert!(!controller.redo());
+        assert_eq!(controller.game_state().player_atoms(ids[1]), 1);
+
+        // Refused actions are not recorded
+        assert!(controller.play(branch).is_none());
+        assert!(controller.undo());
+        assert_eq!(controller.game_state().state_hash(), states[1].state_hash());
+
+        controller.set_undo_enabled(false);
+        assert!(!controller.can_undo());
+        assert!(!controller.undo());
+    }
+
+    #[test]
+    /// Check if listeners receive the events of each change, until they unsubscribe
+    fn events_test() {
+        let (mut controller, ids) = controller(TimeoutPolicy::Skip);
+        controller.set_undo_enabled(true);
+        let events = Arc::new(Mutex::new(vec![]));
+        let listener = {
+            let events = Arc::clone(&events);
+            controller.subscribe(move |e| events.lock().unwrap().push(e.clone()))
+        };
+        let take = || std::mem::take(&mut *events.lock().unwrap());
+
+        controller.play(ClassicAction::place_atom(ids[0], 0, Position::zero()));
+        let occupied = |id, count| ClassicCellState::Occupied(id, count);
+        assert_eq!(
+            take(),
+            vec![
+                GameEvent::CellChanged {
+                    position: Position::zero(),
+                    state: Change::new(ClassicCellState::Empty, occupied(ids[0], 1)),
+                },
+                GameEvent::TurnChanged {
+                    turn: Change::new(0, 1),
+                    player: Change::new(ids[0], ids[1]),
+                }
+            ]
+        );
+
+        // Corner explosion captures the only cell of second player
+        controller.play(ClassicAction::place_atom(ids[1], 1, Position::new(1, 0, 0)));
+        take();
+        let (_, timeline) = controller
+            .play_with_timeline(ClassicAction::place_atom(ids[0], 2, Position::zero()))
+            .unwrap();
+        assert_eq!(timeline.waves().len(), 1);
+        assert_eq!(timeline.waves()[0].captures().len(), 1);
+        let b = controller.game_state().player_state(ids[1]).unwrap();
+        let events = take();
+        // Atom is placed, then the wave of explosions changes the cells
+        assert_eq!(
+            events[..5],
+            [
+                GameEvent::CellChanged {
+                    position: Position::zero(),
+                    state: Change::new(occupied(ids[0], 1), occupied(ids[0], 2)),
+                },
+                GameEvent::SubstepAdvanced {
+                    turn: 2,
+                    substep: Change::new(0, 1),
+                },
+                GameEvent::CellChanged {
+                    position: Position::zero(),
+                    state: Change::new(occupied(ids[0], 2), ClassicCellState::Empty),
+                },
+                GameEvent::CellChanged {
+                    position: Position::new(1, 0, 0),
+                    state: Change::new(occupied(ids[1], 1), occupied(ids[0], 2)),
+                },
+                GameEvent::CellChanged {
+                    position: Position::new(0, 1, 0),
+                    state: Change::new(ClassicCellState::Empty, occupied(ids[0], 1)),
+                },
+            ]
+        );
+        match &events[5] {
+            GameEvent::PlayerEliminated { player, state } => {
+                assert_eq!(*player, ids[1]);
+                assert!(state.before().is_alive());
+                assert_eq!(*state.after(), b);
+            }
+            event => panic!("Unexpected event {:?}", event),
+        }
+        assert_eq!(
+            events[6..],
+            [
+                GameEvent::TurnChanged {
+                    turn: Change::new(2, 3),
+                    player: Change::new(ids[0], ids[0]),
+                },
+                GameEvent::GameOver(GameOutcome::Winner(ids[0])),
+            ]
+        );
+
+        // Undoing emits the changes back
+        assert!(controller.undo());
+        let events = take();
+        assert_eq!(events.len(), 4);
+        assert_eq!(
+            events[3],
+            GameEvent::TurnChanged {
+                turn: Change::new(3, 2),
+                player: Change::new(ids[0], ids[0]),
+            }
+        );
+
+        // Clones do not notify listeners
+        let mut clone = controller.clone();
+        assert!(clone.redo());
+        assert!(take().is_empty());
+
+        assert!(controller.unsubscribe(listener));
+        assert!(!controller.unsubscribe(listener));
+        assert!(controller.redo());
+        assert!(take().is_empty());
+    }
+
+    #[test]
+    /// Check if random move on timeout is the same when replayed
+    fn deterministic_timeout_test() {
+        let (controller, ids) = controller(TimeoutPolicy::RandomMove);
+        let play = || {
+            let mut controller = controller.clone();
+            controller.play(ClassicAction::place_atom(ids[0], 0, Position::zero()));
+            controller.tick(1000);
+            controller.game_state()
+        };
+        let (first, second) = (play(), play());
+        let cells = |gs: &ClassicGameState| -> Vec<_> {
+            gs.occupied_cells()
+                .map(|(p, c)| match c {
+                    ClassicCellState::Occupied(_, count) => (p, *count),
+                    ClassicCellState::Empty => (p, 0),
+                })
+                .collect()
+        };
+        assert_eq!(cells(&first), cells(&second));
+    }
+}
@@ -0,0 +1,197 @@
+use std::{fmt, sync::Arc};
+
+use serde::Serialize;
+
+use crate::{
+    common::id_generator::{new_id, Id},
+    generic_game::{
+        action::TurnId,
+        game_rule::{BasicRules, GameOutcome},
+        game_state::GameState,
+        player::{PlayerId, PlayerState},
+    },
+    geometry::vector::Position,
+};
+
+use super::{
+    basic_rules::ClassicBasicRules, cell_state::ClassicCellState, game_state::ClassicGameState,
+    player::ClassicPlayerState,
+};
+
+/// ID of a listener registered on a `ClassicController`
+pub type ListenerId = Id;
+
+/// Function receiving game events
+pub type Listener = Box<dyn FnMut(&GameEvent) + Send>;
+
+/// Values of something before and after a change
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct Change<T> {
+    before: T,
+    after: T,
+}
+
+impl<T> Change<T> {
+    /// Create a new change
+    pub fn new(before: T, after: T) -> Self {
+        Self { before, after }
+    }
+
+    /// Returns value before the change
+    pub fn before(&self) -> &T {
+        &self.before
+    }
+
+    /// Returns value after the change
+    pub fn after(&self) -> &T {
+        &self.after
+    }
+}
+
+/// Change of the game state emitted by a `ClassicController` to its listeners
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub enum GameEvent {
+    /// A wave of explosions of current turn started (substeps are emitted in order)
+    SubstepAdvanced {
+        turn: TurnId,
+        substep: Change<TurnId>,
+    },
+    /// State of a cell changed
+    CellChanged {
+        position: Position,
+        state: Change<ClassicCellState>,
+    },
+    /// Player has been eliminated
+    PlayerEliminated {
+        player: PlayerId,
+        state: Change<ClassicPlayerState>,
+    },
+    /// Turn passed to next player
+    TurnChanged {
+        turn: Change<TurnId>,
+        player: Change<PlayerId>,
+    },
+    /// Game ended
+    GameOver(GameOutcome),
+}
+
+impl GameEvent {
+    /// Returns the events leading from a game state to another, in this order:
+    /// cells changed by each substep (in volume order, each wave of explosions after the
+    /// `SubstepAdvanced` event starting it), eliminated players, turn change and end of game.
+    ///
+    /// * `basic_rules` - Rules deciding whether the game is over
+    /// * `substeps` - Game state at the end of each substep (see
+    ///   `ClassicRules::execute_with_substeps`); if empty, cells changes are emitted at once
+    pub fn between(
+        basic_rules: &ClassicBasicRules,
+        before: &ClassicGameState,
+        substeps: &[Arc<ClassicGameState>],
+        after: &ClassicGameState,
+    ) -> Vec<Self> {
+        let mut events = vec![];
+        let mut previous = before;
+        for state in substeps
+            .iter()
+            .map(Arc::as_ref)
+            .chain(std::iter::once(after))
+        {
+            let substep = state.current_turn_substep();
+            if state.current_turn() == before.current_turn()
+                && substep > previous.current_turn_substep()
+            {
+                events.push(GameEvent::SubstepAdvanced {
+                    turn: before.current_turn(),
+                    substep: Change::new(previous.current_turn_substep(), substep),
+                });
+            }
+            events.extend(
+                state
+                    .changed_cells(previous)
+                    .map(|(position, previous, cell)| GameEvent::CellChanged {
+                        position,
+                        state: Change::new(previous.clone(), cell.clone()),
+                    }),
+            );
+            previous = state;
+        }
+
+        for player in after.players() {
+            if let Some(previous) = before.player_state(player.id()) {
+                if previous.is_alive() && !player.is_alive() {
+                    events.push(GameEvent::PlayerEliminated {
+                        player: player.id(),
+                        state: Change::new(previous, *player),
+                    });
+                }
+            }
+        }
+        if (before.current_turn(), before.current_player())
+            != (after.current_turn(), after.current_player())
+        {
+            events.push(GameEvent::TurnChanged {
+                turn: Change::new(before.current_turn(), after.current_turn()),
+                player: Change::new(before.current_player(), after.current_player()),
+            });
+        }
+        if basic_rules.outcome(before).is_none() {
+            if let Some(outcome) = basic_rules.outcome(after) {
+                events.push(GameEvent::GameOver(outcome));
+            }
+        }
+        events
+    }
+}
+
+/// Listeners of the events of a controller.
+///
+/// They are not cloned along with the controller (e.g. clones used to explore moves
+/// shall not notify the UI).
+#[derive(Default)]
+pub(crate) struct Listeners {
+    listeners: Vec<(ListenerId, Listener)>,
+}
+
+impl Listeners {
+    /// Register a listener, returning its ID
+    pub(crate) fn add(&mut self, listener: Listener) -> ListenerId {
+        let id = new_id();
+        self.listeners.push((id, listener));
+        id
+    }
+
+    /// Remove a listener. Returns `false` if there is no listener with provided ID
+    pub(crate) fn remove(&mut self, id: ListenerId) -> bool {
+        let count = self.listeners.len();
+        self.listeners.retain(|(listener, _)| *listener != id);
+        self.listeners.len() != count
+    }
+
+    /// Returns whether there are no listeners
+    pub(crate) fn is_empty(&self) -> bool {
+        self.listeners.is_empty()
+    }
+
+    /// Send events to all listeners (in registration order)
+    pub(crate) fn emit(&mut self, events: &[GameEvent]) {
+        for (_, listener) in &mut self.listeners {
+            for event in events {
+                listener(event);
+            }
+        }
+    }
+}
+
+impl Clone for Listeners {
+    fn clone(&self) -> Self {
+        Self::default()
+    }
+}
+
+impl fmt::Debug for Listeners {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list()
+            .entries(self.listeners.iter().map(|(id, _)| id))
+            .finish()
+    }
+}
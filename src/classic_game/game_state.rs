@@ -0,0 +1,648 @@
+use std::sync::Arc;
+
+use log::warn;
+use serde::{de::Error, Deserialize, Deserializer, Serialize};
+
+use crate::{
+    common::{chunked_vec::ChunkedVec, clone_arc::Clonable, serializable::Serializable},
+    generic_game::{
+        action::TurnId,
+        cell_state::CellState,
+        clock::{Millis, TimeControl},
+        game_state::GameState,
+        player::{PlayerId, PlayerInfo, PlayerState},
+        team::Teams,
+        zobrist::{cell_key, current_player_key, Seat, StateHash},
+    },
+    geometry::{terrain::Terrain, vector::Position},
+};
+
+use super::{cell_state::ClassicCellState, player::ClassicPlayerState};
+
+/// State of a classic atomz game at a particular moment
+#[derive(Debug, Clone, Serialize)]
+pub struct ClassicGameState {
+    /// Terrain where the game is played
+    terrain: Arc<Terrain>,
+    /// State of each player (in turn order)
+    players: Vec<ClassicPlayerState>,
+    /// State of each position of terrain volume (indexed as volume iterator).
+    ///
+    /// Chunks are shared with the states this one is cloned from, to make snapshots cheap.
+    cells: ChunkedVec<ClassicCellState>,
+    /// Current active player
+    current_player: PlayerId,
+    /// Current turn
+    current_turn: TurnId,
+    /// Substep of current turn
+    current_turn_substep: TurnId,
+    /// Zobrist hash of cells and current player
+    hash: StateHash,
+    /// Teams of players (they do not change during the game)
+    teams: Teams,
+    /// Time control of the game (`None` no clocks)
+    time_control: Option<TimeControl>,
+}
+
+impl ClassicGameState {
+    /// Create a new game state with all cells empty.
+    ///
+    /// All players are alive and the first one is the current player.
+    /// Returns `None` if the list of players is empty.
+    pub fn new(terrain: Arc<Terrain>, players: &[PlayerInfo]) -> Option<Self> {
+        let first = match players.first() {
+            Some(first) => first.id(),
+            None => {
+                warn!("Cannot create a game state without players");
+                return None;
+            }
+        };
+
+        let players = players
+            .iter()
+            .map(|info| {
+                let mut state = ClassicPlayerState::new(info.id());
+                state.set_is_alive(true);
+                state.set_is_current(info.id() == first);
+                state
+            })
+            .collect();
+        let size = terrain.volume().volume() as usize;
+
+        Some(Self {
+            terrain,
+            players,
+            cells: ChunkedVec::from_elem(ClassicCellState::Empty, size),
+            current_player: first,
+            current_turn: 0,
+            current_turn_substep: 0,
+            hash: current_player_key(0),
+            teams: Teams::default(),
+            time_control: None,
+        })
+    }
+
+    /// Create a new game state where players are grouped in teams.
+    ///
+    /// Players are sorted interleaving teams (see `Teams::turn_order`).
+    /// Returns `None` if the list of players is empty or a team member is not a player.
+    pub fn new_with_teams(
+        terrain: Arc<Terrain>,
+        players: &[PlayerInfo],
+        teams: Teams,
+    ) -> Option<Self> {
+        let ids: Vec<_> = players.iter().map(|p| p.id()).collect();
+        if let Some(team) = teams
+            .teams()
+            .iter()
+            .find(|t| t.members().iter().any(|m| !ids.contains(m)))
+        {
+            warn!("Team {} has members that are not players", team.name());
+            return None;
+        }
+
+        let sorted: Vec<_> = teams
+            .turn_order(&ids)
+            .iter()
+            .filter_map(|id| players.iter().find(|p| p.id() == *id))
+            .cloned()
+            .collect();
+        let mut game_state = Self::new(terrain, &sorted)?;
+        game_state.teams = teams;
+        Some(game_state)
+    }
+
+    /// Returns teams of players
+    pub fn teams(&self) -> &Teams {
+        &self.teams
+    }
+
+    /// Returns whether two players are allies (a player is always allied with itself)
+    pub fn are_allies(&self, player: PlayerId, other: PlayerId) -> bool {
+        self.teams.are_allies(player, other)
+    }
+
+    /// Returns time control of the game (`None` no clocks)
+    pub fn time_control(&self) -> Option<TimeControl> {
+        self.time_control
+    }
+
+    /// Set time control of the game, resetting the clocks of all players
+    pub fn set_time_control(&mut self, time_control: Option<TimeControl>) {
+        self.time_control = time_control;
+        let clock = time_control.map(|c| c.initial_clock()).unwrap_or_default();
+        for player in &mut self.players {
+            player.set_clock(clock);
+        }
+    }
+
+    /// Consume time on the clock of current player.
+    ///
+    /// Returns whether current player ran out of time (always `false` without time control).
+    pub fn tick_clock(&mut self, elapsed: Millis) -> bool {
+        let current = self.current_player;
+        if let Some(player) = self.players.iter_mut().find(|p| p.id() == current) {
+            let mut clock = player.clock();
+            clock.tick(elapsed);
+            player.set_clock(clock);
+        }
+        self.is_timed_out(current)
+    }
+
+    /// Returns whether provided player ran out of time (always `false` without time control)
+    pub fn is_timed_out(&self, player_id: PlayerId) -> bool {
+        match (self.time_control, self.player_state(player_id)) {
+            (Some(time_control), Some(player)) => time_control.is_timed_out(&player.clock()),
+            _ => false,
+        }
+    }
+
+    /// Returns the state of all players (in turn order)
+    pub fn players(&self) -> &[ClassicPlayerState] {
+        &self.players
+    }
+
+    /// Returns an iterator over all non empty cells with their position
+    pub fn occupied_cells(&self) -> impl Iterator<Item = (Position, &ClassicCellState)> + '_ {
+        let volume = self.terrain.volume();
+        self.cells
+            .iter()
+            .enumerate()
+            .filter(|(_, cell)| !cell.is_empty())
+            .map(move |(index, cell)| {
+                (
+                    volume
+                        .position_at(index)
+                        .expect("Cell index shall be valid"),
+                    cell,
+                )
+            })
+    }
+
+    /// Returns the cells that changed since a previous state of the same game,
+    /// with their position and state before and after
+    pub fn changed_cells<'a>(
+        &'a self,
+        previous: &'a Self,
+    ) -> impl Iterator<Item = (Position, &'a ClassicCellState, &'a ClassicCellState)> + 'a {
+        let volume = self.terrain.volume();
+        previous
+            .cells
+            .changes(&self.cells)
+            .map(move |(index, before, after)| {
+                (
+                    volume
+                        .position_at(index)
+                        .expect("Cell index shall be valid"),
+                    before,
+                    after,
+                )
+            })
+    }
+
+    /// Returns number of atoms owned by provided player
+    pub fn player_atoms(&self, player_id: PlayerId) -> usize {
+        self.occupied_cells()
+            .filter_map(|(_, cell)| cell.player_occupation(player_id))
+            .map(usize::from)
+            .sum()
+    }
+
+    /// Returns number of cells owned by provided player
+    pub fn player_cells(&self, player_id: PlayerId) -> usize {
+        self.occupied_cells()
+            .filter(|(_, cell)| cell.player_occupation(player_id).is_some())
+            .count()
+    }
+
+    /// Returns the seat of a player in turn order (`None` if it is not part of this game)
+    pub fn seat(&self, player_id: PlayerId) -> Option<Seat> {
+        self.players.iter().position(|p| p.id() == player_id)
+    }
+
+    /// Returns Zobrist key of a cell state at provided position.
+    ///
+    /// `None` if the cell is owned by a player that is not part of this game.
+    fn cell_hash(&self, position: &Position, state: &ClassicCellState) -> Option<StateHash> {
+        match state {
+            ClassicCellState::Empty => Some(0),
+            ClassicCellState::Occupied(id, occupation) => self
+                .seat(*id)
+                .map(|seat| cell_key(position, seat, *occupation)),
+        }
+    }
+}
+
+impl Clonable for ClassicGameState {}
+
+impl Serializable for ClassicGameState {
+    type Data = Self;
+
+    /// Return data to be serialized (itself)
+    fn data_to_be_serialized(&self) -> &Self::Data {
+        self
+    }
+}
+
+impl<'de> Deserialize<'de> for ClassicGameState {
+    /// Deserialize a game state, refusing inconsistent ones (hash is computed again)
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        #[derive(Deserialize)]
+        struct Data {
+            terrain: Arc<Terrain>,
+            players: Vec<ClassicPlayerState>,
+            cells: ChunkedVec<ClassicCellState>,
+            current_player: PlayerId,
+            current_turn: TurnId,
+            current_turn_substep: TurnId,
+            teams: Teams,
+            time_control: Option<TimeControl>,
+        }
+
+        let data = Data::deserialize(deserializer)?;
+        if data.cells.len() != data.terrain.volume().volume() as usize {
+            return Err(D::Error::custom("Cells do not match terrain volume"));
+        }
+        let mut game_state = Self {
+            terrain: data.terrain,
+            players: data.players,
+            cells: data.cells,
+            current_player: data.current_player,
+            current_turn: data.current_turn,
+            current_turn_substep: data.current_turn_substep,
+            hash: 0,
+            teams: data.teams,
+            time_control: data.time_control,
+        };
+
+        let seat = game_state
+            .seat(game_state.current_player)
+            .ok_or_else(|| D::Error::custom("Current player is not part of the game"))?;
+        let mut hash = current_player_key(seat);
+        for (position, cell) in game_state.occupied_cells() {
+            hash ^= game_state
+                .cell_hash(&position, cell)
+                .ok_or_else(|| D::Error::custom("Cell owner is not part of the game"))?;
+        }
+        game_state.hash = hash;
+        Ok(game_state)
+    }
+}
+
+impl GameState for ClassicGameState {
+    type PS = ClassicPlayerState;
+    type CS = ClassicCellState;
+
+    /// Returns the `Terrain` used in this game state.
+    fn terrain(&self) -> Arc<Terrain> {
+        Arc::clone(&self.terrain)
+    }
+
+    /// Returns current active player.
+    fn current_player(&self) -> PlayerId {
+        self.current_player
+    }
+
+    /// Set current active player.
+    ///
+    /// `true` if player ID is valid.
+    fn set_current_player(&mut self, player: PlayerId) -> bool {
+        let (current, seat) = match (self.seat(self.current_player), self.seat(player)) {
+            (Some(current), Some(seat)) => (current, seat),
+            _ => {
+                warn!("Player {} is not part of this game", player);
+                return false;
+            }
+        };
+
+        self.hash ^= current_player_key(current) ^ current_player_key(seat);
+        self.current_player = player;
+        for p in &mut self.players {
+            p.set_is_current(p.id() == player);
+        }
+        true
+    }
+
+    /// Returns ID of current turn being played.
+    fn current_turn(&self) -> TurnId {
+        self.current_turn
+    }
+
+    /// Set ID of current turn being played.
+    fn set_current_turn(&mut self, turn: TurnId) {
+        self.current_turn = turn;
+    }
+
+    /// Returns ID of substep of current turn being played.
+    fn current_turn_substep(&self) -> TurnId {
+        self.current_turn_substep
+    }
+
+    /// Sets ID of substep of current turn being played.
+    fn set_current_turn_substep(&mut self, substep: TurnId) {
+        self.current_turn_substep = substep;
+    }
+
+    /// Returns the state of selected player.
+    ///
+    /// Returns `None` if player Id is not associated to any player
+    /// of this game state.
+    fn player_state(&self, player_id: PlayerId) -> Option<Self::PS> {
+        self.players.iter().find(|p| p.id() == player_id).copied()
+    }
+
+    /// Set the state of selected player.
+    ///
+    /// Returns `false` if player Id is not associated to any player
+    /// of this game state.
+    fn set_player_state(&mut self, player_id: PlayerId, state: &Self::PS) -> bool {
+        match self.players.iter_mut().find(|p| p.id() == player_id) {
+            Some(player) => {
+                *player = *state;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Returns the state of specified cell.
+    ///
+    /// `None` if the position is outside of terrain volume.
+    fn cell_state(&self, position: &Position) -> Option<Self::CS> {
+        let index = self.terrain.volume().index_of(position)?;
+        self.cells.get(index).cloned()
+    }
+
+    /// Sets the state of specified cell.
+    ///
+    /// Return `false` if the position is outside of terrain volume,
+    /// or the cell is owned by a player that is not part of this game.
+    fn set_cell_state(&mut self, position: &Position, state: &Self::CS) -> bool {
+        let index = match self.terrain.volume().index_of(position) {
+            Some(index) => index,
+            None => {
+                warn!("Position {:?} is outside of terrain", position);
+                return false;
+            }
+        };
+        let key = match self.cell_hash(position, state) {
+            Some(key) => key,
+            None => {
+                warn!(
+                    "Cell {:?} is owned by a player not part of this game",
+                    position
+                );
+                return false;
+            }
+        };
+        let previous = self
+            .cells
+            .get(index)
+            .and_then(|cell| self.cell_hash(position, cell))
+            .unwrap_or(0);
+
+        match self.cells.get_mut(index) {
+            Some(cell) => {
+                self.hash ^= previous ^ key;
+                *cell = state.clone();
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Returns a 64-bit hash of cells and current player of this state.
+    fn state_hash(&self) -> StateHash {
+        self.hash
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        common::{chunked_vec::CHUNK_SIZE, id_generator::new_id},
+        generic_game::{player::PlayerRage, team::Team},
+        geometry::volume::Volume,
+        test_utilities::{
+            constants::NUMBER_OF_LOOPS_FOR_SMALL_TEST,
+            game::players,
+            random_generator::{random_number, random_terrain, random_volume},
+        },
+    };
+
+    /// Create a game state with a random terrain and `n` players
+    fn random_game_state(n: usize) -> ClassicGameState {
+        let vol = random_volume(1, 6);
+        let (terrain, _) = random_terrain(&vol);
+        ClassicGameState::new(Arc::new(terrain), &players(n)).unwrap()
+    }
+
+    /// Compute hash from scratch
+    fn full_hash(gs: &ClassicGameState) -> StateHash {
+        let current = gs.seat(gs.current_player()).unwrap();
+        gs.occupied_cells()
+            .fold(current_player_key(current), |acc, (p, c)| {
+                acc ^ gs.cell_hash(&p, c).unwrap()
+            })
+    }
+
+    #[test]
+    /// Check if new state is consistent
+    fn new_test() {
+        let vol = Volume::new(&Position::zero(), &Position::new(2, 2, 2)).unwrap();
+        let terrain = Arc::new(Terrain::new(&vol));
+        assert!(ClassicGameState::new(Arc::clone(&terrain), &[]).is_none());
+
+        let players = [
+            PlayerInfo::new("A", PlayerRage::Red, true),
+            PlayerInfo::new("B", PlayerRage::Blue, false),
+        ];
+        let gs = ClassicGameState::new(terrain, &players).unwrap();
+
+        assert_eq!(gs.current_player(), players[0].id());
+        assert_eq!(gs.players().len(), 2);
+        assert!(gs.player_state(players[0].id()).unwrap().is_current());
+        assert!(!gs.player_state(players[1].id()).unwrap().is_current());
+        assert!(gs.players().iter().all(|p| p.is_alive()));
+        assert_eq!(gs.occupied_cells().count(), 0);
+        assert!(gs.cell_state(&Position::new(3, 0, 0)).is_none());
+        assert_eq!(gs.state_hash(), full_hash(&gs));
+    }
+
+    #[test]
+    /// Check if players are sorted interleaving teams
+    fn new_with_teams_test() {
+        let vol = Volume::new(&Position::zero(), &Position::new(2, 2, 2)).unwrap();
+        let terrain = Arc::new(Terrain::new(&vol));
+        let players = players(4);
+        let ids: Vec<_> = players.iter().map(|p| p.id()).collect();
+        let teams = Teams::new(vec![
+            Team::new("A", &ids[..2]).unwrap(),
+            Team::new("B", &ids[2..]).unwrap(),
+        ])
+        .unwrap();
+
+        let gs = ClassicGameState::new_with_teams(Arc::clone(&terrain), &players, teams.clone())
+            .unwrap();
+        let order: Vec<_> = gs.players().iter().map(|p| p.id()).collect();
+        assert_eq!(order, vec![ids[0], ids[2], ids[1], ids[3]]);
+        assert_eq!(gs.current_player(), ids[0]);
+        assert!(gs.are_allies(ids[0], ids[1]));
+        assert!(!gs.are_allies(ids[0], ids[2]));
+        assert_eq!(gs.teams(), &teams);
+
+        // Team members must be players of the game
+        assert!(ClassicGameState::new_with_teams(terrain, &players[..3], teams).is_none());
+    }
+
+    #[test]
+    /// Check if incremental hash is equal to hash computed from scratch
+    fn incremental_hash_test() {
+        for _ in 0..NUMBER_OF_LOOPS_FOR_SMALL_TEST {
+            let mut gs = random_game_state(4);
+            let initial_hash = gs.state_hash();
+            let ids: Vec<_> = gs.players().iter().map(|p| p.id()).collect();
+            let vol = gs.terrain().volume();
+            let mut changes = vec![];
+
+            for _ in 0..20 {
+                let pos = vol
+                    .position_at(random_number(0, vol.volume() as usize))
+                    .unwrap();
+                let id = ids[random_number(0, ids.len())];
+                let mut cell = ClassicCellState::Empty;
+                cell.set_player_occupation(id, random_number(0, 4));
+
+                changes.push((pos, gs.cell_state(&pos).unwrap()));
+                assert!(gs.set_cell_state(&pos, &cell));
+                assert_eq!(gs.state_hash(), full_hash(&gs));
+
+                let current = ids[random_number(0, ids.len())];
+                assert!(gs.set_current_player(current));
+                assert_eq!(gs.state_hash(), full_hash(&gs));
+            }
+
+            // Revert all changes: hash shall be the initial one
+            for (pos, cell) in changes.iter().rev() {
+                gs.set_cell_state(pos, cell);
+            }
+            gs.set_current_player(ids[0]);
+            assert_eq!(gs.state_hash(), initial_hash);
+        }
+    }
+
+    #[test]
+    /// Check if equal states built in different order have same hash
+    fn order_independent_hash_test() {
+        let mut gs0 = random_game_state(2);
+        let mut gs1 = gs0.clone();
+        let ids: Vec<_> = gs0.players().iter().map(|p| p.id()).collect();
+        let positions: Vec<_> = gs0.terrain().volume().into_iter().collect();
+
+        for (i, pos) in positions.iter().enumerate() {
+            gs0.set_cell_state(pos, &ClassicCellState::Occupied(ids[i % 2], 1));
+        }
+        for (i, pos) in positions.iter().enumerate().rev() {
+            gs1.set_cell_state(pos, &ClassicCellState::Occupied(ids[i % 2], 1));
+        }
+        assert_eq!(gs0.state_hash(), gs1.state_hash());
+
+        gs1.set_cell_state(&positions[0], &ClassicCellState::Occupied(ids[0], 2));
+        assert_ne!(gs0.state_hash(), gs1.state_hash());
+    }
+
+    #[test]
+    /// Check if equal games have same hash also when their players have different IDs
+    fn player_id_independent_hash_test() {
+        let vol = Volume::new(&Position::zero(), &Position::new(2, 2, 0)).unwrap();
+        let terrain = Arc::new(Terrain::new(&vol));
+        let play = |players: &[PlayerInfo]| {
+            let mut gs = ClassicGameState::new(Arc::clone(&terrain), players).unwrap();
+            gs.set_cell_state(
+                &Position::zero(),
+                &ClassicCellState::Occupied(players[0].id(), 1),
+            );
+            gs.set_cell_state(
+                &Position::new(1, 1, 0),
+                &ClassicCellState::Occupied(players[1].id(), 2),
+            );
+            gs.set_current_player(players[1].id());
+            gs
+        };
+        let local = [
+            PlayerInfo::new("A", PlayerRage::Red, true),
+            PlayerInfo::new("B", PlayerRage::Blue, true),
+        ];
+        let remote = [
+            PlayerInfo::new("A", PlayerRage::Red, true),
+            PlayerInfo::new("B", PlayerRage::Blue, true),
+        ];
+        assert_ne!(local[0].id(), remote[0].id());
+
+        let (gs0, gs1) = (play(&local), play(&remote));
+        assert_eq!(gs0.state_hash(), gs1.state_hash());
+        assert_eq!(gs0.state_hash(), full_hash(&gs0));
+    }
+
+    #[test]
+    /// Check if invalid player and positions are rejected
+    fn invalid_test() {
+        let mut gs = random_game_state(2);
+        let hash = gs.state_hash();
+        let outside = gs.terrain().volume().top_right_corner() + Position::new(1, 1, 1);
+        let unknown = usize::MAX;
+
+        assert!(!gs.set_current_player(unknown));
+        assert!(!gs.set_cell_state(&outside, &ClassicCellState::Occupied(unknown, 1)));
+        assert!(!gs.set_cell_state(&Position::zero(), &ClassicCellState::Occupied(unknown, 1)));
+        assert!(gs.player_state(unknown).is_none());
+        assert!(!gs.set_player_state(unknown, &ClassicPlayerState::new(unknown)));
+        assert_eq!(gs.state_hash(), hash);
+    }
+
+    #[test]
+    /// Check if a cloned state shares unchanged cells with its predecessor
+    fn snapshot_test() {
+        let vol = Volume::new(&Position::zero(), &Position::new(15, 15, 3)).unwrap();
+        let players = [PlayerInfo::new("A", PlayerRage::Red, true)];
+        let gs0 = ClassicGameState::new(Arc::new(Terrain::new(&vol)), &players).unwrap();
+        let chunks = (vol.volume() as usize).div_ceil(CHUNK_SIZE);
+
+        let mut gs1 = gs0.clone();
+        let state = ClassicCellState::Occupied(players[0].id(), 1);
+        for position in &[Position::zero(), Position::new(1, 0, 0)] {
+            gs1.set_cell_state(position, &state);
+        }
+        assert_eq!(gs1.cells.shared_chunks(&gs0.cells), chunks - 1);
+        assert_eq!(gs0.occupied_cells().count(), 0);
+        assert_eq!(gs1.occupied_cells().count(), 2);
+        assert_eq!(gs1.state_hash(), full_hash(&gs1));
+    }
+
+    #[test]
+    /// Check if game state can be serialized and deserialized
+    fn serialize_test() {
+        let mut gs = random_game_state(3);
+        let corner = gs.terrain().volume().bottom_left_corner();
+        let owner = gs.players()[1].id();
+        gs.set_cell_state(&corner, &ClassicCellState::Occupied(owner, 2));
+        let serialized = serde_json::to_string(&gs).expect("Cannot serialize");
+        assert!(serialized.contains("current_player"));
+
+        let read: ClassicGameState = serde_json::from_str(&serialized).unwrap();
+        assert_eq!(read.state_hash(), gs.state_hash());
+        assert_eq!(read.players(), gs.players());
+        assert!(read.occupied_cells().eq(gs.occupied_cells()));
+
+        // Cells owned by unknown players are refused, as cells not matching the terrain
+        let mut value = serde_json::to_value(&gs).unwrap();
+        value["cells"][0] = serde_json::to_value(ClassicCellState::Occupied(new_id(), 1)).unwrap();
+        assert!(serde_json::from_value::<ClassicGameState>(value.clone()).is_err());
+        value["cells"].as_array_mut().unwrap().remove(0);
+        assert!(serde_json::from_value::<ClassicGameState>(value).is_err());
+    }
+}
@@ -1,16 +1,25 @@
 use crate::{
     common::{clone_arc::Clonable, serializable::Serializable},
-    generic_game::player::{PlayerId, PlayerState, Score},
+    generic_game::{
+        clock::PlayerClock,
+        player::{PlayerId, PlayerState, Score},
+    },
 };
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 
 /// Represents the state of a player in the classic atomz game
-#[derive(Debug, Clone, Copy, Serialize, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
 pub struct ClassicPlayerState {
     is_alive: bool,
     is_current: bool,
     score: Score,
     id: PlayerId,
+    /// Whether the player has made its first move
+    has_played: bool,
+    /// Points collected with captures and chain reactions
+    bonus: Score,
+    /// Time left to the player (meaningful only if game has a time control)
+    clock: PlayerClock,
 }
 
 impl Serializable for ClassicPlayerState {
@@ -18,7 +27,7 @@ impl Serializable for ClassicPlayerState {
 
     /// Returns a reference to the object itself, since it is serializable
     fn data_to_be_serialized(&self) -> &Self::Data {
-        &self
+        self
     }
 }
 
@@ -27,14 +36,52 @@ impl ClassicPlayerState {
     /// - Is not alive
     /// - Is not current
     /// - Score 0
-    fn new(id: PlayerId) -> Self {
+    /// - Has not played
+    /// - Bonus 0
+    /// - No time left on clock
+    pub fn new(id: PlayerId) -> Self {
         Self {
             is_alive: false,
             is_current: false,
             score: 0,
-            id: id,
+            id,
+            has_played: false,
+            bonus: 0,
+            clock: PlayerClock::default(),
         }
     }
+
+    /// Returns whether the player has made its first move.
+    ///
+    /// A player without atoms is eliminated only after its first move.
+    pub fn has_played(&self) -> bool {
+        self.has_played
+    }
+
+    /// Set whether the player has made its first move.
+    pub fn set_has_played(&mut self, has_played: bool) {
+        self.has_played = has_played;
+    }
+
+    /// Returns points collected with captures and chain reactions.
+    pub fn bonus(&self) -> Score {
+        self.bonus
+    }
+
+    /// Set points collected with captures and chain reactions.
+    pub fn set_bonus(&mut self, bonus: Score) {
+        self.bonus = bonus;
+    }
+
+    /// Returns the clock of the player (meaningful only if game has a time control).
+    pub fn clock(&self) -> PlayerClock {
+        self.clock
+    }
+
+    /// Set the clock of the player.
+    pub fn set_clock(&mut self, clock: PlayerClock) {
+        self.clock = clock;
+    }
 }
 
 impl Clonable for ClassicPlayerState {}
@@ -80,7 +127,7 @@ impl PlayerState for ClassicPlayerState {
 #[cfg(test)]
 mod tests {
     use crate::{
-        common::clone_arc::{ArcClone, Clonable},
+        common::clone_arc::ArcClone,
         generic_game::player::PlayerState,
         test_utilities::{
             constants::NUMBER_OF_LOOPS_FOR_NORMAL_TEST,
@@ -102,6 +149,9 @@ mod tests {
         let score = random_number(0, u32::MAX);
         default.set_score(score);
 
+        default.set_has_played(random_bool());
+        default.set_bonus(random_number(0, u32::MAX));
+
         default
     }
 
@@ -112,9 +162,11 @@ mod tests {
             let id = random_number(0, usize::MAX);
             let mut default = ClassicPlayerState::new(id);
 
-            assert_eq!(default.is_alive(), false);
-            assert_eq!(default.is_current(), false);
+            assert!(!default.is_alive());
+            assert!(!default.is_current());
             assert_eq!(default.score(), 0);
+            assert!(!default.has_played());
+            assert_eq!(default.bonus(), 0);
 
             let is_alive = random_bool();
             default.set_is_alive(is_alive);
@@ -128,6 +180,14 @@ mod tests {
             default.set_score(score);
             assert_eq!(default.score(), score);
 
+            let has_played = random_bool();
+            default.set_has_played(has_played);
+            assert_eq!(default.has_played(), has_played);
+
+            let bonus = random_number(0, u32::MAX);
+            default.set_bonus(bonus);
+            assert_eq!(default.bonus(), bonus);
+
             assert_eq!(default.id(), id);
         }
     }
@@ -0,0 +1,1241 @@
+use std::{collections::HashSet, sync::Arc};
+
+use log::warn;
+
+use crate::{
+    generic_game::{
+        action::Action,
+        actor::Actor,
+        cell_state::{CellOccupation, CellState},
+        clock::TimeoutPolicy,
+        game_rule::{BasicRules, GameRule},
+        game_state::GameState,
+        player::{PlayerId, PlayerState},
+        zobrist::StateHash,
+    },
+    geometry::{
+        terrain::{CellMaterial, Terrain},
+        vector::{Distance, Position},
+    },
+};
+
+use super::{
+    action::{ClassicAction, ClassicActionKind},
+    basic_rules::ClassicBasicRules,
+    cell_state::ClassicCellState,
+    game_state::ClassicGameState,
+    rules_config::{ClassicRulesConfig, Placement},
+    timeline::{Capture, Fall, Flight, Timeline, Wave},
+};
+
+/// Max number of explosion waves of a chain reaction, for each position of the terrain.
+///
+/// Some chain reactions never end (e.g. two cells with one neighbour each): they are
+/// stopped as soon as a state repeats, or after this number of waves.
+const MAX_WAVES_PER_POSITION: usize = 4;
+
+/// Offsets of the neighbours of a cell
+const NEIGHBOUR_OFFSETS: [(i32, i32, i32); 6] = [
+    (-1, 0, 0),
+    (1, 0, 0),
+    (0, -1, 0),
+    (0, 1, 0),
+    (0, 0, -1),
+    (0, 0, 1),
+];
+
+/// Rules of the classic atomz game.
+///
+/// - Current player adds an atom to an empty cell or to one of its cells.
+/// - When a cell reaches its critical mass (number of neighbour cells) it explodes:
+///   it sends an atom to each neighbour, capturing it.
+/// - Cell materials change these rules according to `MaterialRules`.
+/// - Atoms stay inside cells or on top of `Flat` cells according to `Placement`.
+/// - Neighbours are found across the bounduaries of wrapping axes according to `Topology`.
+/// - Allied cells are captured only if `ClassicRulesConfig::capture_allied_cells` is set.
+/// - A player running out of time (see `TimeControl`) is handled according to `TimeoutPolicy`.
+/// - After each move scores are updated, players without atoms (after their first move)
+///   are eliminated and next alive player becomes the current one.
+#[derive(Debug, Clone, Default)]
+pub struct ClassicRules {
+    config: ClassicRulesConfig,
+}
+
+/// Result of a chain reaction
+struct ChainReaction {
+    /// Number of cells captured
+    captures: usize,
+    /// Number of explosions
+    explosions: usize,
+}
+
+/// Game states at the end of each substep of a chain reaction
+pub type Substeps = Vec<Arc<ClassicGameState>>;
+
+/// What is recorded while an action is executed
+struct Trace {
+    /// Animation timeline of the chain reaction
+    timeline: Timeline,
+    /// Game state at the end of each substep of the chain reaction (`None` if not recorded)
+    substeps: Option<Substeps>,
+}
+
+impl Trace {
+    /// Record the game state reached at the end of a substep (if substeps are recorded)
+    fn end_substep(&mut self, game_state: &ClassicGameState) {
+        if let Some(substeps) = &mut self.substeps {
+            substeps.push(Arc::new(game_state.clone()));
+        }
+    }
+}
+
+impl ClassicRules {
+    /// Create rules with provided configuration
+    pub fn new(config: ClassicRulesConfig) -> Self {
+        Self { config }
+    }
+
+    /// Returns configuration of rules
+    pub fn config(&self) -> &ClassicRulesConfig {
+        &self.config
+    }
+
+    /// Returns whether atoms can stay at provided position of the terrain
+    pub fn is_board_cell(&self, terrain: &Terrain, position: &Position) -> bool {
+        match self.config.placement() {
+            Placement::InsideCells => {
+                terrain.volume().is_inside(position) && terrain.get_cell_at(position).is_some()
+            }
+            Placement::OnTop => terrain.is_playable(position),
+            Placement::Gravity => terrain.landing_position(position).is_some(),
+        }
+    }
+
+    /// Returns whether players can place atoms at provided position of the terrain
+    pub fn is_playable(&self, terrain: &Terrain, position: &Position) -> bool {
+        self.is_board_cell(terrain, position)
+            && !(self.config.materials().no_placement_on_water()
+                && self.is_water(terrain, position))
+    }
+
+    /// Returns the cells receiving an atom when the cell at provided position explodes
+    pub fn neighbours(&self, terrain: &Terrain, position: &Position) -> Vec<Position> {
+        let water_blocks = self.config.materials().water_blocks_propagation();
+
+        let topology = self.config.topology();
+        let mut neighbours = vec![];
+
+        for (x, y, z) in &NEIGHBOUR_OFFSETS {
+            let neighbour =
+                topology.translate(&terrain.volume(), position, &Distance::new(*x, *y, *z));
+            // On wrapping axes with size 1 or 2 a neighbour is found twice (or it is the cell itself)
+            if let Some(neighbour) = neighbour.filter(|p| p != position && !neighbours.contains(p))
+            {
+                if self.is_board_cell(terrain, &neighbour)
+                    && !(water_blocks && self.is_water(terrain, &neighbour))
+                {
+                    neighbours.push(neighbour);
+                }
+            }
+        }
+
+        neighbours
+    }
+
+    /// Returns the number of atoms that makes the cell at provided position explode.
+    ///
+    /// `0` means the cell never explodes (it has no neighbours).
+    pub fn critical_mass(&self, terrain: &Terrain, position: &Position) -> CellOccupation {
+        let neighbours = self.neighbours(terrain, position).len() as CellOccupation;
+
+        if neighbours > 0 && self.is_water(terrain, position) {
+            let reduction = self.config.materials().water_critical_mass_reduction();
+            neighbours.saturating_sub(reduction).max(1)
+        } else {
+            neighbours
+        }
+    }
+
+    /// Returns whether cell at provided position is made of water.
+    ///
+    /// When atoms stay on top of cells, the material is the one of the supporting cell.
+    fn is_water(&self, terrain: &Terrain, position: &Position) -> bool {
+        let cell = match self.config.placement() {
+            Placement::InsideCells => Some(*position),
+            Placement::OnTop | Placement::Gravity => {
+                terrain.landing_position(position).map(|p| p.below())
+            }
+        };
+
+        cell.filter(|c| terrain.volume().is_inside(c))
+            .and_then(|c| terrain.get_cell_at(&c))
+            .is_some_and(|c| c.material() == CellMaterial::Water)
+    }
+
+    /// Returns whether `player` can place an atom at provided position
+    fn can_place(
+        &self,
+        game_state: &ClassicGameState,
+        player: PlayerId,
+        position: &Position,
+    ) -> bool {
+        let terrain = game_state.terrain();
+
+        self.is_playable(&terrain, position)
+            && match game_state.cell_state(position) {
+                Some(ClassicCellState::Empty) => self.is_supported(game_state, position),
+                Some(ClassicCellState::Occupied(owner, _)) => owner == player,
+                None => false,
+            }
+    }
+
+    /// Returns whether an atom placed at provided position does not fall
+    /// (always, unless `Placement::Gravity` is used)
+    fn is_supported(&self, game_state: &ClassicGameState, position: &Position) -> bool {
+        let below = position.below();
+
+        self.config.placement() != Placement::Gravity
+            || !self.is_board_cell(&game_state.terrain(), &below)
+            || matches!(
+                game_state.cell_state(&below),
+                Some(ClassicCellState::Occupied(..))
+            )
+    }
+
+    /// Returns all positions where current player can place an atom
+    pub fn legal_positions(&self, game_state: &ClassicGameState) -> Vec<Position> {
+        let player = game_state.current_player();
+
+        game_state
+            .terrain()
+            .volume()
+            .into_iter()
+            .filter(|p| self.can_place(game_state, player, p))
+            .collect()
+    }
+
+    /// Returns a pseudo-random legal position for current player, played on its behalf
+    /// (e.g. when it runs out of time).
+    ///
+    /// Position is chosen with the state hash, so that replays are deterministic.
+    /// `None` if there are no legal positions.
+    pub fn fallback_position(&self, game_state: &ClassicGameState) -> Option<Position> {
+        let legal = self.legal_positions(game_state);
+        if legal.is_empty() {
+            return None;
+        }
+        let index = game_state.state_hash() % legal.len() as StateHash;
+        Some(legal[index as usize])
+    }
+
+    /// Execute an action on a specified game state (see `GameRule::execute`),
+    /// also returning the animation timeline of the chain reaction it causes
+    pub fn execute_with_timeline(
+        &self,
+        game_state: Arc<ClassicGameState>,
+        action: Arc<ClassicAction>,
+    ) -> (Arc<ClassicGameState>, Vec<Arc<ClassicAction>>, Timeline) {
+        let (game_state, reactions, trace) = self.trace(game_state, action, false);
+        (game_state, reactions, trace.timeline)
+    }
+
+    /// Execute an action on a specified game state (see `execute_with_timeline`), also
+    /// returning the game state at the end of each substep of the chain reaction it causes
+    /// (the first one right after the atom is placed, empty if no atom is placed)
+    pub fn execute_with_substeps(
+        &self,
+        game_state: Arc<ClassicGameState>,
+        action: Arc<ClassicAction>,
+    ) -> (
+        Arc<ClassicGameState>,
+        Vec<Arc<ClassicAction>>,
+        Timeline,
+        Substeps,
+    ) {
+        let (game_state, reactions, trace) = self.trace(game_state, action, true);
+        (
+            game_state,
+            reactions,
+            trace.timeline,
+            trace.substeps.unwrap_or_default(),
+        )
+    }
+
+    /// Execute an action, recording its trace (with substep states if `substeps` is set)
+    fn trace(
+        &self,
+        game_state: Arc<ClassicGameState>,
+        action: Arc<ClassicAction>,
+        substeps: bool,
+    ) -> (Arc<ClassicGameState>, Vec<Arc<ClassicAction>>, Trace) {
+        let mut trace = Trace {
+            timeline: Timeline::new(game_state.current_turn()),
+            substeps: if substeps { Some(vec![]) } else { None },
+        };
+        if !self.is_valid(&game_state, &action) {
+            warn!("Invalid action {:?}", action);
+            return (game_state, vec![], trace);
+        }
+
+        let mut gs = (*game_state).clone();
+        let mut reactions = vec![];
+        match action.kind() {
+            ClassicActionKind::PlaceAtom(position) => {
+                self.place_atom(&mut gs, position, &mut reactions, &mut trace)
+            }
+            ClassicActionKind::Timeout(player) => {
+                self.timeout(&mut gs, player, &mut reactions, &mut trace)
+            }
+            _ => {}
+        }
+
+        (
+            Arc::new(gs),
+            reactions.into_iter().map(Arc::new).collect(),
+            trace,
+        )
+    }
+
+    /// Make atoms fall down on the cell below them, when it is empty
+    /// (only with `Placement::Gravity`).
+    ///
+    /// Returns the positions reached by falling atoms, which are added to the wave.
+    fn apply_gravity(
+        &self,
+        game_state: &mut ClassicGameState,
+        reactions: &mut Vec<ClassicAction>,
+        wave: &mut Wave,
+    ) -> Vec<Position> {
+        if self.config.placement() != Placement::Gravity {
+            return vec![];
+        }
+        let terrain = game_state.terrain();
+        let mut landed = vec![];
+
+        // Volume order is bottom-up: lower atoms land before the upper ones
+        for position in &terrain.volume() {
+            let cell = match game_state.cell_state(&position) {
+                Some(cell @ ClassicCellState::Occupied(..)) => cell,
+                _ => continue,
+            };
+            let mut target = position;
+            while self.is_board_cell(&terrain, &target.below())
+                && game_state.cell_state(&target.below()) == Some(ClassicCellState::Empty)
+            {
+                target = target.below();
+            }
+
+            if target != position {
+                if let ClassicCellState::Occupied(player, _) = cell {
+                    reactions.push(ClassicAction::reaction(
+                        game_state.current_turn(),
+                        game_state.current_turn_substep(),
+                        ClassicActionKind::Fall {
+                            from: position,
+                            to: target,
+                            player,
+                        },
+                    ));
+                    wave.add_fall(Fall::new(position, target, player));
+                }
+                game_state.set_cell_state(&position, &ClassicCellState::Empty);
+                game_state.set_cell_state(&target, &cell);
+                landed.push(target);
+            }
+        }
+
+        landed
+    }
+
+    /// Returns whether provided cell exceeded its critical mass
+    fn is_unstable(&self, terrain: &Terrain, position: &Position, cell: &ClassicCellState) -> bool {
+        let critical_mass = self.critical_mass(terrain, position);
+        match cell {
+            ClassicCellState::Occupied(_, count) => critical_mass > 0 && *count >= critical_mass,
+            ClassicCellState::Empty => false,
+        }
+    }
+
+    /// Returns whether all atoms belong to `player` (or its allies) and all other players
+    /// have already played
+    fn is_decided(&self, game_state: &ClassicGameState, player: PlayerId) -> bool {
+        game_state.occupied_cells().all(|(_, cell)| match cell {
+            ClassicCellState::Occupied(owner, _) => game_state.are_allies(player, *owner),
+            ClassicCellState::Empty => true,
+        }) && game_state
+            .players()
+            .iter()
+            .all(|p| p.id() == player || p.has_played() || !p.is_alive())
+    }
+
+    /// Make unstable cells explode, until all cells are stable.
+    ///
+    /// Each wave of explosions is a substep of the current turn, added to the timeline of
+    /// `trace` (along with the game state at the end of each substep, if recorded).
+    ///
+    /// * `origin` - Position of the cell that may start the chain reaction
+    fn chain_reaction(
+        &self,
+        game_state: &mut ClassicGameState,
+        origin: Position,
+        reactions: &mut Vec<ClassicAction>,
+        trace: &mut Trace,
+    ) -> ChainReaction {
+        let terrain = game_state.terrain();
+        let volume = terrain.volume();
+        let turn = game_state.current_turn();
+        let max_waves = MAX_WAVES_PER_POSITION * volume.volume() as usize;
+        let mut result = ChainReaction {
+            captures: 0,
+            explosions: 0,
+        };
+        // Cells that may explode in next wave
+        let mut candidates = vec![origin];
+        // States reached after each wave
+        let mut visited = HashSet::new();
+        trace.end_substep(game_state);
+
+        for substep in 1..=max_waves {
+            // Cells exploding in this wave (in volume order, to be deterministic)
+            candidates.sort_by_key(|p| volume.index_of(p));
+            candidates.dedup();
+            let unstable: Vec<_> = candidates
+                .drain(..)
+                .filter(|p| {
+                    game_state
+                        .cell_state(p)
+                        .is_some_and(|c| self.is_unstable(&terrain, p, &c))
+                })
+                .collect();
+
+            let current_player = game_state.current_player();
+            if unstable.is_empty()
+                || self.is_decided(game_state, current_player)
+                || !visited.insert(game_state.state_hash())
+            {
+                break;
+            }
+            game_state.set_current_turn_substep(substep);
+            let mut wave = Wave::new(substep);
+
+            for position in unstable {
+                let (owner, count) = match game_state.cell_state(&position) {
+                    Some(ClassicCellState::Occupied(owner, count)) => (owner, count),
+                    _ => continue,
+                };
+                // Each neighbour receives one of the exploding atoms (when critical mass is
+                // reduced only the first ones do, so atoms are never created)
+                let critical_mass = self.critical_mass(&terrain, &position);
+                let neighbours: Vec<_> = self
+                    .neighbours(&terrain, &position)
+                    .into_iter()
+                    .take(critical_mass as usize)
+                    .collect();
+                let remaining = count.saturating_sub(critical_mass);
+
+                let mut exploded = ClassicCellState::Empty;
+                exploded.set_player_occupation(owner, remaining);
+                game_state.set_cell_state(&position, &exploded);
+                reactions.push(ClassicAction::reaction(
+                    turn,
+                    substep,
+                    ClassicActionKind::Explode {
+                        position,
+                        player: owner,
+                    },
+                ));
+                result.explosions += 1;
+                candidates.push(position);
+                wave.add_explosion(position);
+
+                for neighbour in neighbours {
+                    wave.add_flight(Flight::new(position, neighbour, owner));
+                    let (owner, count) = match game_state.cell_state(&neighbour) {
+                        // Allied cells keep their owner, unless they can be captured
+                        Some(ClassicCellState::Occupied(previous, count))
+                            if previous != owner
+                                && !self.config.capture_allied_cells()
+                                && game_state.are_allies(owner, previous) =>
+                        {
+                            (previous, count)
+                        }
+                        Some(ClassicCellState::Occupied(previous, count)) => {
+                            if previous != owner {
+                                reactions.push(ClassicAction::reaction(
+                                    turn,
+                                    substep,
+                                    ClassicActionKind::Capture {
+                                        position: neighbour,
+                                        from: previous,
+                                        to: owner,
+                                    },
+                                ));
+                                result.captures += 1;
+                                wave.add_capture(Capture::new(neighbour, previous, owner));
+                            }
+                            (owner, count)
+                        }
+                        _ => (owner, 0),
+                    };
+                    game_state.set_cell_state(
+                        &neighbour,
+                        &ClassicCellState::Occupied(owner, count.saturating_add(1)),
+                    );
+                    candidates.push(neighbour);
+                }
+            }
+            candidates.extend(self.apply_gravity(game_state, reactions, &mut wave));
+            trace.timeline.push(wave);
+            trace.end_substep(game_state);
+        }
+
+        result
+    }
+
+    /// Update score of all players, and eliminate players without atoms
+    fn update_players(
+        &self,
+        game_state: &mut ClassicGameState,
+        reactions: &mut Vec<ClassicAction>,
+    ) {
+        let scoring = self.config.scoring();
+        let players = game_state.players().to_vec();
+
+        for mut player in players {
+            let atoms = game_state.player_atoms(player.id());
+            let cells = game_state.player_cells(player.id());
+            player.set_score(scoring.score(atoms, cells, player.bonus()));
+
+            if player.is_alive() && player.has_played() && atoms == 0 {
+                player.set_is_alive(false);
+                reactions.push(ClassicAction::reaction(
+                    game_state.current_turn(),
+                    game_state.current_turn_substep(),
+                    ClassicActionKind::Eliminate(player.id()),
+                ));
+            }
+            game_state.set_player_state(player.id(), &player);
+        }
+    }
+
+    /// Current player places an atom at provided position, then turn passes to next player
+    fn place_atom(
+        &self,
+        game_state: &mut ClassicGameState,
+        position: Position,
+        reactions: &mut Vec<ClassicAction>,
+        trace: &mut Trace,
+    ) {
+        let player = game_state.current_player();
+
+        // Add the atom
+        let count = game_state
+            .cell_state(&position)
+            .and_then(|c| c.player_occupation(player))
+            .unwrap_or(0);
+        game_state.set_cell_state(
+            &position,
+            &ClassicCellState::Occupied(player, count.saturating_add(1)),
+        );
+
+        let chain = self.chain_reaction(game_state, position, reactions, trace);
+
+        if let Some(mut state) = game_state.player_state(player) {
+            let bonus = self
+                .config
+                .scoring()
+                .move_bonus(chain.captures, chain.explosions);
+            state.set_has_played(true);
+            state.set_bonus(state.bonus().saturating_add(bonus));
+            if let Some(time_control) = game_state.time_control() {
+                let mut clock = state.clock();
+                clock.end_move(&time_control);
+                state.set_clock(clock);
+            }
+            game_state.set_player_state(player, &state);
+        }
+        self.update_players(game_state, reactions);
+        self.advance_turn(game_state);
+    }
+
+    /// Apply the `TimeoutPolicy` of the game to current player, that ran out of time
+    fn timeout(
+        &self,
+        game_state: &mut ClassicGameState,
+        player: PlayerId,
+        reactions: &mut Vec<ClassicAction>,
+        trace: &mut Trace,
+    ) {
+        let time_control = game_state.time_control();
+        let policy = time_control.map_or(TimeoutPolicy::Skip, |c| c.on_timeout());
+
+        if let Some(position) = self
+            .fallback_position(game_state)
+            .filter(|_| policy == TimeoutPolicy::RandomMove)
+        {
+            // Atom is placed by the controller, on behalf of the timed out player
+            reactions.push(ClassicAction::reaction(
+                game_state.current_turn(),
+                0,
+                ClassicActionKind::Timeout(player),
+            ));
+            reactions.push(ClassicAction::reaction(
+                game_state.current_turn(),
+                0,
+                ClassicActionKind::PlaceAtom(position),
+            ));
+            self.place_atom(game_state, position, reactions, trace);
+            return;
+        }
+
+        if let Some(mut state) = game_state.player_state(player) {
+            let mut clock = state.clock();
+            // Skipped player gets the increment, otherwise an empty bank would time out
+            // all its next turns
+            match time_control.filter(|_| policy == TimeoutPolicy::Skip) {
+                Some(time_control) => clock.end_move(&time_control),
+                None => clock.restart_move(),
+            }
+            state.set_clock(clock);
+            if policy == TimeoutPolicy::Forfeit {
+                state.set_is_alive(false);
+                reactions.push(ClassicAction::reaction(
+                    game_state.current_turn(),
+                    0,
+                    ClassicActionKind::Eliminate(player),
+                ));
+            }
+            game_state.set_player_state(player, &state);
+        }
+        self.update_players(game_state, reactions);
+        self.advance_turn(game_state);
+    }
+
+    /// Pass the turn to the next alive player (according to `ClassicBasicRules`)
+    fn advance_turn(&self, game_state: &mut ClassicGameState) {
+        let basic_rules = ClassicBasicRules::new(self.config);
+        let players = ClassicBasicRules::players_of(game_state);
+
+        if let Some(next) = basic_rules.next_player(game_state, players) {
+            game_state.set_current_player(next);
+        }
+        game_state.set_current_turn(game_state.current_turn() + 1);
+        game_state.set_current_turn_substep(0);
+    }
+}
+
+impl GameRule for ClassicRules {
+    type GS = ClassicGameState;
+    type A = ClassicAction;
+
+    /// Check whether the specified action is valid in the specified game state.
+    ///
+    /// Current player (alive) can place an atom in its turn in an empty cell or in one of its cells.
+    /// Controller can notify current player ran out of time.
+    fn is_valid(&self, game_state: &Self::GS, action: &Self::A) -> bool {
+        let player = game_state.current_player();
+        let is_alive = game_state
+            .player_state(player)
+            .is_some_and(|p| p.is_alive());
+        let is_turn = is_alive && action.turn() == game_state.current_turn();
+
+        match action.kind() {
+            ClassicActionKind::PlaceAtom(position) => {
+                is_turn
+                    && action.source() == Actor::Player(player)
+                    && self.can_place(game_state, player, &position)
+            }
+            ClassicActionKind::Timeout(timed_out) => {
+                is_turn
+                    && action.source() == Actor::Controller
+                    && timed_out == player
+                    && game_state.is_timed_out(player)
+            }
+            _ => false,
+        }
+    }
+
+    /// Execute an action on a specified game state
+    ///
+    /// Returns the new game state (next player turn) and the list of explosions,
+    /// captures and eliminations caused by the action.
+    /// Invalid actions do not change the game state.
+    fn execute(
+        &self,
+        game_state: Arc<Self::GS>,
+        action: Arc<Self::A>,
+    ) -> (Arc<Self::GS>, Vec<Arc<Self::A>>) {
+        let (game_state, reactions, _) = self.execute_with_timeline(game_state, action);
+        (game_state, reactions)
+    }
+
+    /// Returns `true` if this rule can manage the provided action.
+    fn can_handle(&self, action: &Self::A) -> bool {
+        matches!(
+            action.kind(),
+            ClassicActionKind::PlaceAtom(_) | ClassicActionKind::Timeout(_)
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        classic_game::{rules_config::MaterialRules, scoring::ScoringPolicy},
+        generic_game::team::{Team, Teams},
+        geometry::{terrain::CellType, topology::Topology, volume::Volume},
+        test_utilities::{
+            constants::NUMBER_OF_LOOPS_FOR_TINY_TEST,
+            game::players,
+            random_generator::{random_number, random_terrain, random_volume},
+        },
+    };
+
+    /// Create a terrain with cells at provided positions
+    fn terrain_with(cells: &[Position]) -> Arc<Terrain> {
+        let vol = Volume::new(&Position::new(-1, -1, -1), &Position::new(4, 4, 4)).unwrap();
+        let mut terrain = Terrain::new(&vol);
+        for p in cells {
+            terrain.set_cell_at(p, CellType::Flat(CellMaterial::Ground));
+        }
+        Arc::new(terrain)
+    }
+
+    /// Create a 2x2 square terrain
+    fn square() -> Arc<Terrain> {
+        terrain_with(&[
+            Position::new(0, 0, 0),
+            Position::new(1, 0, 0),
+            Position::new(0, 1, 0),
+            Position::new(1, 1, 0),
+        ])
+    }
+
+    /// Current player places an atom
+    fn place(
+        rules: &ClassicRules,
+        gs: Arc<ClassicGameState>,
+        position: Position,
+    ) -> (Arc<ClassicGameState>, Vec<Arc<ClassicAction>>) {
+        let action = ClassicAction::place_atom(gs.current_player(), gs.current_turn(), position);
+        assert!(rules.is_valid(&gs, &action), "{:?}", action);
+        rules.execute(gs, Arc::new(action))
+    }
+
+    #[test]
+    /// Check neighbours and critical mass
+    fn critical_mass_test() {
+        let rules = ClassicRules::default();
+        let terrain = square();
+        for (p, _) in terrain.as_ref() {
+            assert_eq!(rules.critical_mass(&terrain, &p), 2, "{:?}", p);
+        }
+
+        let cube = Volume::new(&Position::zero(), &Position::new(2, 2, 2)).unwrap();
+        let terrain = terrain_with(&cube.into_iter().collect::<Vec<_>>());
+        assert_eq!(rules.critical_mass(&terrain, &Position::new(0, 0, 0)), 3);
+        assert_eq!(rules.critical_mass(&terrain, &Position::new(1, 0, 0)), 4);
+        assert_eq!(rules.critical_mass(&terrain, &Position::new(1, 1, 0)), 5);
+        assert_eq!(rules.critical_mass(&terrain, &Position::new(1, 1, 1)), 6);
+    }
+
+    #[test]
+    /// Check which actions are valid
+    fn is_valid_test() {
+        let rules = ClassicRules::default();
+        let p = players(2);
+        let gs = Arc::new(ClassicGameState::new(square(), &p).unwrap());
+        let cell = Position::zero();
+
+        assert!(rules.is_valid(&gs, &ClassicAction::place_atom(p[0].id(), 0, cell)));
+        // Wrong player
+        assert!(!rules.is_valid(&gs, &ClassicAction::place_atom(p[1].id(), 0, cell)));
+        // Wrong turn
+        assert!(!rules.is_valid(&gs, &ClassicAction::place_atom(p[0].id(), 1, cell)));
+        // Not a cell of the board
+        let hole = Position::new(2, 2, 2);
+        assert!(!rules.is_valid(&gs, &ClassicAction::place_atom(p[0].id(), 0, hole)));
+        // Not a placement
+        let explode = ClassicAction::reaction(
+            0,
+            0,
+            ClassicActionKind::Explode {
+                position: cell,
+                player: p[0].id(),
+            },
+        );
+        assert!(!rules.is_valid(&gs, &explode));
+        assert!(!rules.can_handle(&explode));
+
+        // Cell of another player
+        let (gs, _) = place(&rules, gs, cell);
+        assert!(!rules.is_valid(&gs, &ClassicAction::place_atom(p[1].id(), 1, cell)));
+
+        // Invalid actions do not change the state
+        let (same, reactions) = rules.execute(
+            Arc::clone(&gs),
+            Arc::new(ClassicAction::place_atom(p[1].id(), 1, cell)),
+        );
+        assert!(Arc::ptr_eq(&gs, &same));
+        assert!(reactions.is_empty());
+    }
+
+    #[test]
+    /// Check placement without explosions
+    fn place_test() {
+        let rules = ClassicRules::default();
+        let p = players(3);
+        let gs = Arc::new(ClassicGameState::new(square(), &p).unwrap());
+
+        let (gs, reactions) = place(&rules, gs, Position::zero());
+        assert!(reactions.is_empty());
+        assert_eq!(
+            gs.cell_state(&Position::zero()),
+            Some(ClassicCellState::Occupied(p[0].id(), 1))
+        );
+        assert_eq!(gs.current_player(), p[1].id());
+        assert_eq!(gs.current_turn(), 1);
+
+        let first = gs.player_state(p[0].id()).unwrap();
+        assert!(first.has_played());
+        assert_eq!(first.score(), 1);
+        // Players who have not played yet are not eliminated
+        assert!(gs.players().iter().all(|p| p.is_alive()));
+    }
+
+    #[test]
+    /// Check explosion, capture, elimination and scoring
+    fn explosion_test() {
+        let mut config = ClassicRulesConfig::default();
+        config.set_scoring(ScoringPolicy::new(1, 0, 10, 100));
+        let rules = ClassicRules::new(config);
+        let p = players(2);
+        let gs = Arc::new(ClassicGameState::new(square(), &p).unwrap());
+
+        let (gs, _) = place(&rules, gs, Position::new(0, 0, 0));
+        let (gs, _) = place(&rules, gs, Position::new(1, 0, 0));
+        // Second atom in a corner: explosion captures opponent cell and it is eliminated
+        let (gs, reactions) = place(&rules, gs, Position::new(0, 0, 0));
+
+        let kinds: Vec<_> = reactions.iter().map(|a| a.kind()).collect();
+        assert_eq!(
+            kinds[0],
+            ClassicActionKind::Explode {
+                position: Position::new(0, 0, 0),
+                player: p[0].id()
+            }
+        );
+        assert!(kinds.contains(&ClassicActionKind::Capture {
+            position: Position::new(1, 0, 0),
+            from: p[1].id(),
+            to: p[0].id()
+        }));
+        assert_eq!(kinds.last(), Some(&ClassicActionKind::Eliminate(p[1].id())));
+        assert!(reactions
+            .iter()
+            .all(|a| a.turn() == 2 && a.turn_substep() > 0));
+        assert!(reactions.iter().all(|a| a.source() == Actor::Controller));
+
+        let winner = gs.player_state(p[0].id()).unwrap();
+        let loser = gs.player_state(p[1].id()).unwrap();
+        assert!(winner.is_alive());
+        assert!(!loser.is_alive());
+        assert_eq!(loser.score(), 0);
+        // 3 atoms + 1 capture, no chain
+        assert_eq!(gs.player_atoms(p[0].id()), 3);
+        assert_eq!(winner.score(), 3 + 10);
+        assert_eq!(gs.current_player(), p[0].id());
+    }
+
+    /// Create a line of 4 cells along X, where the third one is made of water
+    fn line_with_water() -> Arc<Terrain> {
+        let vol = Volume::new(&Position::new(-1, -1, -1), &Position::new(4, 4, 4)).unwrap();
+        let mut terrain = Terrain::new(&vol);
+        for x in 0..4 {
+            let material = if x == 2 {
+                CellMaterial::Water
+            } else {
+                CellMaterial::Ground
+            };
+            terrain.set_cell_at(&Position::new(x, 0, 0), CellType::Fill(material));
+        }
+        Arc::new(terrain)
+    }
+
+    fn rules_with(materials: MaterialRules) -> ClassicRules {
+        let mut config = ClassicRulesConfig::default();
+        config.set_materials(materials);
+        ClassicRules::new(config)
+    }
+
+    #[test]
+    /// Check if materials do not change rules by default
+    fn default_materials_test() {
+        let rules = ClassicRules::default();
+        let terrain = line_with_water();
+        let water = Position::new(2, 0, 0);
+
+        assert!(rules.is_playable(&terrain, &water));
+        assert_eq!(rules.critical_mass(&terrain, &water), 2);
+        assert_eq!(rules.neighbours(&terrain, &Position::new(1, 0, 0)).len(), 2);
+    }
+
+    #[test]
+    /// Check if atoms cannot be placed on water
+    fn no_placement_on_water_test() {
+        let rules = rules_with(MaterialRules::new(true, 0, false));
+        let terrain = line_with_water();
+        let water = Position::new(2, 0, 0);
+        let gs = ClassicGameState::new(Arc::clone(&terrain), &players(2)).unwrap();
+
+        assert!(!rules.is_playable(&terrain, &water));
+        assert!(rules.is_board_cell(&terrain, &water));
+        assert!(!rules.legal_positions(&gs).contains(&water));
+        assert_eq!(rules.legal_positions(&gs).len(), 3);
+        let action = ClassicAction::place_atom(gs.current_player(), 0, water);
+        assert!(!rules.is_valid(&gs, &action));
+    }
+
+    #[test]
+    /// Check if water cells explode earlier without creating atoms
+    fn water_critical_mass_test() {
+        let rules = rules_with(MaterialRules::new(false, 1, false));
+        let terrain = line_with_water();
+        let water = Position::new(2, 0, 0);
+        assert_eq!(rules.critical_mass(&terrain, &water), 1);
+        assert_eq!(rules.critical_mass(&terrain, &Position::new(1, 0, 0)), 2);
+        // Never less than 1
+        let rules = rules_with(MaterialRules::new(false, 10, false));
+        assert_eq!(rules.critical_mass(&terrain, &water), 1);
+
+        let p = players(2);
+        let gs = Arc::new(ClassicGameState::new(terrain, &p).unwrap());
+        let (gs, reactions) = place(&rules, gs, water);
+        // The only atom explodes and goes to the first neighbour
+        assert_eq!(reactions.len(), 1);
+        assert_eq!(gs.cell_state(&water), Some(ClassicCellState::Empty));
+        assert_eq!(
+            gs.cell_state(&Position::new(1, 0, 0)),
+            Some(ClassicCellState::Occupied(p[0].id(), 1))
+        );
+        assert_eq!(gs.player_atoms(p[0].id()), 1);
+    }
+
+    #[test]
+    /// Check if chain reactions do not cross water
+    fn water_blocks_propagation_test() {
+        let rules = rules_with(MaterialRules::new(false, 0, true));
+        let terrain = line_with_water();
+        let p = players(2);
+
+        // Cell next to water has only one neighbour
+        assert_eq!(rules.neighbours(&terrain, &Position::new(1, 0, 0)).len(), 1);
+        assert_eq!(rules.critical_mass(&terrain, &Position::new(1, 0, 0)), 1);
+        // Water cell sends atoms to ground cells
+        assert_eq!(rules.critical_mass(&terrain, &Position::new(2, 0, 0)), 2);
+
+        let gs = Arc::new(ClassicGameState::new(terrain, &p).unwrap());
+        let (gs, _) = place(&rules, gs, Position::new(3, 0, 0));
+        let (gs, reactions) = place(&rules, gs, Position::new(1, 0, 0));
+        // Explosion in (1, 0, 0) reaches (0, 0, 0) and bounces back, never reaching water
+        assert!(!reactions.is_empty());
+        assert_eq!(
+            gs.cell_state(&Position::new(2, 0, 0)),
+            Some(ClassicCellState::Empty)
+        );
+        assert_eq!(
+            gs.cell_state(&Position::new(3, 0, 0)),
+            Some(ClassicCellState::Occupied(p[0].id(), 1))
+        );
+    }
+
+    fn rules_with_placement(placement: Placement) -> ClassicRules {
+        let mut config = ClassicRulesConfig::default();
+        config.set_placement(placement);
+        ClassicRules::new(config)
+    }
+
+    #[test]
+    /// Check if atoms are placed on top of flat cells
+    fn on_top_test() {
+        let rules = rules_with_placement(Placement::OnTop);
+        let terrain = square();
+        let gs = ClassicGameState::new(Arc::clone(&terrain), &players(2)).unwrap();
+
+        assert_eq!(rules.legal_positions(&gs), terrain.playable_positions());
+        assert!(!rules.is_board_cell(&terrain, &Position::zero()));
+        // Cells on top of the square are neighbours on the same layer
+        let top = Position::new(0, 0, 1);
+        assert!(rules.is_board_cell(&terrain, &top));
+        assert_eq!(rules.critical_mass(&terrain, &top), 2);
+
+        // Fill cells do not support anything
+        let mut terrain = (*terrain).clone();
+        terrain.set_cell_at(&Position::zero(), CellType::Fill(CellMaterial::Ground));
+        assert!(!rules.is_board_cell(&terrain, &top));
+        assert_eq!(rules.critical_mass(&terrain, &Position::new(1, 0, 1)), 1);
+    }
+
+    #[test]
+    /// Check if atoms are stacked and fall when the cell below explodes
+    fn gravity_test() {
+        let rules = rules_with_placement(Placement::Gravity);
+        let cube = Volume::new(&Position::zero(), &Position::new(2, 2, 2)).unwrap();
+        let terrain = terrain_with(&cube.into_iter().filter(|p| p.z() == 0).collect::<Vec<_>>());
+        let p = players(2);
+        let (a, b) = (p[0].id(), p[1].id());
+        let mut gs = ClassicGameState::new(terrain, &p).unwrap();
+
+        // Atoms can be placed only on top of the stack
+        let legal = rules.legal_positions(&gs);
+        assert_eq!(legal.len(), 9);
+        assert!(legal.iter().all(|p| p.z() == 1));
+
+        let center = Position::new(1, 1, 1);
+        let above = Position::new(1, 1, 2);
+        assert_eq!(rules.critical_mass(gs.terrain().as_ref(), &center), 5);
+        gs.set_cell_state(&center, &ClassicCellState::Occupied(a, 4));
+        gs.set_cell_state(&above, &ClassicCellState::Occupied(b, 1));
+        gs.set_cell_state(&Position::new(0, 0, 1), &ClassicCellState::Occupied(b, 1));
+        assert!(rules.legal_positions(&gs).contains(&center));
+        assert!(!rules.legal_positions(&gs).contains(&above));
+        assert!(!rules.legal_positions(&gs).contains(&Position::new(0, 0, 3)));
+        gs.set_current_player(b);
+        assert!(rules.legal_positions(&gs).contains(&Position::new(0, 0, 2)));
+        assert!(rules.legal_positions(&gs).contains(&Position::new(1, 1, 3)));
+        gs.set_current_player(a);
+
+        // Center explodes capturing the cell above, which falls down
+        let (gs, reactions) = place(&rules, Arc::new(gs), center);
+        let kinds: Vec<_> = reactions.iter().map(|r| r.kind()).collect();
+        assert!(kinds.contains(&ClassicActionKind::Fall {
+            from: above,
+            to: center,
+            player: a
+        }));
+        assert_eq!(gs.cell_state(&above), Some(ClassicCellState::Empty));
+        assert_eq!(
+            gs.cell_state(&center),
+            Some(ClassicCellState::Occupied(a, 2))
+        );
+        assert_eq!(gs.player_atoms(a), 6);
+        assert_eq!(gs.player_atoms(b), 1);
+    }
+
+    #[test]
+    /// Check if neighbours and critical mass respect the topology
+    fn topology_test() {
+        let cube = Volume::new(&Position::zero(), &Position::new(2, 2, 2)).unwrap();
+        // Axes wrap around the terrain volume, that is the cube
+        let mut terrain = Terrain::new(&cube);
+        for p in &cube {
+            terrain.set_cell_at(&p, CellType::Fill(CellMaterial::Ground));
+        }
+
+        let mut config = ClassicRulesConfig::default();
+        config.set_topology(Topology::torus());
+        let rules = ClassicRules::new(config);
+        for p in &cube {
+            assert_eq!(rules.critical_mass(&terrain, &p), 6, "{:?}", p);
+        }
+
+        config.set_topology(Topology::new(true, false, false));
+        let rules = ClassicRules::new(config);
+        let corner = Position::zero();
+        assert_eq!(rules.critical_mass(&terrain, &corner), 4);
+        assert!(rules
+            .neighbours(&terrain, &corner)
+            .contains(&Position::new(2, 0, 0)));
+
+        // Axes of size 1 or 2 do not produce duplicated neighbours
+        let small = Volume::new(&Position::zero(), &Position::new(1, 1, 1)).unwrap();
+        let mut terrain = Terrain::new(&small);
+        for p in &small {
+            terrain.set_cell_at(&p, CellType::Fill(CellMaterial::Ground));
+        }
+        config.set_topology(Topology::torus());
+        let rules = ClassicRules::new(config);
+        assert_eq!(rules.critical_mass(&terrain, &corner), 3);
+    }
+
+    #[test]
+    /// Check if allied cells are captured only when enabled
+    fn allied_capture_test() {
+        let p = players(3);
+        let ids: Vec<_> = p.iter().map(|p| p.id()).collect();
+        let teams = Teams::new(vec![Team::new("A", &ids[..2]).unwrap()]).unwrap();
+        let cube = Volume::new(&Position::zero(), &Position::new(2, 2, 2)).unwrap();
+        let terrain = terrain_with(&cube.into_iter().filter(|p| p.z() == 0).collect::<Vec<_>>());
+        let mut gs = ClassicGameState::new_with_teams(terrain, &p, teams).unwrap();
+        // P0 corner is about to explode next to an ally and an enemy
+        gs.set_cell_state(&Position::zero(), &ClassicCellState::Occupied(ids[0], 1));
+        gs.set_cell_state(
+            &Position::new(1, 0, 0),
+            &ClassicCellState::Occupied(ids[1], 1),
+        );
+        gs.set_cell_state(
+            &Position::new(0, 1, 0),
+            &ClassicCellState::Occupied(ids[2], 1),
+        );
+        let gs = Arc::new(gs);
+
+        for capture_allied_cells in &[false, true] {
+            let mut config = ClassicRulesConfig::default();
+            config.set_capture_allied_cells(*capture_allied_cells);
+            let rules = ClassicRules::new(config);
+
+            let (next, reactions) = place(&rules, Arc::clone(&gs), Position::zero());
+            let captures = reactions
+                .iter()
+                .filter(|r| matches!(r.kind(), ClassicActionKind::Capture { .. }))
+                .count();
+            let ally_owner = if *capture_allied_cells {
+                ids[0]
+            } else {
+                ids[1]
+            };
+            assert_eq!(
+                next.cell_state(&Position::new(1, 0, 0)),
+                Some(ClassicCellState::Occupied(ally_owner, 2))
+            );
+            assert_eq!(
+                next.cell_state(&Position::new(0, 1, 0)),
+                Some(ClassicCellState::Occupied(ids[0], 2))
+            );
+            assert_eq!(captures, if *capture_allied_cells { 2 } else { 1 });
+        }
+    }
+
+    #[test]
+    /// Check if the timeline holds the exact wavefront of each substep
+    fn timeline_test() {
+        let rules = ClassicRules::default();
+        let p = players(2);
+        let (a, b) = (p[0].id(), p[1].id());
+        let gs = Arc::new(ClassicGameState::new(square(), &p).unwrap());
+        let (gs, _) = place(&rules, gs, Position::new(0, 0, 0));
+        let (gs, _) = place(&rules, gs, Position::new(1, 1, 0));
+        let (gs, _) = place(&rules, gs, Position::new(1, 0, 0));
+
+        let action = ClassicAction::place_atom(b, 3, Position::new(1, 1, 0));
+        let (gs, reactions, timeline) = rules.execute_with_timeline(gs, Arc::new(action));
+        let mut first = Wave::new(1);
+        first.add_explosion(Position::new(1, 1, 0));
+        first.add_flight(Flight::new(
+            Position::new(1, 1, 0),
+            Position::new(0, 1, 0),
+            b,
+        ));
+        first.add_flight(Flight::new(
+            Position::new(1, 1, 0),
+            Position::new(1, 0, 0),
+            b,
+        ));
+        first.add_capture(Capture::new(Position::new(1, 0, 0), a, b));
+        // Captured cell reached its critical mass, and explodes capturing last opponent cell
+        let mut second = Wave::new(2);
+        second.add_explosion(Position::new(1, 0, 0));
+        second.add_flight(Flight::new(
+            Position::new(1, 0, 0),
+            Position::new(0, 0, 0),
+            b,
+        ));
+        second.add_flight(Flight::new(
+            Position::new(1, 0, 0),
+            Position::new(1, 1, 0),
+            b,
+        ));
+        second.add_capture(Capture::new(Position::new(0, 0, 0), a, b));
+
+        assert_eq!(timeline.turn(), 3);
+        assert_eq!(timeline.waves(), &[first, second]);
+        assert_eq!(
+            timeline.wave(2).unwrap().explosions(),
+            &[Position::new(1, 0, 0)]
+        );
+        assert!(timeline.wave(3).is_none());
+        let substeps: Vec<_> = reactions.iter().map(|r| r.turn_substep()).collect();
+        assert_eq!(substeps.iter().max(), Some(&2));
+        assert_eq!(gs.player_atoms(a), 0);
+
+        // No explosion, empty timeline
+        let action = ClassicAction::place_atom(b, 4, Position::new(0, 1, 0));
+        let (_, _, timeline) = rules.execute_with_timeline(gs, Arc::new(action));
+        assert!(timeline.is_empty());
+
+        // With gravity, atoms above an exploding cell fall down in the same wave
+        let rules = rules_with_placement(Placement::Gravity);
+        let terrain = terrain_with(&[
+            Position::new(0, 0, 0),
+            Position::new(1, 0, 0),
+            Position::new(2, 0, 0),
+        ]);
+        let mut gs = ClassicGameState::new(terrain, &p).unwrap();
+        let (center, above) = (Position::new(1, 0, 1), Position::new(1, 0, 2));
+        gs.set_cell_state(&center, &ClassicCellState::Occupied(a, 2));
+        gs.set_cell_state(&above, &ClassicCellState::Occupied(b, 1));
+        gs.set_cell_state(&Position::new(2, 0, 1), &ClassicCellState::Occupied(b, 1));
+        let action = ClassicAction::place_atom(a, gs.current_turn(), center);
+        let (_, reactions, timeline) = rules.execute_with_timeline(Arc::new(gs), Arc::new(action));
+        let wave = timeline.wave(1).unwrap();
+        assert_eq!(wave.explosions(), &[center]);
+        assert!(wave.captures().contains(&Capture::new(above, b, a)));
+        assert_eq!(wave.falls(), &[Fall::new(above, center, a)]);
+        assert!(reactions.iter().any(|r| r.kind()
+            == ClassicActionKind::Fall {
+                from: above,
+                to: center,
+                player: a
+            }));
+    }
+
+    #[test]
+    /// Check if a chain reaction that never ends is stopped
+    fn endless_chain_test() {
+        let rules = ClassicRules::default();
+        let p = players(2);
+        // Two cells with critical mass 1: atom bounces between them
+        let terrain = terrain_with(&[Position::new(0, 0, 0), Position::new(1, 0, 0)]);
+        let gs = Arc::new(ClassicGameState::new(terrain, &p).unwrap());
+
+        let (gs, reactions) = place(&rules, gs, Position::zero());
+        assert!(!reactions.is_empty());
+        assert_eq!(gs.player_atoms(p[0].id()), 1);
+        assert_eq!(gs.current_player(), p[1].id());
+    }
+
+    #[test]
+    /// Play random games checking atoms are preserved and hash is consistent
+    fn random_game_test() {
+        let rules = ClassicRules::default();
+        for _ in 0..NUMBER_OF_LOOPS_FOR_TINY_TEST {
+            let (terrain, cells) = random_terrain(&random_volume(1, 5));
+            if cells.is_empty() {
+                continue;
+            }
+            let p = players(random_number(2, 5));
+            let mut gs = Arc::new(ClassicGameState::new(Arc::new(terrain), &p).unwrap());
+
+            for moves in 1..200 {
+                let positions = rules.legal_positions(&gs);
+                if positions.is_empty() || gs.players().iter().filter(|p| p.is_alive()).count() < 2
+                {
+                    break;
+                }
+                let position = positions[random_number(0, positions.len())];
+                let (next, _) = place(&rules, gs, position);
+                gs = next;
+
+                let atoms: usize = gs.players().iter().map(|p| gs.player_atoms(p.id())).sum();
+                assert_eq!(atoms, moves);
+                let mut fresh = ClassicGameState::new(gs.terrain(), &p).unwrap();
+                for (pos, cell) in gs.occupied_cells() {
+                    fresh.set_cell_state(&pos, cell);
+                }
+                fresh.set_current_player(gs.current_player());
+                assert_eq!(fresh.state_hash(), gs.state_hash());
+            }
+        }
+    }
+}
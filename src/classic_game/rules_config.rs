@@ -0,0 +1,156 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    generic_game::{action::TurnId, cell_state::CellOccupation},
+    geometry::topology::Topology,
+};
+
+use super::scoring::ScoringPolicy;
+
+/// Effects of cell materials on gameplay.
+///
+/// All effects are disabled by default (every material plays the same).
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct MaterialRules {
+    /// Whether atoms cannot be placed on water cells
+    no_placement_on_water: bool,
+    /// How much critical mass of water cells is reduced (it is never less than 1)
+    water_critical_mass_reduction: CellOccupation,
+    /// Whether explosions do not send atoms to water cells
+    water_blocks_propagation: bool,
+}
+
+impl MaterialRules {
+    /// Create a new material rules configuration
+    pub fn new(
+        no_placement_on_water: bool,
+        water_critical_mass_reduction: CellOccupation,
+        water_blocks_propagation: bool,
+    ) -> Self {
+        Self {
+            no_placement_on_water,
+            water_critical_mass_reduction,
+            water_blocks_propagation,
+        }
+    }
+
+    /// Returns whether atoms cannot be placed on water cells
+    pub fn no_placement_on_water(&self) -> bool {
+        self.no_placement_on_water
+    }
+
+    /// Returns how much critical mass of water cells is reduced (it is never less than 1).
+    ///
+    /// An exploding water cell sends its atoms to the first neighbours only
+    /// (as returned by `ClassicRules::neighbours`), so the number of atoms never grows.
+    pub fn water_critical_mass_reduction(&self) -> CellOccupation {
+        self.water_critical_mass_reduction
+    }
+
+    /// Returns whether explosions do not send atoms to water cells
+    /// (chain reactions cannot cross water).
+    pub fn water_blocks_propagation(&self) -> bool {
+        self.water_blocks_propagation
+    }
+}
+
+/// Where atoms can stay on the terrain
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub enum Placement {
+    /// Atoms are placed inside terrain cells (of any type)
+    InsideCells,
+    /// Atoms are placed in empty positions directly above `Flat` cells
+    /// (see `Terrain::is_playable`)
+    OnTop,
+    /// Atoms are stacked in columns resting on `Flat` cells: they are placed on top of
+    /// other atoms and they fall when the cell below them gets empty
+    Gravity,
+}
+
+impl Default for Placement {
+    /// Atoms are placed inside cells
+    fn default() -> Self {
+        Placement::InsideCells
+    }
+}
+
+/// Configuration of the rules of a classic game
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct ClassicRulesConfig {
+    /// How the score of players is computed
+    scoring: ScoringPolicy,
+    /// Number of turns after which the game ends in a draw (`None` no limit)
+    max_turns: Option<TurnId>,
+    /// Effects of cell materials
+    materials: MaterialRules,
+    /// Where atoms can stay on the terrain
+    placement: Placement,
+    /// Which axes of the terrain volume wrap around
+    topology: Topology,
+    /// Whether explosions capture cells of allied players
+    capture_allied_cells: bool,
+}
+
+impl ClassicRulesConfig {
+    /// Returns how the score of players is computed
+    pub fn scoring(&self) -> ScoringPolicy {
+        self.scoring
+    }
+
+    /// Set how the score of players is computed
+    pub fn set_scoring(&mut self, scoring: ScoringPolicy) {
+        self.scoring = scoring;
+    }
+
+    /// Returns number of turns after which the game ends in a draw (`None` no limit)
+    pub fn max_turns(&self) -> Option<TurnId> {
+        self.max_turns
+    }
+
+    /// Set number of turns after which the game ends in a draw (`None` no limit)
+    pub fn set_max_turns(&mut self, max_turns: Option<TurnId>) {
+        self.max_turns = max_turns;
+    }
+
+    /// Returns effects of cell materials
+    pub fn materials(&self) -> MaterialRules {
+        self.materials
+    }
+
+    /// Set effects of cell materials
+    pub fn set_materials(&mut self, materials: MaterialRules) {
+        self.materials = materials;
+    }
+
+    /// Returns where atoms can stay on the terrain
+    pub fn placement(&self) -> Placement {
+        self.placement
+    }
+
+    /// Set where atoms can stay on the terrain
+    pub fn set_placement(&mut self, placement: Placement) {
+        self.placement = placement;
+    }
+
+    /// Returns which axes of the terrain volume wrap around
+    pub fn topology(&self) -> Topology {
+        self.topology
+    }
+
+    /// Set which axes of the terrain volume wrap around
+    pub fn set_topology(&mut self, topology: Topology) {
+        self.topology = topology;
+    }
+
+    /// Returns whether explosions capture cells of allied players.
+    ///
+    /// If not, an allied cell receiving an atom keeps its owner.
+    pub fn capture_allied_cells(&self) -> bool {
+        self.capture_allied_cells
+    }
+
+    /// Set whether explosions capture cells of allied players
+    pub fn set_capture_allied_cells(&mut self, capture_allied_cells: bool) {
+        self.capture_allied_cells = capture_allied_cells;
+    }
+}
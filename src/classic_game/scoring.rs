@@ -0,0 +1,128 @@
+use serde::{Deserialize, Serialize};
+
+use crate::generic_game::player::Score;
+
+/// Defines how the score of a player is computed in the classic game.
+///
+/// Score is the sum of:
+/// - Points for each atom owned
+/// - Points for each cell owned
+/// - Bonus collected during the game: points for each captured cell and
+///   points for each explosion (after the first one) of a chain reaction.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub struct ScoringPolicy {
+    points_per_atom: Score,
+    points_per_cell: Score,
+    points_per_capture: Score,
+    chain_bonus: Score,
+}
+
+impl Default for ScoringPolicy {
+    /// Score is the total number of atoms owned
+    fn default() -> Self {
+        Self::new(1, 0, 0, 0)
+    }
+}
+
+impl ScoringPolicy {
+    /// Create a new scoring policy
+    pub fn new(
+        points_per_atom: Score,
+        points_per_cell: Score,
+        points_per_capture: Score,
+        chain_bonus: Score,
+    ) -> Self {
+        Self {
+            points_per_atom,
+            points_per_cell,
+            points_per_capture,
+            chain_bonus,
+        }
+    }
+
+    /// Returns points for each atom owned
+    pub fn points_per_atom(&self) -> Score {
+        self.points_per_atom
+    }
+
+    /// Returns points for each cell owned
+    pub fn points_per_cell(&self) -> Score {
+        self.points_per_cell
+    }
+
+    /// Returns points for each cell captured
+    pub fn points_per_capture(&self) -> Score {
+        self.points_per_capture
+    }
+
+    /// Returns points for each explosion after the first one of a chain reaction
+    pub fn chain_bonus(&self) -> Score {
+        self.chain_bonus
+    }
+
+    /// Returns bonus for a move that captured `captures` cells with a chain of `chain` explosions
+    pub fn move_bonus(&self, captures: usize, chain: usize) -> Score {
+        let captures = self.points_per_capture.saturating_mul(captures as Score);
+        let chain = self
+            .chain_bonus
+            .saturating_mul(chain.saturating_sub(1) as Score);
+
+        captures.saturating_add(chain)
+    }
+
+    /// Returns the score of a player owning `atoms` atoms in `cells` cells, having
+    /// collected `bonus` points.
+    pub fn score(&self, atoms: usize, cells: usize, bonus: Score) -> Score {
+        self.points_per_atom
+            .saturating_mul(atoms as Score)
+            .saturating_add(self.points_per_cell.saturating_mul(cells as Score))
+            .saturating_add(bonus)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utilities::{
+        constants::NUMBER_OF_LOOPS_FOR_NORMAL_TEST, random_generator::random_number,
+    };
+
+    #[test]
+    /// Check score computation
+    fn score_test() {
+        for _ in 0..NUMBER_OF_LOOPS_FOR_NORMAL_TEST {
+            let pa = random_number(0, 10);
+            let pc = random_number(0, 10);
+            let pcap = random_number(0, 10);
+            let cb = random_number(0, 10);
+            let policy = ScoringPolicy::new(pa, pc, pcap, cb);
+
+            let atoms = random_number(0, 1000);
+            let cells = random_number(0, 1000);
+            let bonus = random_number(0, 1000);
+            assert_eq!(
+                policy.score(atoms, cells, bonus),
+                pa * atoms as Score + pc * cells as Score + bonus
+            );
+
+            let captures = random_number(0, 100);
+            let chain = random_number(1, 100);
+            assert_eq!(
+                policy.move_bonus(captures, chain),
+                pcap * captures as Score + cb * (chain as Score - 1)
+            );
+            assert_eq!(policy.move_bonus(0, 0), 0);
+        }
+    }
+
+    #[test]
+    /// Check default policy and saturation
+    fn default_test() {
+        let policy = ScoringPolicy::default();
+        assert_eq!(policy.score(12, 3, 0), 12);
+        assert_eq!(policy.move_bonus(5, 5), 0);
+
+        let policy = ScoringPolicy::new(Score::MAX, 1, 1, 1);
+        assert_eq!(policy.score(2, 1, 1), Score::MAX);
+    }
+}
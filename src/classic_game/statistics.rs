@@ -0,0 +1,358 @@
+use std::collections::BTreeMap;
+
+use serde::Serialize;
+
+use crate::generic_game::{
+    action::{Action, TurnId},
+    actor::Actor,
+    player::{PlayerId, PlayerState, Score},
+};
+
+use super::{
+    action::{ClassicAction, ClassicActionKind},
+    game_state::ClassicGameState,
+};
+
+/// Statistics of a player collected during a game
+#[derive(Debug, Clone, Default, Serialize, PartialEq, Eq)]
+pub struct PlayerStatistics {
+    atoms_placed: usize,
+    explosions_triggered: usize,
+    longest_chain: usize,
+    cells_captured: usize,
+    cells_lost: usize,
+    peak_score: Score,
+    eliminated_at_turn: Option<TurnId>,
+}
+
+impl PlayerStatistics {
+    /// Returns number of atoms placed by the player
+    pub fn atoms_placed(&self) -> usize {
+        self.atoms_placed
+    }
+
+    /// Returns number of explosions caused by the moves of the player
+    /// (also of cells owned by other players, e.g. allies)
+    pub fn explosions_triggered(&self) -> usize {
+        self.explosions_triggered
+    }
+
+    /// Returns max number of explosions caused by a single move of the player
+    pub fn longest_chain(&self) -> usize {
+        self.longest_chain
+    }
+
+    /// Returns number of cells captured to other players
+    pub fn cells_captured(&self) -> usize {
+        self.cells_captured
+    }
+
+    /// Returns number of cells captured by other players
+    pub fn cells_lost(&self) -> usize {
+        self.cells_lost
+    }
+
+    /// Returns the max score reached by the player
+    pub fn peak_score(&self) -> Score {
+        self.peak_score
+    }
+
+    /// Returns the turn when player has been eliminated (`None` if still alive)
+    pub fn eliminated_at_turn(&self) -> Option<TurnId> {
+        self.eliminated_at_turn
+    }
+}
+
+/// Statistics of all players of a game.
+///
+/// Statistics are collected recording the actions returned by `GameRule::execute`
+/// and the game states it produces.
+#[derive(Debug, Clone, Default, Serialize, PartialEq, Eq)]
+pub struct GameStatistics {
+    /// Statistics for each player
+    players: BTreeMap<PlayerId, PlayerStatistics>,
+    /// Player who made (or timed out) the move of the turn being recorded (turn, player)
+    #[serde(skip)]
+    current_move: Option<(TurnId, PlayerId)>,
+    /// Chain being recorded (turn, player who made the move, explosions)
+    #[serde(skip)]
+    current_chain: Option<(TurnId, PlayerId, usize)>,
+}
+
+impl GameStatistics {
+    /// Create empty statistics for provided players
+    pub fn new(players: &[PlayerId]) -> Self {
+        Self {
+            players: players
+                .iter()
+                .map(|id| (*id, PlayerStatistics::default()))
+                .collect(),
+            current_move: None,
+            current_chain: None,
+        }
+    }
+
+    /// Returns statistics of a player
+    ///
+    /// `None` if player is not part of the game
+    pub fn player(&self, player_id: PlayerId) -> Option<&PlayerStatistics> {
+        self.players.get(&player_id)
+    }
+
+    /// Update statistics with an action.
+    ///
+    /// Actions involving players not part of the game are ignored.
+    pub fn record(&mut self, action: &ClassicAction) {
+        match action.kind() {
+            ClassicActionKind::PlaceAtom(_) => {
+                // Atoms placed by the controller (on timeout) are credited to the timed out player
+                let player = action_player(action).or_else(|| match self.current_move {
+                    Some((turn, player)) if turn == action.turn() => Some(player),
+                    _ => None,
+                });
+                if let Some(id) = player {
+                    self.current_move = Some((action.turn(), id));
+                    if let Some(stats) = self.players.get_mut(&id) {
+                        stats.atoms_placed += 1;
+                    }
+                }
+            }
+            ClassicActionKind::Explode { player, .. } => {
+                // Explosions are credited to the player who made the move of the turn
+                // (to the owner of the cell, if the move was not recorded)
+                let player = match self.current_move {
+                    Some((turn, mover)) if turn == action.turn() => mover,
+                    _ => player,
+                };
+                if let Some(stats) = self.players.get_mut(&player) {
+                    stats.explosions_triggered += 1;
+
+                    let chain = match self.current_chain {
+                        Some((turn, p, count)) if turn == action.turn() && p == player => count + 1,
+                        _ => 1,
+                    };
+                    self.current_chain = Some((action.turn(), player, chain));
+                    stats.longest_chain = stats.longest_chain.max(chain);
+                }
+            }
+            ClassicActionKind::Capture { from, to, .. } => {
+                if let Some(stats) = self.players.get_mut(&to) {
+                    stats.cells_captured += 1;
+                }
+                if let Some(stats) = self.players.get_mut(&from) {
+                    stats.cells_lost += 1;
+                }
+            }
+            ClassicActionKind::Eliminate(player) => {
+                if let Some(stats) = self.players.get_mut(&player) {
+                    stats.eliminated_at_turn.get_or_insert(action.turn());
+                }
+            }
+            ClassicActionKind::Timeout(player) => {
+                self.current_move = Some((action.turn(), player));
+            }
+            ClassicActionKind::Fall { .. } => {}
+        }
+    }
+
+    /// Update statistics depending on state (e.g. peak score) with provided game state
+    pub fn record_state(&mut self, game_state: &ClassicGameState) {
+        for player in game_state.players() {
+            if let Some(stats) = self.players.get_mut(&player.id()) {
+                stats.peak_score = stats.peak_score.max(player.score());
+            }
+        }
+    }
+
+    /// Update statistics with the result of `GameRule::execute`
+    pub fn record_execution<'a, I>(&mut self, game_state: &ClassicGameState, actions: I)
+    where
+        I: IntoIterator<Item = &'a ClassicAction>,
+    {
+        for action in actions {
+            self.record(action);
+        }
+        self.record_state(game_state);
+    }
+
+    /// Returns statistics serialized as JSON (to be stored at game end)
+    pub fn to_json(&self) -> serde_json::Result<String> {
+        serde_json::to_string(self)
+    }
+}
+
+/// Returns the player that has generated the action
+fn action_player(action: &ClassicAction) -> Option<PlayerId> {
+    match action.source() {
+        Actor::Player(id) => Some(id),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::*;
+    use crate::{
+        classic_game::rules::ClassicRules,
+        generic_game::{
+            clock::{TimeControl, TimeoutPolicy},
+            game_rule::GameRule,
+            game_state::GameState,
+            player::{PlayerInfo, PlayerRage},
+        },
+        geometry::{terrain::Terrain, vector::Position, volume::Volume},
+        test_utilities::game::{ground_game, ids},
+    };
+
+    fn explode(turn: TurnId, player: PlayerId) -> ClassicAction {
+        ClassicAction::reaction(
+            turn,
+            1,
+            ClassicActionKind::Explode {
+                position: Position::zero(),
+                player,
+            },
+        )
+    }
+
+    fn capture(turn: TurnId, from: PlayerId, to: PlayerId) -> ClassicAction {
+        ClassicAction::reaction(
+            turn,
+            1,
+            ClassicActionKind::Capture {
+                position: Position::zero(),
+                from,
+                to,
+            },
+        )
+    }
+
+    #[test]
+    /// Check if counters follow the action stream
+    fn record_test() {
+        let mut stats = GameStatistics::new(&[0, 1]);
+
+        stats.record(&ClassicAction::place_atom(0, 0, Position::zero()));
+        stats.record(&ClassicAction::place_atom(1, 1, Position::zero()));
+        stats.record(&ClassicAction::place_atom(0, 2, Position::zero()));
+        // Chain of 3 explosions at turn 2
+        stats.record(&explode(2, 0));
+        stats.record(&capture(2, 1, 0));
+        stats.record(&explode(2, 0));
+        stats.record(&explode(2, 0));
+        stats.record(&capture(2, 1, 0));
+        // Chain of 1 explosion at turn 3
+        stats.record(&ClassicAction::place_atom(1, 3, Position::zero()));
+        stats.record(&explode(3, 1));
+        stats.record(&capture(3, 0, 1));
+        stats.record(&ClassicAction::reaction(
+            4,
+            1,
+            ClassicActionKind::Eliminate(1),
+        ));
+        // Unknown player is ignored
+        stats.record(&ClassicAction::place_atom(42, 5, Position::zero()));
+        assert!(stats.player(42).is_none());
+
+        let p0 = stats.player(0).unwrap();
+        assert_eq!(p0.atoms_placed(), 2);
+        assert_eq!(p0.explosions_triggered(), 3);
+        assert_eq!(p0.longest_chain(), 3);
+        assert_eq!(p0.cells_captured(), 2);
+        assert_eq!(p0.cells_lost(), 1);
+        assert_eq!(p0.eliminated_at_turn(), None);
+
+        let p1 = stats.player(1).unwrap();
+        assert_eq!(p1.atoms_placed(), 2);
+        assert_eq!(p1.explosions_triggered(), 1);
+        assert_eq!(p1.longest_chain(), 1);
+        assert_eq!(p1.cells_captured(), 1);
+        assert_eq!(p1.cells_lost(), 2);
+        assert_eq!(p1.eliminated_at_turn(), Some(4));
+    }
+
+    #[test]
+    /// Check if a chain through allied cells is credited to the player who made the move
+    fn allied_chain_test() {
+        let mut stats = GameStatistics::new(&[0, 1, 2]);
+
+        // Player 0 triggers a cascade through cells of its ally 1
+        stats.record(&ClassicAction::place_atom(0, 0, Position::zero()));
+        stats.record(&explode(0, 0));
+        stats.record(&explode(0, 1));
+        stats.record(&capture(0, 2, 1));
+        stats.record(&explode(0, 0));
+        stats.record(&explode(0, 1));
+        // Player 1 triggers an explosion of its own
+        stats.record(&ClassicAction::place_atom(1, 1, Position::zero()));
+        stats.record(&explode(1, 1));
+
+        let p0 = stats.player(0).unwrap();
+        assert_eq!(p0.explosions_triggered(), 4);
+        assert_eq!(p0.longest_chain(), 4);
+        let p1 = stats.player(1).unwrap();
+        assert_eq!(p1.explosions_triggered(), 1);
+        assert_eq!(p1.longest_chain(), 1);
+        assert_eq!(p1.cells_captured(), 1);
+    }
+
+    #[test]
+    /// Check if the atom placed on timeout is credited to the timed out player
+    fn random_move_timeout_test() {
+        let mut gs = ground_game(Position::new(2, 2, 0), 2);
+        gs.set_time_control(Some(TimeControl::new(
+            None,
+            1000,
+            100,
+            TimeoutPolicy::RandomMove,
+        )));
+        let ids = ids(&gs);
+        let mut stats = GameStatistics::new(&ids);
+        assert!(gs.tick_clock(1000));
+
+        let action = ClassicAction::timeout(ids[0], 0);
+        let (gs, reactions) = ClassicRules::default().execute(Arc::new(gs), Arc::new(action));
+        let place = reactions
+            .iter()
+            .find(|r| matches!(r.kind(), ClassicActionKind::PlaceAtom(_)))
+            .unwrap();
+        assert_eq!(place.source(), Actor::Controller);
+        stats.record_execution(&gs, reactions.iter().map(AsRef::as_ref));
+
+        assert_eq!(gs.player_atoms(ids[0]), 1);
+        assert_eq!(stats.player(ids[0]).unwrap().atoms_placed(), 1);
+        assert_eq!(stats.player(ids[1]).unwrap().atoms_placed(), 0);
+    }
+
+    #[test]
+    /// Check if peak score is kept and statistics can be serialized
+    fn record_state_test() {
+        let vol = Volume::new(&Position::zero(), &Position::new(1, 1, 1)).unwrap();
+        let players = [
+            PlayerInfo::new("A", PlayerRage::Red, true),
+            PlayerInfo::new("B", PlayerRage::Blue, true),
+        ];
+        let ids: Vec<_> = players.iter().map(|p| p.id()).collect();
+        let mut gs = ClassicGameState::new(Arc::new(Terrain::new(&vol)), &players).unwrap();
+        let mut stats = GameStatistics::new(&ids);
+
+        for score in &[3, 10, 4] {
+            let mut ps = gs.player_state(ids[0]).unwrap();
+            ps.set_score(*score);
+            gs.set_player_state(ids[0], &ps);
+            stats.record_execution(
+                &gs,
+                &[ClassicAction::place_atom(ids[0], 0, Position::zero())],
+            );
+        }
+        assert_eq!(stats.player(ids[0]).unwrap().peak_score(), 10);
+        assert_eq!(stats.player(ids[0]).unwrap().atoms_placed(), 3);
+        assert_eq!(stats.player(ids[1]).unwrap().peak_score(), 0);
+
+        let json = stats.to_json().expect("Cannot serialize");
+        assert!(json.contains("peak_score"));
+        assert!(!json.contains("current_chain"));
+    }
+}
@@ -0,0 +1,222 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    generic_game::{action::TurnId, player::PlayerId},
+    geometry::vector::Position,
+};
+
+/// Atom sent by an exploding cell to one of its neighbours
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub struct Flight {
+    /// Position of the exploding cell
+    from: Position,
+    /// Position of the neighbour receiving the atom
+    to: Position,
+    /// Owner of the atom
+    player: PlayerId,
+}
+
+impl Flight {
+    /// Create a new flight
+    pub fn new(from: Position, to: Position, player: PlayerId) -> Self {
+        Self { from, to, player }
+    }
+
+    /// Returns position of the exploding cell
+    pub fn from(&self) -> Position {
+        self.from
+    }
+
+    /// Returns position of the neighbour receiving the atom
+    pub fn to(&self) -> Position {
+        self.to
+    }
+
+    /// Returns owner of the atom
+    pub fn player(&self) -> PlayerId {
+        self.player
+    }
+}
+
+/// Cell changing owner because of an explosion
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub struct Capture {
+    /// Position of the captured cell
+    position: Position,
+    /// Previous owner
+    from: PlayerId,
+    /// New owner
+    to: PlayerId,
+}
+
+impl Capture {
+    /// Create a new capture
+    pub fn new(position: Position, from: PlayerId, to: PlayerId) -> Self {
+        Self { position, from, to }
+    }
+
+    /// Returns position of the captured cell
+    pub fn position(&self) -> Position {
+        self.position
+    }
+
+    /// Returns previous owner
+    pub fn from(&self) -> PlayerId {
+        self.from
+    }
+
+    /// Returns new owner
+    pub fn to(&self) -> PlayerId {
+        self.to
+    }
+}
+
+/// Atoms falling down on the empty cell below them (with `Placement::Gravity`)
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub struct Fall {
+    /// Position the atoms fall from
+    from: Position,
+    /// Position the atoms land on
+    to: Position,
+    /// Owner of the atoms
+    player: PlayerId,
+}
+
+impl Fall {
+    /// Create a new fall
+    pub fn new(from: Position, to: Position, player: PlayerId) -> Self {
+        Self { from, to, player }
+    }
+
+    /// Returns position the atoms fall from
+    pub fn from(&self) -> Position {
+        self.from
+    }
+
+    /// Returns position the atoms land on
+    pub fn to(&self) -> Position {
+        self.to
+    }
+
+    /// Returns owner of the atoms
+    pub fn player(&self) -> PlayerId {
+        self.player
+    }
+}
+
+/// Wave of explosions happening in a substep of a turn
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct Wave {
+    /// Substep of the turn
+    substep: TurnId,
+    /// Cells exploding (in volume order)
+    explosions: Vec<Position>,
+    /// Atoms sent by the exploding cells
+    flights: Vec<Flight>,
+    /// Cells captured when the atoms land
+    captures: Vec<Capture>,
+    /// Atoms falling down once the atoms landed
+    #[serde(default)]
+    falls: Vec<Fall>,
+}
+
+impl Wave {
+    /// Create an empty wave for a substep
+    pub fn new(substep: TurnId) -> Self {
+        Self {
+            substep,
+            ..Self::default()
+        }
+    }
+
+    /// Returns substep of the turn
+    pub fn substep(&self) -> TurnId {
+        self.substep
+    }
+
+    /// Returns cells exploding (the wavefront)
+    pub fn explosions(&self) -> &[Position] {
+        &self.explosions
+    }
+
+    /// Returns atoms sent by the exploding cells
+    pub fn flights(&self) -> &[Flight] {
+        &self.flights
+    }
+
+    /// Returns cells captured when the atoms land
+    pub fn captures(&self) -> &[Capture] {
+        &self.captures
+    }
+
+    /// Returns atoms falling down once the atoms landed
+    pub fn falls(&self) -> &[Fall] {
+        &self.falls
+    }
+
+    /// Add an exploding cell
+    pub fn add_explosion(&mut self, position: Position) {
+        self.explosions.push(position);
+    }
+
+    /// Add an atom sent by an exploding cell
+    pub fn add_flight(&mut self, flight: Flight) {
+        self.flights.push(flight);
+    }
+
+    /// Add a captured cell
+    pub fn add_capture(&mut self, capture: Capture) {
+        self.captures.push(capture);
+    }
+
+    /// Add atoms falling down
+    pub fn add_fall(&mut self, fall: Fall) {
+        self.falls.push(fall);
+    }
+}
+
+/// Animation timeline of a turn: the waves of its chain reaction, one per substep.
+///
+/// Renderers can interpolate the atoms in flight of each wave.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct Timeline {
+    /// Turn of the chain reaction
+    turn: TurnId,
+    /// Waves of explosions (in substep order)
+    waves: Vec<Wave>,
+}
+
+impl Timeline {
+    /// Create an empty timeline for a turn
+    pub fn new(turn: TurnId) -> Self {
+        Self {
+            turn,
+            waves: vec![],
+        }
+    }
+
+    /// Returns turn of the chain reaction
+    pub fn turn(&self) -> TurnId {
+        self.turn
+    }
+
+    /// Returns waves of explosions (in substep order)
+    pub fn waves(&self) -> &[Wave] {
+        &self.waves
+    }
+
+    /// Returns the wave of a substep (`None` if there is no such substep)
+    pub fn wave(&self, substep: TurnId) -> Option<&Wave> {
+        self.waves.iter().find(|w| w.substep == substep)
+    }
+
+    /// Returns whether no cell exploded
+    pub fn is_empty(&self) -> bool {
+        self.waves.is_empty()
+    }
+
+    /// Add a wave after the others
+    pub fn push(&mut self, wave: Wave) {
+        self.waves.push(wave);
+    }
+}
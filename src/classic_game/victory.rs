@@ -0,0 +1,317 @@
+use serde::Serialize;
+
+use crate::{
+    generic_game::{
+        action::TurnId,
+        cell_state::CellState,
+        game_rule::GameOutcome,
+        game_state::GameState,
+        player::{PlayerId, PlayerState, Score},
+    },
+    geometry::vector::Position,
+};
+
+use super::{game_state::ClassicGameState, rules::ClassicRules};
+
+/// Condition that ends a classic game.
+///
+/// Conditions are checked for each side (a team, or a player on its own) still alive.
+/// When more sides satisfy a condition, the best one wins; sides with the same
+/// value share a draw.
+#[derive(Debug, Clone, Serialize, PartialEq, Eq)]
+pub enum VictoryCondition {
+    /// Only one side is left with atoms, once every player has moved at least once
+    LastStanding,
+    /// Side score reaches the threshold
+    ScoreThreshold(Score),
+    /// Side owns at least the provided percentage of board cells
+    CellControl(u8),
+    /// Side owns all the objective cells
+    Objectives(Vec<Position>),
+    /// Side with most atoms when the turn is reached
+    MostAtoms(TurnId),
+}
+
+impl VictoryCondition {
+    /// Returns the outcome of the game if the condition is satisfied, otherwise `None`
+    pub fn evaluate(
+        &self,
+        game_state: &ClassicGameState,
+        rules: &ClassicRules,
+    ) -> Option<GameOutcome> {
+        let sides = sides(game_state);
+        let atoms =
+            |side: &[PlayerId]| -> usize { side.iter().map(|p| game_state.player_atoms(*p)).sum() };
+        let cells =
+            |side: &[PlayerId]| -> usize { side.iter().map(|p| game_state.player_cells(*p)).sum() };
+
+        let candidates: Vec<_> = match self {
+            VictoryCondition::LastStanding => {
+                let everybody_played = game_state
+                    .players()
+                    .iter()
+                    .all(|p| !p.is_alive() || p.has_played());
+                let with_atoms: Vec<_> = sides.into_iter().filter(|s| atoms(s) > 0).collect();
+
+                if everybody_played && with_atoms.len() == 1 {
+                    vec![(with_atoms[0].clone(), 0)]
+                } else {
+                    vec![]
+                }
+            }
+            VictoryCondition::ScoreThreshold(threshold) => sides
+                .into_iter()
+                .map(|s| {
+                    let score = s
+                        .iter()
+                        .filter_map(|p| game_state.player_state(*p))
+                        .fold(0 as Score, |acc, p| acc.saturating_add(p.score()));
+                    (s, score as usize)
+                })
+                .filter(|(_, score)| *score >= *threshold as usize)
+                .collect(),
+            VictoryCondition::CellControl(percent) => {
+                let terrain = game_state.terrain();
+                let board = terrain
+                    .volume()
+                    .into_iter()
+                    .filter(|p| rules.is_board_cell(&terrain, p))
+                    .count();
+                sides
+                    .into_iter()
+                    .map(|s| {
+                        let cells = cells(&s);
+                        (s, cells)
+                    })
+                    .filter(|(_, cells)| *cells > 0 && cells * 100 >= *percent as usize * board)
+                    .collect()
+            }
+            VictoryCondition::Objectives(objectives) => sides
+                .into_iter()
+                .filter(|s| {
+                    !objectives.is_empty()
+                        && objectives.iter().all(|o| {
+                            game_state.cell_state(o).is_some_and(|c| {
+                                s.iter().any(|p| c.player_occupation(*p).is_some())
+                            })
+                        })
+                })
+                .map(|s| (s, 0))
+                .collect(),
+            VictoryCondition::MostAtoms(turn) => {
+                if game_state.current_turn() >= *turn {
+                    sides
+                        .into_iter()
+                        .map(|s| {
+                            let atoms = atoms(&s);
+                            (s, atoms)
+                        })
+                        .collect()
+                } else {
+                    vec![]
+                }
+            }
+        };
+
+        decide(game_state, candidates)
+    }
+}
+
+/// Returns alive players grouped by side (allies play on the same side)
+fn sides(game_state: &ClassicGameState) -> Vec<Vec<PlayerId>> {
+    let mut sides: Vec<Vec<PlayerId>> = vec![];
+
+    for player in game_state.players().iter().filter(|p| p.is_alive()) {
+        match sides
+            .iter_mut()
+            .find(|s| game_state.are_allies(s[0], player.id()))
+        {
+            Some(side) => side.push(player.id()),
+            None => sides.push(vec![player.id()]),
+        }
+    }
+    sides
+}
+
+/// Returns the outcome given the sides satisfying a condition with their value:
+/// the side with highest value wins, sides with the same value share a draw.
+///
+/// `None` if no side satisfies the condition.
+fn decide(
+    game_state: &ClassicGameState,
+    candidates: Vec<(Vec<PlayerId>, usize)>,
+) -> Option<GameOutcome> {
+    let best = candidates.iter().map(|(_, value)| *value).max()?;
+    let mut winners: Vec<_> = candidates
+        .into_iter()
+        .filter(|(_, value)| *value == best)
+        .map(|(side, _)| side)
+        .collect();
+
+    if winners.len() == 1 {
+        let side = winners.remove(0);
+        Some(match game_state.teams().team_of(side[0]) {
+            Some(team) => GameOutcome::TeamWinner(team.id()),
+            None => GameOutcome::Winner(side[0]),
+        })
+    } else {
+        Some(GameOutcome::Draw(winners.into_iter().flatten().collect()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::*;
+    use crate::{
+        classic_game::cell_state::ClassicCellState,
+        generic_game::team::{Team, Teams},
+        geometry::volume::Volume,
+        test_utilities::game::{ground_terrain, players, update_player},
+    };
+
+    /// Create a 10 cells board with 3 players, where players 0 and 1 are a team if `teams`
+    fn game_state(teams: bool) -> (ClassicGameState, Vec<PlayerId>) {
+        let vol = Volume::new(&Position::zero(), &Position::new(9, 0, 0)).unwrap();
+        let terrain = ground_terrain(&vol);
+        let players = players(3);
+        let ids: Vec<_> = players.iter().map(|p| p.id()).collect();
+        let teams = if teams {
+            Teams::new(vec![Team::new("T", &ids[..2]).unwrap()]).unwrap()
+        } else {
+            Teams::default()
+        };
+        let gs = ClassicGameState::new_with_teams(Arc::new(terrain), &players, teams).unwrap();
+        (gs, ids)
+    }
+
+    fn occupy(gs: &mut ClassicGameState, x: i32, player: PlayerId, atoms: u8) {
+        gs.set_cell_state(
+            &Position::new(x, 0, 0),
+            &ClassicCellState::Occupied(player, atoms),
+        );
+    }
+
+    #[test]
+    /// Check last player standing
+    fn last_standing_test() {
+        let rules = ClassicRules::default();
+        let (mut gs, ids) = game_state(false);
+        let condition = VictoryCondition::LastStanding;
+
+        occupy(&mut gs, 0, ids[0], 1);
+        assert_eq!(condition.evaluate(&gs, &rules), None);
+        for id in &ids {
+            update_player(&mut gs, *id, |p| p.set_has_played(true));
+        }
+        assert_eq!(
+            condition.evaluate(&gs, &rules),
+            Some(GameOutcome::Winner(ids[0]))
+        );
+        occupy(&mut gs, 1, ids[2], 1);
+        assert_eq!(condition.evaluate(&gs, &rules), None);
+    }
+
+    #[test]
+    /// Check score threshold, with draws and teams
+    fn score_threshold_test() {
+        let rules = ClassicRules::default();
+        let condition = VictoryCondition::ScoreThreshold(10);
+
+        let (mut gs, ids) = game_state(false);
+        update_player(&mut gs, ids[0], |p| p.set_score(9));
+        assert_eq!(condition.evaluate(&gs, &rules), None);
+        update_player(&mut gs, ids[1], |p| p.set_score(12));
+        assert_eq!(
+            condition.evaluate(&gs, &rules),
+            Some(GameOutcome::Winner(ids[1]))
+        );
+        update_player(&mut gs, ids[2], |p| p.set_score(12));
+        assert_eq!(
+            condition.evaluate(&gs, &rules),
+            Some(GameOutcome::Draw(vec![ids[1], ids[2]]))
+        );
+
+        // Team score is the sum of members ones
+        let (mut gs, ids) = game_state(true);
+        let team = gs.teams().teams()[0].id();
+        update_player(&mut gs, ids[0], |p| p.set_score(6));
+        update_player(&mut gs, ids[1], |p| p.set_score(6));
+        update_player(&mut gs, ids[2], |p| p.set_score(11));
+        assert_eq!(
+            condition.evaluate(&gs, &rules),
+            Some(GameOutcome::TeamWinner(team))
+        );
+    }
+
+    #[test]
+    /// Check control of a percentage of board cells
+    fn cell_control_test() {
+        let rules = ClassicRules::default();
+        let condition = VictoryCondition::CellControl(30);
+        let (mut gs, ids) = game_state(false);
+
+        occupy(&mut gs, 0, ids[0], 3);
+        occupy(&mut gs, 1, ids[0], 3);
+        occupy(&mut gs, 2, ids[1], 1);
+        assert_eq!(condition.evaluate(&gs, &rules), None);
+        occupy(&mut gs, 3, ids[0], 1);
+        assert_eq!(
+            condition.evaluate(&gs, &rules),
+            Some(GameOutcome::Winner(ids[0]))
+        );
+        assert_eq!(
+            VictoryCondition::CellControl(0).evaluate(&gs, &rules),
+            Some(GameOutcome::Winner(ids[0]))
+        );
+    }
+
+    #[test]
+    /// Check control of objective cells
+    fn objectives_test() {
+        let rules = ClassicRules::default();
+        let objectives = vec![Position::new(2, 0, 0), Position::new(7, 0, 0)];
+        let condition = VictoryCondition::Objectives(objectives);
+        let (mut gs, ids) = game_state(true);
+        let team = gs.teams().teams()[0].id();
+
+        occupy(&mut gs, 2, ids[0], 1);
+        occupy(&mut gs, 7, ids[2], 1);
+        assert_eq!(condition.evaluate(&gs, &rules), None);
+        // Objectives can be held by different members of a team
+        occupy(&mut gs, 7, ids[1], 1);
+        assert_eq!(
+            condition.evaluate(&gs, &rules),
+            Some(GameOutcome::TeamWinner(team))
+        );
+        assert_eq!(
+            VictoryCondition::Objectives(vec![]).evaluate(&gs, &rules),
+            None
+        );
+    }
+
+    #[test]
+    /// Check most atoms after a turn limit
+    fn most_atoms_test() {
+        let rules = ClassicRules::default();
+        let condition = VictoryCondition::MostAtoms(20);
+        let (mut gs, ids) = game_state(false);
+
+        occupy(&mut gs, 0, ids[0], 2);
+        occupy(&mut gs, 1, ids[1], 3);
+        occupy(&mut gs, 2, ids[2], 3);
+        gs.set_current_turn(19);
+        assert_eq!(condition.evaluate(&gs, &rules), None);
+        gs.set_current_turn(20);
+        assert_eq!(
+            condition.evaluate(&gs, &rules),
+            Some(GameOutcome::Draw(vec![ids[1], ids[2]]))
+        );
+        occupy(&mut gs, 3, ids[2], 1);
+        assert_eq!(
+            condition.evaluate(&gs, &rules),
+            Some(GameOutcome::Winner(ids[2]))
+        );
+    }
+}
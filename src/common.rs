@@ -1,3 +1,4 @@
+pub mod chunked_vec;
 pub mod clone_arc;
 pub mod id_generator;
 pub mod serializable;
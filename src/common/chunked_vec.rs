@@ -0,0 +1,175 @@
+use std::{fmt, sync::Arc};
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+/// Number of elements in each chunk
+pub const CHUNK_SIZE: usize = 64;
+
+/// Persistent vector of fixed length, split in reference counted chunks.
+///
+/// Cloning only copies the chunk pointers: clones share all their chunks, and a chunk is
+/// copied only when one of its elements is changed (copy on write), so a new state
+/// shares unchanged chunks with its predecessor.
+#[derive(Clone)]
+pub struct ChunkedVec<T> {
+    /// Chunks of elements (all of `CHUNK_SIZE` elements, except the last one)
+    chunks: Vec<Arc<Vec<T>>>,
+    /// Number of elements
+    len: usize,
+}
+
+impl<T: Clone> ChunkedVec<T> {
+    /// Create a vector of `len` clones of `value` (sharing the same chunk)
+    pub fn from_elem(value: T, len: usize) -> Self {
+        let full = Arc::new(vec![value.clone(); CHUNK_SIZE]);
+        let mut chunks = vec![full; len / CHUNK_SIZE];
+        let remainder = len % CHUNK_SIZE;
+        if remainder > 0 {
+            chunks.push(Arc::new(vec![value; remainder]));
+        }
+        Self { chunks, len }
+    }
+
+    /// Create a vector holding provided elements
+    pub fn from_vec(elements: Vec<T>) -> Self {
+        Self {
+            chunks: elements
+                .chunks(CHUNK_SIZE)
+                .map(|chunk| Arc::new(chunk.to_vec()))
+                .collect(),
+            len: elements.len(),
+        }
+    }
+
+    /// Returns a mutable reference to an element, copying its chunk if shared.
+    ///
+    /// `None` if index is out of bounds.
+    pub fn get_mut(&mut self, index: usize) -> Option<&mut T> {
+        if index >= self.len {
+            return None;
+        }
+        let chunk = &mut self.chunks[index / CHUNK_SIZE];
+        Arc::make_mut(chunk).get_mut(index % CHUNK_SIZE)
+    }
+}
+
+impl<T> ChunkedVec<T> {
+    /// Returns number of elements
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns whether there are no elements
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns an element (`None` if index is out of bounds)
+    pub fn get(&self, index: usize) -> Option<&T> {
+        self.chunks
+            .get(index / CHUNK_SIZE)
+            .and_then(|chunk| chunk.get(index % CHUNK_SIZE))
+    }
+
+    /// Returns an iterator over all elements
+    pub fn iter(&self) -> impl Iterator<Item = &T> + '_ {
+        self.chunks.iter().flat_map(|chunk| chunk.iter())
+    }
+
+    /// Returns the elements that differ from another vector of the same length, with their index
+    /// (shared chunks are skipped)
+    pub fn changes<'a>(&'a self, other: &'a Self) -> impl Iterator<Item = (usize, &'a T, &'a T)>
+    where
+        T: PartialEq,
+    {
+        self.chunks
+            .iter()
+            .zip(&other.chunks)
+            .enumerate()
+            .filter(|(_, (chunk, other))| !Arc::ptr_eq(chunk, other))
+            .flat_map(|(index, (chunk, other))| {
+                chunk
+                    .iter()
+                    .zip(other.iter())
+                    .enumerate()
+                    .filter(|(_, (element, other))| element != other)
+                    .map(move |(offset, (element, other))| {
+                        (index * CHUNK_SIZE + offset, element, other)
+                    })
+            })
+    }
+
+    /// Returns number of chunks shared with another vector
+    pub fn shared_chunks(&self, other: &Self) -> usize {
+        self.chunks
+            .iter()
+            .zip(&other.chunks)
+            .filter(|(chunk, other)| Arc::ptr_eq(chunk, other))
+            .count()
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for ChunkedVec<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+/// Serialized as a flat sequence, like a `Vec`
+impl<T: Serialize> Serialize for ChunkedVec<T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(self.iter())
+    }
+}
+
+/// Deserialized from a flat sequence, like a `Vec`
+impl<'de, T: Clone + Deserialize<'de>> Deserialize<'de> for ChunkedVec<T> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        Vec::deserialize(deserializer).map(Self::from_vec)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    /// Check if clones share unchanged chunks only
+    fn copy_on_write_test() {
+        let len = CHUNK_SIZE * 3 + 5;
+        let mut vec = ChunkedVec::from_elem(0, len);
+        assert_eq!(vec.len(), len);
+        assert_eq!(vec.iter().count(), len);
+        assert_eq!(vec.get(len - 1), Some(&0));
+        assert_eq!(vec.get(len), None);
+        assert!(vec.get_mut(len).is_none());
+
+        *vec.get_mut(CHUNK_SIZE + 1).unwrap() = 7;
+        let mut copy = vec.clone();
+        assert_eq!(copy.shared_chunks(&vec), 4);
+
+        *copy.get_mut(len - 1).unwrap() = 9;
+        assert_eq!(copy.shared_chunks(&vec), 3);
+        assert_eq!((vec.get(len - 1), copy.get(len - 1)), (Some(&0), Some(&9)));
+        assert_eq!(copy.get(CHUNK_SIZE + 1), Some(&7));
+
+        // Changing a chunk shared by the same vector copies it once
+        *copy.get_mut(0).unwrap() = 1;
+        *copy.get_mut(1).unwrap() = 2;
+        assert_eq!(copy.shared_chunks(&vec), 2);
+        assert_eq!(copy.iter().take(3).collect::<Vec<_>>(), vec![&1, &2, &0]);
+        assert_eq!(
+            serde_json::to_string(&ChunkedVec::from_elem(1, 3)).unwrap(),
+            "[1,1,1]"
+        );
+        assert!(ChunkedVec::from_elem(1, 0).is_empty());
+        let json = serde_json::to_string(&copy).unwrap();
+        let read: ChunkedVec<i32> = serde_json::from_str(&json).unwrap();
+        assert_eq!(read.len(), len);
+        assert!(read.iter().eq(copy.iter()));
+        assert_eq!(
+            vec.changes(&copy).collect::<Vec<_>>(),
+            vec![(0, &0, &1), (1, &0, &2), (len - 1, &0, &9)]
+        );
+    }
+}
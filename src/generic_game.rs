@@ -15,3 +15,18 @@ pub mod cell_state;
 
 /// Imports GameRule
 pub mod game_rule;
+
+/// Imports StateHash and Zobrist keys
+pub mod zobrist;
+
+/// Imports TranspositionTable
+pub mod transposition_table;
+
+/// Imports Team, Teams
+pub mod team;
+
+/// Imports TimeControl, PlayerClock
+pub mod clock;
+
+/// Imports PlayerIdentity, Color
+pub mod identity;
@@ -1,7 +1,9 @@
+use serde::{Deserialize, Serialize};
+
 use crate::common::id_generator::Id;
 
 /// A generic actor that can perform or _receive_ an `Action`
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum Actor {
     /// Game controller
     Controller,
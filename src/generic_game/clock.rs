@@ -0,0 +1,177 @@
+use serde::{Deserialize, Serialize};
+
+use crate::common::serializable::Serializable;
+
+/// Type used to store time, in milliseconds
+pub type Millis = u64;
+
+/// What happens when a player runs out of time
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub enum TimeoutPolicy {
+    /// A random legal move is played on behalf of the player
+    RandomMove,
+    /// Player loses its turn, but still gets the increment: with an empty bank it has
+    /// just the increment for its next move (without increment it loses all its next turns)
+    Skip,
+    /// Player loses the game
+    Forfeit,
+}
+
+/// Chess-style time control of a game
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub struct TimeControl {
+    /// Max time for a single move (`None` no limit)
+    move_timeout: Option<Millis>,
+    /// Total time available to each player at game start
+    bank: Millis,
+    /// Time added to the bank of a player after each of its moves
+    increment: Millis,
+    /// What happens when a player runs out of time
+    on_timeout: TimeoutPolicy,
+}
+
+impl TimeControl {
+    /// Create a new time control
+    pub fn new(
+        move_timeout: Option<Millis>,
+        bank: Millis,
+        increment: Millis,
+        on_timeout: TimeoutPolicy,
+    ) -> Self {
+        Self {
+            move_timeout,
+            bank,
+            increment,
+            on_timeout,
+        }
+    }
+
+    /// Returns max time for a single move (`None` no limit)
+    pub fn move_timeout(&self) -> Option<Millis> {
+        self.move_timeout
+    }
+
+    /// Returns total time available to each player at game start
+    pub fn bank(&self) -> Millis {
+        self.bank
+    }
+
+    /// Returns time added to the bank of a player after each of its moves
+    pub fn increment(&self) -> Millis {
+        self.increment
+    }
+
+    /// Returns what happens when a player runs out of time
+    pub fn on_timeout(&self) -> TimeoutPolicy {
+        self.on_timeout
+    }
+
+    /// Returns the clock of a player at game start
+    pub fn initial_clock(&self) -> PlayerClock {
+        PlayerClock {
+            bank: self.bank,
+            move_elapsed: 0,
+        }
+    }
+
+    /// Returns whether provided clock ran out of time (bank or current move)
+    pub fn is_timed_out(&self, clock: &PlayerClock) -> bool {
+        clock.bank == 0
+            || self
+                .move_timeout
+                .is_some_and(|timeout| clock.move_elapsed >= timeout)
+    }
+}
+
+impl Serializable for TimeControl {
+    /// Type of data to be serialized
+    type Data = TimeControl;
+
+    /// Return data to be serialized
+    fn data_to_be_serialized(&self) -> &Self::Data {
+        self
+    }
+}
+
+/// Clock of a player
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct PlayerClock {
+    /// Time left to the player
+    bank: Millis,
+    /// Time spent on current move
+    move_elapsed: Millis,
+}
+
+impl PlayerClock {
+    /// Returns time left to the player
+    pub fn bank(&self) -> Millis {
+        self.bank
+    }
+
+    /// Returns time spent on current move
+    pub fn move_elapsed(&self) -> Millis {
+        self.move_elapsed
+    }
+
+    /// Consume time while the player is thinking
+    pub fn tick(&mut self, elapsed: Millis) {
+        self.bank = self.bank.saturating_sub(elapsed);
+        self.move_elapsed = self.move_elapsed.saturating_add(elapsed);
+    }
+
+    /// Player completed its move: add the increment and restart move time
+    pub fn end_move(&mut self, time_control: &TimeControl) {
+        self.bank = self.bank.saturating_add(time_control.increment);
+        self.restart_move();
+    }
+
+    /// Restart move time without any increment (e.g. player lost its turn)
+    pub fn restart_move(&mut self) {
+        self.move_elapsed = 0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utilities::{
+        constants::NUMBER_OF_LOOPS_FOR_NORMAL_TEST, random_generator::random_number,
+    };
+
+    #[test]
+    /// Check if clock consumes bank and move time
+    fn tick_test() {
+        for _ in 0..NUMBER_OF_LOOPS_FOR_NORMAL_TEST {
+            let bank = random_number(1, 10_000);
+            let increment = random_number(0, 1000);
+            let control = TimeControl::new(None, bank, increment, TimeoutPolicy::Skip);
+            let mut clock = control.initial_clock();
+
+            let elapsed = random_number(0, 20_000);
+            clock.tick(elapsed);
+            assert_eq!(clock.bank(), bank.saturating_sub(elapsed));
+            assert_eq!(clock.move_elapsed(), elapsed);
+            assert_eq!(control.is_timed_out(&clock), elapsed >= bank);
+
+            clock.end_move(&control);
+            assert_eq!(clock.bank(), bank.saturating_sub(elapsed) + increment);
+            assert_eq!(clock.move_elapsed(), 0);
+        }
+    }
+
+    #[test]
+    /// Check per-move timeout
+    fn move_timeout_test() {
+        let control = TimeControl::new(Some(500), 10_000, 0, TimeoutPolicy::Forfeit);
+        let mut clock = control.initial_clock();
+
+        clock.tick(499);
+        assert!(!control.is_timed_out(&clock));
+        clock.end_move(&control);
+        clock.tick(499);
+        assert!(!control.is_timed_out(&clock));
+        clock.tick(1);
+        assert!(control.is_timed_out(&clock));
+        assert_eq!(clock.bank(), 9001);
+    }
+}
@@ -1,9 +1,12 @@
 use std::sync::Arc;
 
+use serde::Serialize;
+
 use super::{
     action::Action,
     game_state::GameState,
     player::{PlayerId, PlayerState},
+    team::TeamId,
 };
 
 /// Represents a generic game rule.
@@ -33,9 +36,51 @@ where
     fn can_handle(&self, action: &Self::A) -> bool;
 }
 
+/// Final result of a game
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub enum GameOutcome {
+    /// Game won by a player
+    Winner(PlayerId),
+    /// Game won by a team
+    TeamWinner(TeamId),
+    /// Game ended without a winner, players that shared the draw
+    Draw(Vec<PlayerId>),
+}
+
+/// Final result of a game: outcome and ranking of players
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct GameResult {
+    outcome: GameOutcome,
+    /// Groups of players, best first (players in the same group are tied)
+    ranking: Vec<Vec<PlayerId>>,
+}
+
+impl GameResult {
+    /// Create a new result
+    pub fn new(outcome: GameOutcome, ranking: Vec<Vec<PlayerId>>) -> Self {
+        Self { outcome, ranking }
+    }
+
+    /// Returns outcome of the game
+    pub fn outcome(&self) -> &GameOutcome {
+        &self.outcome
+    }
+
+    /// Returns groups of players, best first (players in the same group are tied)
+    pub fn ranking(&self) -> &[Vec<PlayerId>] {
+        &self.ranking
+    }
+
+    /// Returns the rank of a player (0 is the best), `None` if player is not ranked
+    pub fn rank_of(&self, player_id: PlayerId) -> Option<usize> {
+        self.ranking.iter().position(|g| g.contains(&player_id))
+    }
+}
+
 /// Represents generic basic rules that defines criteria for:
 /// - Next player.
-/// - In case there is winner which player is.
+/// - Whether the game is over and which is the outcome.
+/// - Ranking of players.
 pub trait BasicRules
 where
     Self::GS: GameState,
@@ -46,8 +91,33 @@ where
     /// Associated type for player state
     type PS;
 
-    /// Given a `GameState` returns the winner or `None`
-    fn winner(&self, game_state: &Self::GS) -> Option<PlayerId>;
+    /// Given a `GameState` returns the outcome of the game or `None` if game is not over
+    fn outcome(&self, game_state: &Self::GS) -> Option<GameOutcome>;
+
+    /// Given a `GameState` returns the winner or `None` (also when the game is won by a team)
+    fn winner(&self, game_state: &Self::GS) -> Option<PlayerId> {
+        match self.outcome(game_state) {
+            Some(GameOutcome::Winner(id)) => Some(id),
+            _ => None,
+        }
+    }
+
+    /// Given a `GameState` returns the winning team or `None`
+    fn winning_team(&self, game_state: &Self::GS) -> Option<TeamId> {
+        match self.outcome(game_state) {
+            Some(GameOutcome::TeamWinner(id)) => Some(id),
+            _ => None,
+        }
+    }
+
+    /// Given a `GameState` returns groups of players, best first (players in the same group are tied)
+    fn ranking(&self, game_state: &Self::GS) -> Vec<Vec<PlayerId>>;
+
+    /// Given a `GameState` returns outcome and ranking, or `None` if game is not over
+    fn result(&self, game_state: &Self::GS) -> Option<GameResult> {
+        self.outcome(game_state)
+            .map(|outcome| GameResult::new(outcome, self.ranking(game_state)))
+    }
 
     /// Given current state and list of players in the game return next player
     /// that has to play
@@ -1,7 +1,7 @@
 use std::{fmt::Debug, sync::Arc};
 
 use crate::{
-    common::{clone_arc::Clonable, id_generator::Id},
+    common::clone_arc::Clonable,
     geometry::{terrain::Terrain, vector::Position},
 };
 
@@ -9,6 +9,7 @@ use super::{
     action::TurnId,
     cell_state::CellState,
     player::{PlayerId, PlayerState},
+    zobrist::StateHash,
 };
 /// Represents state of particular moment of the game.
 pub trait GameState
@@ -73,4 +74,11 @@ where
     ///
     /// Return `false` if the position is not valid.
     fn set_cell_state(&mut self, position: &Position, state: &Self::CS) -> bool;
+
+    /// Returns a 64-bit hash of cells and current player of this state.
+    ///
+    /// Hash is updated incrementally, thus this call is cheap.
+    /// Players are hashed by their seat in turn order, not by their ID, so equal games
+    /// have the same hash also when their players have different IDs (e.g. on different machines).
+    fn state_hash(&self) -> StateHash;
 }
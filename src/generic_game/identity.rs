@@ -0,0 +1,158 @@
+use serde::{Deserialize, Serialize};
+
+use crate::common::serializable::Serializable;
+
+use super::player::PlayerRage;
+
+/// A RGB color
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash)]
+pub struct Color {
+    r: u8,
+    g: u8,
+    b: u8,
+}
+
+impl Color {
+    /// Create a new color with provided components
+    pub fn new(r: u8, g: u8, b: u8) -> Self {
+        Self { r, g, b }
+    }
+
+    /// Returns red component
+    pub fn r(&self) -> u8 {
+        self.r
+    }
+
+    /// Returns green component
+    pub fn g(&self) -> u8 {
+        self.g
+    }
+
+    /// Returns blue component
+    pub fn b(&self) -> u8 {
+        self.b
+    }
+}
+
+/// Colors and symbols of the default palette.
+///
+/// First six entries are the `PlayerRage` presets.
+const PALETTE: [((u8, u8, u8), char); 16] = [
+    ((220, 30, 30), 'R'),
+    ((30, 180, 30), 'G'),
+    ((30, 60, 220), 'B'),
+    ((240, 240, 240), 'W'),
+    ((20, 20, 20), 'K'),
+    ((240, 220, 30), 'Y'),
+    ((250, 140, 20), 'O'),
+    ((140, 40, 200), 'P'),
+    ((30, 210, 220), 'C'),
+    ((230, 40, 180), 'M'),
+    ((130, 80, 30), 'N'),
+    ((250, 160, 190), 'I'),
+    ((160, 240, 60), 'L'),
+    ((20, 30, 120), 'V'),
+    ((20, 130, 120), 'T'),
+    ((128, 128, 128), 'A'),
+];
+
+/// How a player (or faction) is shown: a color and a symbol
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash)]
+pub struct PlayerIdentity {
+    color: Color,
+    symbol: char,
+}
+
+impl PlayerIdentity {
+    /// Create a new identity with provided color and symbol
+    pub fn new(color: Color, symbol: char) -> Self {
+        Self { color, symbol }
+    }
+
+    /// Returns the identity at provided index of the default palette.
+    ///
+    /// `None` if index is not less than `PlayerIdentity::palette_size()`.
+    pub fn from_palette(index: usize) -> Option<Self> {
+        PALETTE
+            .get(index)
+            .map(|((r, g, b), symbol)| Self::new(Color::new(*r, *g, *b), *symbol))
+    }
+
+    /// Returns number of identities of the default palette
+    pub fn palette_size() -> usize {
+        PALETTE.len()
+    }
+
+    /// Returns the color
+    pub fn color(&self) -> Color {
+        self.color
+    }
+
+    /// Returns the symbol
+    pub fn symbol(&self) -> char {
+        self.symbol
+    }
+}
+
+impl From<PlayerRage> for PlayerIdentity {
+    /// Returns the preset identity of a rage
+    fn from(rage: PlayerRage) -> Self {
+        let index = PlayerRage::all()
+            .iter()
+            .position(|r| *r == rage)
+            .expect("Each rage shall be in the palette");
+        Self::from_palette(index).expect("Each rage shall be in the palette")
+    }
+}
+
+impl Serializable for PlayerIdentity {
+    /// Type of data to be serialized
+    type Data = PlayerIdentity;
+
+    /// Return data to be serialized
+    fn data_to_be_serialized(&self) -> &Self::Data {
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utilities::common::check_for_duplicate;
+
+    #[test]
+    /// Check if palette identities are all different
+    fn palette_test() {
+        let identities: Vec<_> = (0..PlayerIdentity::palette_size())
+            .map(|i| PlayerIdentity::from_palette(i).unwrap())
+            .collect();
+        assert_eq!(identities.len(), 16);
+        assert!(PlayerIdentity::from_palette(16).is_none());
+
+        let mut symbols: Vec<_> = identities.iter().map(|i| i.symbol()).collect();
+        assert!(check_for_duplicate(&mut symbols));
+        let mut colors: Vec<_> = identities
+            .iter()
+            .map(|i| {
+                let c = i.color();
+                (c.r(), c.g(), c.b())
+            })
+            .collect();
+        assert!(check_for_duplicate(&mut colors));
+    }
+
+    #[test]
+    /// Check if rages are presets of the palette
+    fn rage_test() {
+        for (i, rage) in PlayerRage::all().iter().enumerate() {
+            let identity = PlayerIdentity::from(*rage);
+            assert_eq!(Some(identity), PlayerIdentity::from_palette(i));
+            assert_eq!(PlayerRage::from_identity(&identity), Some(*rage));
+        }
+        let custom = PlayerIdentity::new(Color::new(1, 2, 3), '#');
+        assert_eq!(PlayerRage::from_identity(&custom), None);
+
+        let json = serde_json::to_string(&custom).unwrap();
+        assert_eq!(json, r##"{"color":{"r":1,"g":2,"b":3},"symbol":"#"}"##);
+    }
+}
@@ -1,6 +1,8 @@
 use std::fmt::Debug;
 
-use serde::Serialize;
+use log::warn;
+use serde::{Deserialize, Serialize};
+use unicode_segmentation::UnicodeSegmentation;
 
 /// Type used to store score points
 pub type Score = u32;
@@ -8,13 +10,15 @@ pub type Score = u32;
 /// Type used to store player ID
 pub type PlayerId = Id;
 
+use super::identity::PlayerIdentity;
 use crate::common::{
     clone_arc::Clonable,
     id_generator::{new_id, Id},
     serializable::Serializable,
 };
-/// Represents player _rage_.
-#[derive(Clone, Copy, Debug, Serialize, PartialEq, Eq)]
+
+/// Represents player _rage_ (presets of `PlayerIdentity`).
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, PartialEq, Eq)]
 pub enum PlayerRage {
     Red,
     Green,
@@ -24,13 +28,41 @@ pub enum PlayerRage {
     Yellow,
 }
 
-const MAX_PLAYER_NAME_LEN: usize = 8usize;
+impl PlayerRage {
+    /// Returns all rages
+    pub fn all() -> [PlayerRage; 6] {
+        [
+            PlayerRage::Red,
+            PlayerRage::Green,
+            PlayerRage::Blue,
+            PlayerRage::White,
+            PlayerRage::Black,
+            PlayerRage::Yellow,
+        ]
+    }
+
+    /// Returns the rage having provided identity as preset (`None` for custom identities)
+    pub fn from_identity(identity: &PlayerIdentity) -> Option<Self> {
+        Self::all()
+            .iter()
+            .find(|r| PlayerIdentity::from(**r) == *identity)
+            .copied()
+    }
+}
+
+/// Max length of player names, in graphemes (user-perceived characters)
+const MAX_PLAYER_NAME_LEN: usize = 16usize;
+
+/// Returns the first `MAX_PLAYER_NAME_LEN` graphemes of provided name
+fn truncate_name(name: &str) -> String {
+    name.graphemes(true).take(MAX_PLAYER_NAME_LEN).collect()
+}
 
-#[derive(Clone, Debug, Serialize)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 /// Contains the _static_ information of a player.
 pub struct PlayerInfo {
-    /// Player rage
-    rage: PlayerRage,
+    /// How player is shown (color and symbol)
+    identity: PlayerIdentity,
     /// Player name
     name: String,
     /// Whether this player is controlled by human
@@ -42,24 +74,64 @@ pub struct PlayerInfo {
 impl PlayerInfo {
     /// Create a new player info with provided data
     ///
-    /// *Note*: Player name will be truncated to 16 chars.
+    /// *Note*: Player name will be truncated to 16 graphemes.
     pub fn new(name: &str, rage: PlayerRage, is_human: bool) -> Self {
+        Self::with_identity(name, PlayerIdentity::from(rage), is_human)
+    }
+
+    /// Create a new player info with a custom identity (e.g. from `PlayerIdentity::from_palette`)
+    ///
+    /// *Note*: Player name will be truncated to 16 graphemes.
+    pub fn with_identity(name: &str, identity: PlayerIdentity, is_human: bool) -> Self {
         Self {
-            name: String::from(&name[..usize::min(name.len(), MAX_PLAYER_NAME_LEN)]),
-            rage,
+            name: truncate_name(name),
+            identity,
             is_human,
             id: new_id(),
         }
     }
 
+    /// Create a new player info, validating its name against the other players of the game.
+    ///
+    /// Name is trimmed and truncated to 16 graphemes, then `None` is returned if it is empty,
+    /// contains control characters or is already used by one of `players`.
+    pub fn validated(
+        name: &str,
+        identity: PlayerIdentity,
+        is_human: bool,
+        players: &[PlayerInfo],
+    ) -> Option<Self> {
+        let name = truncate_name(name.trim());
+
+        if name.is_empty() || name.chars().any(char::is_control) {
+            warn!("Invalid player name {:?}", name);
+            None
+        } else if players.iter().any(|p| p.name == name) {
+            warn!("Player name {:?} already used", name);
+            None
+        } else {
+            Some(Self::with_identity(&name, identity, is_human))
+        }
+    }
+
     /// Returns player name
     pub fn name(&self) -> &str {
         &self.name
     }
 
-    /// Returns player rage
-    pub fn rage(&self) -> PlayerRage {
-        self.rage
+    /// Returns player rage (`None` if player has a custom identity)
+    pub fn rage(&self) -> Option<PlayerRage> {
+        PlayerRage::from_identity(&self.identity)
+    }
+
+    /// Returns how player is shown (color and symbol)
+    pub fn identity(&self) -> PlayerIdentity {
+        self.identity
+    }
+
+    /// Set how player is shown (player ID is kept)
+    pub fn set_identity(&mut self, identity: PlayerIdentity) {
+        self.identity = identity;
     }
 
     /// Returns whether player is human
@@ -79,7 +151,7 @@ impl Serializable for PlayerInfo {
 
     /// Return data to be serialized
     fn data_to_be_serialized(&self) -> &Self::Data {
-        &self
+        self
     }
 }
 
@@ -122,7 +194,7 @@ mod tests {
     };
 
     use super::PlayerRage;
-    use super::{PlayerInfo, MAX_PLAYER_NAME_LEN};
+    use super::{PlayerIdentity, PlayerInfo, MAX_PLAYER_NAME_LEN};
 
     #[test]
     /// Test if constructor is not messing up data
@@ -149,9 +221,10 @@ mod tests {
                 p_info.name(),
                 &name[..usize::min(name.len(), MAX_PLAYER_NAME_LEN)]
             );
-            assert!(p_info.name().len() <= MAX_PLAYER_NAME_LEN);
+            assert!(p_info.name().chars().count() <= MAX_PLAYER_NAME_LEN);
             assert_eq!(p_info.is_human(), is_human);
-            assert_eq!(p_info.rage(), rage);
+            assert_eq!(p_info.rage(), Some(rage));
+            assert_eq!(p_info.identity(), PlayerIdentity::from(rage));
             ids.push(p_info.id());
         }
 
@@ -164,4 +237,82 @@ mod tests {
         let p_info = PlayerInfo::new("", PlayerRage::Red, true);
         assert_eq!(p_info.name(), "");
     }
+
+    #[test]
+    /// Check if non-ASCII names are truncated on grapheme boundaries
+    fn unicode_name_test() {
+        let names = [
+            ("Zoë", "Zoë"),
+            ("Åsa Øberg-Ñúñez", "Åsa Øberg-Ñúñez"),
+            (
+                "日本語のプレイヤーの名前はとても長い",
+                "日本語のプレイヤーの名前はとても",
+            ),
+            (
+                "🎮🎮🎮🎮🎮🎮🎮🎮🎮🎮🎮🎮🎮🎮🎮🎮🎮",
+                "🎮🎮🎮🎮🎮🎮🎮🎮🎮🎮🎮🎮🎮🎮🎮🎮",
+            ),
+        ];
+        for (name, expected) in &names {
+            let p_info = PlayerInfo::new(name, PlayerRage::Red, true);
+            assert_eq!(p_info.name(), *expected);
+        }
+
+        // Combining marks and emoji sequences are kept whole
+        let name = "e\u{301}".repeat(20);
+        let p_info = PlayerInfo::new(&name, PlayerRage::Red, true);
+        assert_eq!(p_info.name(), "e\u{301}".repeat(MAX_PLAYER_NAME_LEN));
+        let family = "👨\u{200d}👩\u{200d}👧";
+        let name = format!("{}{}", "x".repeat(15), family);
+        let p_info = PlayerInfo::new(&name, PlayerRage::Red, true);
+        assert_eq!(p_info.name(), name);
+    }
+
+    #[test]
+    /// Check if invalid or duplicate names are rejected by validated constructor
+    fn validated_test() {
+        let identity = PlayerIdentity::from(PlayerRage::Green);
+        let mut players = vec![];
+        for name in &["Åsa", "Zoë", "  名前  ", "🎮"] {
+            let player = PlayerInfo::validated(name, identity, true, &players).unwrap();
+            players.push(player);
+        }
+        assert_eq!(players[2].name(), "名前");
+
+        for name in &["", "   ", "tab\there", "new\nline", "nul\0", "bell\u{7}"] {
+            assert!(PlayerInfo::validated(name, identity, true, &players).is_none());
+        }
+        for name in &["Zoë", " 名前", "🎮 "] {
+            assert!(PlayerInfo::validated(name, identity, true, &players).is_none());
+        }
+        // Names are the same once truncated
+        let long = "ÆØÅ".repeat(10);
+        let first = PlayerInfo::validated(&long, identity, false, &players).unwrap();
+        players.push(first);
+        assert!(PlayerInfo::validated(&format!("{}!", long), identity, false, &players).is_none());
+    }
+
+    #[test]
+    /// Check if more than six players can have different identities
+    fn identity_test() {
+        let players: Vec<_> = (0..PlayerIdentity::palette_size())
+            .map(|i| {
+                let identity = PlayerIdentity::from_palette(i).unwrap();
+                PlayerInfo::with_identity(&format!("P{}", i), identity, true)
+            })
+            .collect();
+
+        for (i, p) in players.iter().enumerate() {
+            assert_eq!(p.identity(), PlayerIdentity::from_palette(i).unwrap());
+            assert_eq!(p.rage().is_some(), i < PlayerRage::all().len());
+        }
+        let json = serde_json::to_string(&players[8]).unwrap();
+        assert!(json.contains(r#""identity":{"color""#));
+
+        // Changing the identity keeps the player
+        let mut p = players[0].clone();
+        p.set_identity(PlayerIdentity::from(PlayerRage::Yellow));
+        assert_eq!(p.id(), players[0].id());
+        assert_eq!(p.rage(), Some(PlayerRage::Yellow));
+    }
 }
@@ -0,0 +1,229 @@
+use log::warn;
+use serde::{Deserialize, Serialize};
+
+use crate::common::{
+    clone_arc::Clonable,
+    id_generator::{new_id, Id},
+    serializable::Serializable,
+};
+
+use super::player::PlayerId;
+
+/// Type used to store team ID
+pub type TeamId = Id;
+
+/// A group of allied players sharing victory conditions
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct Team {
+    /// Id of the team
+    id: TeamId,
+    /// Team name
+    name: String,
+    /// Members of the team (in turn order)
+    members: Vec<PlayerId>,
+}
+
+impl Team {
+    /// Create a new team with provided members (in turn order).
+    ///
+    /// Returns `None` if team has no members or a player is listed twice.
+    pub fn new(name: &str, members: &[PlayerId]) -> Option<Self> {
+        let mut sorted = members.to_vec();
+        sorted.sort_unstable();
+        sorted.dedup();
+
+        if members.is_empty() || sorted.len() != members.len() {
+            warn!("Invalid members for team {}: {:?}", name, members);
+            None
+        } else {
+            Some(Self {
+                id: new_id(),
+                name: String::from(name),
+                members: members.to_vec(),
+            })
+        }
+    }
+
+    /// Returns team ID
+    pub fn id(&self) -> TeamId {
+        self.id
+    }
+
+    /// Returns team name
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns members of the team (in turn order)
+    pub fn members(&self) -> &[PlayerId] {
+        &self.members
+    }
+
+    /// Returns whether provided player is a member of this team
+    pub fn has_member(&self, player_id: PlayerId) -> bool {
+        self.members.contains(&player_id)
+    }
+}
+
+/// Teams of a game.
+///
+/// Players not belonging to any team play on their own (by default there are no teams).
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct Teams {
+    teams: Vec<Team>,
+}
+
+impl Teams {
+    /// Create the teams of a game.
+    ///
+    /// Returns `None` if a player belongs to more than one team.
+    pub fn new(teams: Vec<Team>) -> Option<Self> {
+        let mut players: Vec<_> = teams.iter().flat_map(|t| t.members()).collect();
+        let count = players.len();
+        players.sort_unstable();
+        players.dedup();
+
+        if players.len() == count {
+            Some(Self { teams })
+        } else {
+            warn!("A player belongs to more than one team");
+            None
+        }
+    }
+
+    /// Returns all teams
+    pub fn teams(&self) -> &[Team] {
+        &self.teams
+    }
+
+    /// Returns whether there are no teams (everybody plays on its own)
+    pub fn is_empty(&self) -> bool {
+        self.teams.is_empty()
+    }
+
+    /// Returns the team of provided player (`None` if player plays on its own)
+    pub fn team_of(&self, player_id: PlayerId) -> Option<&Team> {
+        self.teams.iter().find(|t| t.has_member(player_id))
+    }
+
+    /// Returns whether two players are allies (a player is always allied with itself)
+    pub fn are_allies(&self, player: PlayerId, other: PlayerId) -> bool {
+        player == other || self.team_of(player).is_some_and(|t| t.has_member(other))
+    }
+
+    /// Returns provided players in turn order, interleaving teams.
+    ///
+    /// Sides (teams or players on their own) are ordered as their first player in `players`,
+    /// then the first member of each side plays, then the second one and so on.
+    /// Team members not listed in `players` are ignored.
+    pub fn turn_order(&self, players: &[PlayerId]) -> Vec<PlayerId> {
+        // Players of each side, in turn order
+        let mut sides: Vec<Vec<PlayerId>> = vec![];
+        for player in players {
+            match self.team_of(*player) {
+                Some(team) => {
+                    if !sides.iter().any(|s| team.has_member(s[0])) {
+                        sides.push(
+                            team.members()
+                                .iter()
+                                .filter(|m| players.contains(m))
+                                .copied()
+                                .collect(),
+                        );
+                    }
+                }
+                None => sides.push(vec![*player]),
+            }
+        }
+
+        let rounds = sides.iter().map(Vec::len).max().unwrap_or(0);
+        (0..rounds)
+            .flat_map(|round| sides.iter().filter_map(move |s| s.get(round).copied()))
+            .collect()
+    }
+}
+
+impl Serializable for Teams {
+    /// Type of data to be serialized
+    type Data = Teams;
+
+    /// Return data to be serialized
+    fn data_to_be_serialized(&self) -> &Self::Data {
+        self
+    }
+}
+
+impl Clonable for Teams {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn teams(members: &[&[PlayerId]]) -> Teams {
+        Teams::new(
+            members
+                .iter()
+                .enumerate()
+                .map(|(i, m)| Team::new(&format!("T{}", i), m).unwrap())
+                .collect(),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    /// Check if invalid teams are rejected
+    fn new_test() {
+        assert!(Team::new("Empty", &[]).is_none());
+        assert!(Team::new("Twice", &[1, 2, 1]).is_none());
+
+        let t0 = Team::new("A", &[1, 2]).unwrap();
+        let t1 = Team::new("B", &[3, 2]).unwrap();
+        assert_ne!(t0.id(), t1.id());
+        assert_eq!(t0.name(), "A");
+        assert_eq!(t0.members(), &[1, 2]);
+        assert!(Teams::new(vec![t0.clone(), t1]).is_none());
+        assert!(Teams::new(vec![t0]).is_some());
+        assert!(Teams::default().is_empty());
+    }
+
+    #[test]
+    /// Check alliances
+    fn allies_test() {
+        let teams = teams(&[&[1, 2], &[3, 4]]);
+
+        assert!(teams.are_allies(1, 2));
+        assert!(teams.are_allies(4, 3));
+        assert!(!teams.are_allies(1, 3));
+        // Players on their own
+        assert!(teams.are_allies(5, 5));
+        assert!(!teams.are_allies(5, 6));
+        assert!(!teams.are_allies(5, 1));
+        assert_eq!(teams.team_of(3), Some(&teams.teams()[1]));
+        assert_eq!(teams.team_of(5), None);
+    }
+
+    #[test]
+    /// Check if turn order interleaves teams
+    fn turn_order_test() {
+        // 2v2
+        let teams_2v2 = teams(&[&[1, 2], &[3, 4]]);
+        assert_eq!(teams_2v2.turn_order(&[1, 2, 3, 4]), vec![1, 3, 2, 4]);
+        assert_eq!(teams_2v2.turn_order(&[3, 1, 4, 2]), vec![3, 1, 4, 2]);
+
+        // 3v3
+        let teams_3v3 = teams(&[&[1, 2, 3], &[4, 5, 6]]);
+        assert_eq!(
+            teams_3v3.turn_order(&[1, 2, 3, 4, 5, 6]),
+            vec![1, 4, 2, 5, 3, 6]
+        );
+
+        // Uneven teams and players on their own
+        let uneven = teams(&[&[1, 2, 3], &[4]]);
+        assert_eq!(uneven.turn_order(&[1, 2, 3, 4, 5]), vec![1, 4, 5, 2, 3]);
+        // Missing members are ignored
+        assert_eq!(uneven.turn_order(&[1, 4, 3]), vec![1, 4, 3]);
+
+        // No teams
+        assert_eq!(Teams::default().turn_order(&[3, 1, 2]), vec![3, 1, 2]);
+    }
+}
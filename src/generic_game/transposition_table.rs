@@ -0,0 +1,211 @@
+use std::sync::Mutex;
+
+use super::zobrist::StateHash;
+
+/// Type used for search depth
+pub type Depth = usize;
+
+/// Entry stored in a `TranspositionTable`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TranspositionEntry<T> {
+    /// Hash of the game state this entry refers to
+    hash: StateHash,
+    /// Depth of the search that produced this entry
+    depth: Depth,
+    /// Value computed by the search
+    value: T,
+}
+
+impl<T> TranspositionEntry<T> {
+    /// Returns hash of the game state this entry refers to
+    pub fn hash(&self) -> StateHash {
+        self.hash
+    }
+
+    /// Returns depth of the search that produced this entry
+    pub fn depth(&self) -> Depth {
+        self.depth
+    }
+
+    /// Returns value computed by the search
+    pub fn value(&self) -> &T {
+        &self.value
+    }
+}
+
+/// Bounded cache of search results indexed by game state hash.
+///
+/// Table has a fixed number of slots, when two states fall in the same slot
+/// the entry computed with the deeper search is kept.
+/// All methods take `&self`, so the table can be shared between searches
+/// (also running in different threads) wrapping it into an `Arc`.
+#[derive(Debug)]
+pub struct TranspositionTable<T> {
+    /// Slots of the table
+    slots: Mutex<Vec<Option<TranspositionEntry<T>>>>,
+    /// Mask used to compute slot index from hash
+    mask: usize,
+}
+
+impl<T: Clone> TranspositionTable<T> {
+    /// Create an empty table with at least `capacity` slots.
+    ///
+    /// *Note*: Capacity is rounded up to the next power of two (minimum 1).
+    pub fn new(capacity: usize) -> Self {
+        let size = capacity.max(1).next_power_of_two();
+        Self {
+            slots: Mutex::new(vec![None; size]),
+            mask: size - 1,
+        }
+    }
+
+    /// Returns the number of slots of this table
+    pub fn capacity(&self) -> usize {
+        self.mask + 1
+    }
+
+    /// Returns the number of entries stored in this table
+    pub fn len(&self) -> usize {
+        self.lock().iter().filter(|slot| slot.is_some()).count()
+    }
+
+    /// Returns whether this table does not contain any entry
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Store a value computed for the state with provided hash.
+    ///
+    /// Returns `false` if the slot is already used by an entry computed with a deeper search
+    /// for another state, in that case the value is not stored.
+    pub fn store(&self, hash: StateHash, depth: Depth, value: T) -> bool {
+        let index = self.index(hash);
+        let mut slots = self.lock();
+        let slot = &mut slots[index];
+
+        let replace = match slot {
+            Some(entry) => entry.hash == hash || entry.depth <= depth,
+            None => true,
+        };
+        if replace {
+            *slot = Some(TranspositionEntry { hash, depth, value });
+        }
+        replace
+    }
+
+    /// Returns the entry stored for the state with provided hash.
+    ///
+    /// `None` if there is no entry for this state.
+    pub fn probe(&self, hash: StateHash) -> Option<TranspositionEntry<T>> {
+        let index = self.index(hash);
+        match &self.lock()[index] {
+            Some(entry) if entry.hash == hash => Some(entry.clone()),
+            _ => None,
+        }
+    }
+
+    /// Remove all entries
+    pub fn clear(&self) {
+        self.lock().iter_mut().for_each(|slot| *slot = None);
+    }
+
+    /// Returns slot index for the provided hash
+    fn index(&self, hash: StateHash) -> usize {
+        hash as usize & self.mask
+    }
+
+    /// Lock the slots (a poisoned lock is still usable since each write is atomic)
+    fn lock(&self) -> std::sync::MutexGuard<'_, Vec<Option<TranspositionEntry<T>>>> {
+        self.slots
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{sync::Arc, thread};
+
+    use super::*;
+    use crate::test_utilities::{
+        constants::NUMBER_OF_LOOPS_FOR_NORMAL_TEST, random_generator::random_number,
+    };
+
+    #[test]
+    /// Check if capacity is rounded to power of two
+    fn new_test() {
+        assert_eq!(TranspositionTable::<u8>::new(0).capacity(), 1);
+        assert_eq!(TranspositionTable::<u8>::new(1).capacity(), 1);
+        assert_eq!(TranspositionTable::<u8>::new(3).capacity(), 4);
+        assert_eq!(TranspositionTable::<u8>::new(1024).capacity(), 1024);
+        assert!(TranspositionTable::<u8>::new(16).is_empty());
+    }
+
+    #[test]
+    /// Check if stored values can be read back
+    fn store_probe_test() {
+        for _ in 0..NUMBER_OF_LOOPS_FOR_NORMAL_TEST {
+            let table = TranspositionTable::new(16);
+            let hash = random_number(0, u64::MAX);
+            let depth = random_number(0, 10);
+            let value = random_number(0, i32::MAX);
+
+            assert!(table.store(hash, depth, value));
+            let entry = table.probe(hash).expect("Entry shall be stored");
+            assert_eq!(entry.hash(), hash);
+            assert_eq!(entry.depth(), depth);
+            assert_eq!(*entry.value(), value);
+
+            table.clear();
+            assert!(table.is_empty());
+            assert!(table.probe(hash).is_none());
+        }
+    }
+
+    #[test]
+    /// Check if table is bounded and keeps deeper entries on collision
+    fn replacement_test() {
+        let table = TranspositionTable::new(4);
+        for hash in 0..100 {
+            table.store(hash, 1, hash);
+        }
+        assert_eq!(table.len(), 4);
+
+        // Same slot of hash 0 (4 slots), but shallower search
+        assert!(table.store(0, 5, 0));
+        assert!(!table.store(4, 2, 4));
+        assert!(table.probe(4).is_none());
+        assert_eq!(table.probe(0).unwrap().depth(), 5);
+
+        // Same state is always replaced
+        assert!(table.store(0, 1, 42));
+        assert_eq!(*table.probe(0).unwrap().value(), 42);
+    }
+
+    #[test]
+    /// Check if table can be shared between threads
+    fn shared_test() {
+        const NTHREADS: u64 = 8;
+        let table = Arc::new(TranspositionTable::new(1 << 16));
+
+        let handles: Vec<_> = (0..NTHREADS)
+            .map(|t| {
+                let table = Arc::clone(&table);
+                thread::spawn(move || {
+                    for i in 0..1000 {
+                        table.store(t * 1000 + i, 1, t);
+                    }
+                })
+            })
+            .collect();
+        for handle in handles {
+            handle.join().unwrap();
+        }
+
+        for t in 0..NTHREADS {
+            for i in 0..1000 {
+                assert_eq!(*table.probe(t * 1000 + i).unwrap().value(), t);
+            }
+        }
+    }
+}
@@ -0,0 +1,106 @@
+use crate::geometry::vector::Position;
+
+use super::cell_state::CellOccupation;
+
+/// Type used to store the hash of a game state
+pub type StateHash = u64;
+
+/// Index of a player in the turn order of a game.
+///
+/// Keys use seats and not `PlayerId`s, since IDs are generated by each process.
+pub type Seat = usize;
+
+/// Seed used to derive all Zobrist keys.
+///
+/// Keys are derived from this constant and not from a random generator,
+/// so every machine computes the same hash for the same state.
+const ZOBRIST_SEED: u64 = 0x5EED_A70B_2020_0001;
+
+/// Discriminators used to keep keys of different kind of features apart
+const CELL_FEATURE: u64 = 1;
+const CURRENT_PLAYER_FEATURE: u64 = 2;
+
+/// Mixing function (SplitMix64 finalizer) used to turn features into keys
+fn mix(value: u64) -> u64 {
+    let mut z = value.wrapping_add(0x9E37_79B9_7F4A_7C15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^ (z >> 31)
+}
+
+/// Combine a list of values into a single key
+fn key_of(values: &[u64]) -> StateHash {
+    values
+        .iter()
+        .fold(ZOBRIST_SEED, |acc, value| mix(acc ^ mix(*value)))
+}
+
+/// Returns the Zobrist key of a cell at `position` owned by the player at `seat`
+/// with `occupation` elements.
+///
+/// An empty cell (occupation equal to 0) has key 0, thus it does not
+/// contribute to the hash.
+pub fn cell_key(position: &Position, seat: Seat, occupation: CellOccupation) -> StateHash {
+    if occupation == 0 {
+        0
+    } else {
+        key_of(&[
+            CELL_FEATURE,
+            position.x() as u64,
+            position.y() as u64,
+            position.z() as u64,
+            seat as u64,
+            u64::from(occupation),
+        ])
+    }
+}
+
+/// Returns the Zobrist key for the player at `seat` being the current player.
+pub fn current_player_key(seat: Seat) -> StateHash {
+    key_of(&[CURRENT_PLAYER_FEATURE, seat as u64])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utilities::{
+        common::check_for_duplicate, constants::NUMBER_OF_LOOPS_FOR_NORMAL_TEST,
+        random_generator::*,
+    };
+
+    #[test]
+    /// Check if keys are stable and do not depend on the call order
+    fn deterministic_test() {
+        for _ in 0..NUMBER_OF_LOOPS_FOR_NORMAL_TEST {
+            let pos = random_vector(-100, 100);
+            let seat = random_number(0, 16);
+            let occ = random_number(1, u8::MAX);
+
+            assert_eq!(cell_key(&pos, seat, occ), cell_key(&pos, seat, occ));
+            assert_eq!(current_player_key(seat), current_player_key(seat));
+            assert_eq!(cell_key(&pos, seat, 0), 0);
+        }
+    }
+
+    #[test]
+    /// Check if neighbour features generate different keys
+    fn collision_test() {
+        let mut keys = vec![];
+        for x in -4..4 {
+            for y in -4..4 {
+                for z in -4..4 {
+                    for seat in 0..4 {
+                        for occ in 1..4 {
+                            keys.push(cell_key(&Position::new(x, y, z), seat, occ));
+                        }
+                    }
+                }
+            }
+        }
+        for seat in 0..64 {
+            keys.push(current_player_key(seat));
+        }
+
+        assert!(check_for_duplicate(&mut keys));
+    }
+}
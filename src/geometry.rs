@@ -5,3 +5,5 @@ pub mod volume;
 
 // Imports CellType, CellMaterial, Terrain
 pub mod terrain;
+// Imports Topology
+pub mod topology;
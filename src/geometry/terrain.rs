@@ -4,19 +4,20 @@ use crate::common::serializable::Serializable;
 
 use super::{vector::Position, volume::Volume};
 use log::warn;
-use serde::ser::{SerializeSeq, Serializer};
-use serde::Serialize;
-use std::collections::{hash_map::Iter, HashMap};
+use serde::de::{Deserializer, Error};
+use serde::ser::{SerializeStruct, Serializer};
+use serde::{Deserialize, Serialize};
+use std::collections::{btree_map, BTreeMap};
 
 /// Possible types of cell materials
-#[derive(Debug, Copy, Clone, PartialEq, Serialize)]
+#[derive(Debug, Copy, Clone, PartialEq, Serialize, Deserialize)]
 pub enum CellMaterial {
     Water,
     Ground,
 }
 
 /// Possible types of cell
-#[derive(Debug, Copy, Clone, PartialEq, Serialize)]
+#[derive(Debug, Copy, Clone, PartialEq, Serialize, Deserialize)]
 pub enum CellType {
     /// Cell full of material (cannot placed anything on top of that)
     Fill(CellMaterial),
@@ -24,30 +25,79 @@ pub enum CellType {
     Flat(CellMaterial),
 }
 
+impl CellType {
+    /// Returns the material of the cell
+    pub fn material(&self) -> CellMaterial {
+        match self {
+            CellType::Fill(material) | CellType::Flat(material) => *material,
+        }
+    }
+}
+
 /// Represents a terrain where players can place their pawns
 ///
 /// Terrain has a volume (its bounding box), cells can be placed only
 /// inside this volume.
+/// Cells are always iterated (and serialized) in volume order, so that all the
+/// peers of a game see the same terrain in the same order.
 #[derive(Debug, Clone)]
 pub struct Terrain {
     /// Max bounduary box (cell cannot be placed outside of this volume)
     volume: Volume,
-    /// Maps that for each valid position returns the type of cell at that position.
-    cells: HashMap<Position, CellType>,
+    /// Type of cell of each non empty position, by its index in the volume
+    /// (sorted as volume iterator).
+    cells: BTreeMap<usize, CellType>,
 }
 
 impl Serialize for Terrain {
-    /// Serialize contain of Terrain
+    /// Serialize volume and cells of Terrain
     fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
     where
         S: Serializer,
     {
-        let mut seq = serializer.serialize_seq(Some(self.cells.len()))?;
-        for cell in &self.cells {
-            seq.serialize_element(&cell)?;
+        let mut state = serializer.serialize_struct("Terrain", 2)?;
+        state.serialize_field("volume", &self.volume)?;
+        state.serialize_field("cells", &Cells(self))?;
+        state.end()
+    }
+}
+
+/// Cells of a terrain, serialized in volume order
+struct Cells<'a>(&'a Terrain);
+
+impl<'a> Serialize for Cells<'a> {
+    /// Serialize each cell with its position
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.collect_seq(self.0)
+    }
+}
+
+impl<'de> Deserialize<'de> for Terrain {
+    /// Deserialize a terrain, refusing cells outside of its volume
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        #[derive(Deserialize)]
+        struct Data {
+            volume: Volume,
+            cells: Vec<(Position, CellType)>,
         }
 
-        seq.end()
+        let data = Data::deserialize(deserializer)?;
+        let mut terrain = Terrain::new(&data.volume);
+        for (position, cell_type) in data.cells {
+            if !terrain.set_cell_at(&position, cell_type) {
+                return Err(D::Error::custom(format!(
+                    "Cell {:?} is outside of terrain",
+                    position
+                )));
+            }
+        }
+        Ok(terrain)
     }
 }
 
@@ -58,7 +108,7 @@ impl Terrain {
     pub fn new(volume: &Volume) -> Self {
         Self {
             volume: *volume,
-            cells: HashMap::new(),
+            cells: BTreeMap::new(),
         }
     }
 
@@ -77,7 +127,7 @@ impl Terrain {
             let mut lower = (i32::MAX, i32::MAX, i32::MAX);
             let mut upper = (i32::MIN, i32::MIN, i32::MIN);
 
-            for (p, _) in &self.cells {
+            for (p, _) in self {
                 if p.x() < lower.0 {
                     lower.0 = p.x();
                 }
@@ -115,15 +165,15 @@ impl Terrain {
             warn!("Position {:?} is outside of terrain", position);
             return None;
         }
-        self.cells.get(position).copied()
+        self.cell(position)
     }
 
     /// Set the cell type at selected position
     ///
     /// The cell is update only if the position is inside the volume and return `true`, otherwise returns `false`
     pub fn set_cell_at(&mut self, position: &Position, cell_type: CellType) -> bool {
-        if self.volume.is_inside(&position) {
-            self.cells.insert(*position, cell_type);
+        if let Some(index) = self.volume.index_of(position) {
+            self.cells.insert(index, cell_type);
             true
         } else {
             warn!(
@@ -138,8 +188,8 @@ impl Terrain {
     ///
     /// If cell existed return true
     pub fn remove_cell_at(&mut self, position: &Position) -> bool {
-        if self.volume.is_inside(position) {
-            self.cells.remove(position).is_some()
+        if let Some(index) = self.volume.index_of(position) {
+            self.cells.remove(&index).is_some()
         } else {
             warn!(
                 "Position {:?} is not valid for terrain {:?}",
@@ -148,6 +198,51 @@ impl Terrain {
             false
         }
     }
+
+    /// Returns cell type at provided position (`None` if position is empty or outside the volume)
+    fn cell(&self, position: &Position) -> Option<CellType> {
+        self.volume
+            .index_of(position)
+            .and_then(|index| self.cells.get(&index).copied())
+    }
+
+    /// Returns whether stuff can be placed on top of the cell at provided position
+    /// (that is a `Flat` cell)
+    pub fn is_support(&self, position: &Position) -> bool {
+        matches!(self.cell(position), Some(CellType::Flat(_)))
+    }
+
+    /// Returns whether stuff can be placed at provided position: position is empty and
+    /// the cell directly below is a `Flat` cell (stuff is never placed inside a cell).
+    pub fn is_playable(&self, position: &Position) -> bool {
+        self.volume.is_inside(position)
+            && self.cell(position).is_none()
+            && self.is_support(&position.below())
+    }
+
+    /// Returns all playable positions (in volume order)
+    pub fn playable_positions(&self) -> Vec<Position> {
+        self.volume
+            .into_iter()
+            .filter(|p| self.is_playable(p))
+            .collect()
+    }
+
+    /// Returns the position where stuff released at provided position falls to.
+    ///
+    /// Returns `None` if position is not empty or stuff does not land on a `Flat` cell
+    /// (it lands on a `Fill` cell or falls out of the volume).
+    pub fn landing_position(&self, position: &Position) -> Option<Position> {
+        let mut current = *position;
+
+        while self.volume.is_inside(&current) && self.cell(&current).is_none() {
+            if self.is_playable(&current) {
+                return Some(current);
+            }
+            current = current.below();
+        }
+        None
+    }
 }
 
 impl Serializable for Terrain {
@@ -156,20 +251,56 @@ impl Serializable for Terrain {
 
     /// Return data to be serialized
     fn data_to_be_serialized(&self) -> &Self::Data {
-        &self
+        self
     }
 }
 
+/// Support struct for iteration over the cells of a Terrain (in volume order)
+pub struct TerrainIterator<'a> {
+    cells: btree_map::Iter<'a, usize, CellType>,
+    volume: Volume,
+}
+
+impl<'a> Iterator for TerrainIterator<'a> {
+    type Item = (Position, &'a CellType);
+
+    /// Returns next non empty position with its cell
+    fn next(&mut self) -> Option<Self::Item> {
+        self.cells.next().map(|(index, cell)| {
+            let position = self
+                .volume
+                .position_at(*index)
+                .expect("Cell index shall be valid");
+            (position, cell)
+        })
+    }
+
+    /// Returns the number of cells left (without computing their positions)
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.cells.size_hint()
+    }
+
+    /// Returns the number of cells left (without computing their positions)
+    fn count(self) -> usize {
+        self.cells.len()
+    }
+}
+
+impl<'a> ExactSizeIterator for TerrainIterator<'a> {}
+
 impl<'a> IntoIterator for &'a Terrain {
     /// Type of elements being iterated over
-    type Item = (&'a Position, &'a CellType);
+    type Item = (Position, &'a CellType);
     /// Type of iterator being turned into
-    type IntoIter = Iter<'a, Position, CellType>;
+    type IntoIter = TerrainIterator<'a>;
 
-    /// Returns an iterator that iterate along all position inside
-    /// this volume
+    /// Returns an iterator that iterate along all cells of this terrain,
+    /// in volume order
     fn into_iter(self) -> Self::IntoIter {
-        self.cells.iter()
+        TerrainIterator {
+            cells: self.cells.iter(),
+            volume: self.volume,
+        }
     }
 }
 
@@ -293,20 +424,91 @@ mod tests {
                     for z in vol.bottom_left_corner().z() - 2..vol.bottom_left_corner().z() + 2 {
                         let pos = Position::new(x, y, z);
 
-                        assert_eq!(terrain.remove_cell_at(&pos), false);
+                        assert!(!terrain.remove_cell_at(&pos));
                     }
                 }
             }
         }
     }
 
+    #[test]
+    /// Check which positions are playable: above flat cells, never inside cells
+    fn placement_test() {
+        let vol = Volume::new(&Position::zero(), &Position::new(2, 2, 3)).unwrap();
+        let mut terrain = Terrain::new(&vol);
+        let flat = Position::new(0, 0, 0);
+        let fill = Position::new(1, 0, 0);
+        terrain.set_cell_at(&flat, CellType::Flat(CellMaterial::Ground));
+        terrain.set_cell_at(&fill, CellType::Fill(CellMaterial::Ground));
+
+        assert!(terrain.is_support(&flat));
+        assert!(!terrain.is_support(&fill));
+        assert!(!terrain.is_support(&Position::new(2, 0, 0)));
+
+        assert!(terrain.is_playable(&Position::new(0, 0, 1)));
+        assert!(!terrain.is_playable(&Position::new(0, 0, 2)));
+        assert!(!terrain.is_playable(&Position::new(1, 0, 1)));
+        assert!(!terrain.is_playable(&flat));
+        assert_eq!(terrain.playable_positions(), vec![Position::new(0, 0, 1)]);
+
+        // A flat cell on top of another one hides it
+        terrain.set_cell_at(&Position::new(0, 0, 1), CellType::Flat(CellMaterial::Water));
+        assert_eq!(terrain.playable_positions(), vec![Position::new(0, 0, 2)]);
+
+        assert_eq!(
+            terrain.landing_position(&Position::new(0, 0, 3)),
+            Some(Position::new(0, 0, 2))
+        );
+        assert_eq!(
+            terrain.landing_position(&Position::new(0, 0, 2)),
+            Some(Position::new(0, 0, 2))
+        );
+        assert_eq!(terrain.landing_position(&Position::new(0, 0, 1)), None);
+        assert_eq!(terrain.landing_position(&Position::new(1, 0, 3)), None);
+        assert_eq!(terrain.landing_position(&Position::new(2, 2, 3)), None);
+        assert_eq!(terrain.landing_position(&Position::new(0, 0, 4)), None);
+    }
+
+    #[test]
+    /// Check if cells are iterated in volume order, whatever the order they are set
+    fn iteration_order_test() {
+        for _ in 0..NUMBER_OF_LOOPS_FOR_SMALL_TEST {
+            let vol = random_volume(1, 6);
+            let (terrain, cells) = random_terrain(&vol);
+
+            let mut shuffled = Terrain::new(&vol);
+            let mut positions: Vec<_> = cells.keys().collect();
+            positions.sort_by_key(|p| std::cmp::Reverse(vol.index_of(p)));
+            for p in positions {
+                shuffled.set_cell_at(p, cells[p]);
+            }
+
+            let expected: Vec<_> = vol.into_iter().filter(|p| cells.contains_key(p)).collect();
+            let iterated: Vec<_> = terrain.into_iter().map(|(p, _)| p).collect();
+            assert_eq!(iterated, expected);
+            assert_eq!(
+                serde_json::to_string(&terrain).unwrap(),
+                serde_json::to_string(&shuffled).unwrap()
+            );
+        }
+    }
+
+    #[test]
+    /// Check if material is returned for both cell types
+    fn material_test() {
+        for material in &[CellMaterial::Ground, CellMaterial::Water] {
+            assert_eq!(CellType::Fill(*material).material(), *material);
+            assert_eq!(CellType::Flat(*material).material(), *material);
+        }
+    }
+
     #[test]
     /// Check if serialization + deserialization of a terrain is consistent
     fn serialize_test() {
         for _ in 0..NUMBER_OF_LOOPS_FOR_SMALL_TEST {
             let vol = random_volume(1, 10);
             let (t, cells) = random_terrain(&vol);
-            let serialized_t = serde_json::to_string(&t).ok().expect("Cannot serialize");
+            let serialized_t = serde_json::to_string(&t).expect("Cannot serialize");
             assert_eq!(cells.len(), t.into_iter().count());
             // For each cell we need at least 24 chars.
             assert!(
@@ -315,6 +517,17 @@ mod tests {
                 serialized_t.len(),
                 cells.len() * 24
             );
+
+            let deserialized: Terrain = serde_json::from_str(&serialized_t).unwrap();
+            assert_eq!(deserialized.volume(), vol);
+            assert_eq!(
+                deserialized.into_iter().collect::<Vec<_>>(),
+                t.into_iter().collect::<Vec<_>>()
+            );
         }
+
+        // Cells outside of the volume are refused
+        let json = r#"{"volume":{"bottom_left_corner":[0,0,0],"top_right_corner":[1,1,1]},"cells":[[[2,0,0],{"Fill":"Water"}]]}"#;
+        assert!(serde_json::from_str::<Terrain>(json).is_err());
     }
 }
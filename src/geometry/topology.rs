@@ -0,0 +1,168 @@
+use crate::common::serializable::Serializable;
+
+use super::{
+    vector::{Distance, Position, Scalar},
+    volume::Volume,
+};
+use serde::{Deserialize, Serialize};
+
+/// Describes how the axes of a volume behave at its bounduaries.
+///
+/// A bounded axis clips positions outside the volume, a wrapping axis connects
+/// the two opposite faces of the volume (like a torus).
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Topology {
+    wrap_x: bool,
+    wrap_y: bool,
+    wrap_z: bool,
+}
+
+impl Topology {
+    /// Create a new topology, choosing which axes wrap around
+    pub fn new(wrap_x: bool, wrap_y: bool, wrap_z: bool) -> Self {
+        Self {
+            wrap_x,
+            wrap_y,
+            wrap_z,
+        }
+    }
+
+    /// Topology where no axis wraps around (positions outside the volume are clipped)
+    pub fn bounded() -> Self {
+        Self::new(false, false, false)
+    }
+
+    /// Topology where all axes wrap around
+    pub fn torus() -> Self {
+        Self::new(true, true, true)
+    }
+
+    /// Returns whether X axis wraps around
+    pub fn wraps_x(&self) -> bool {
+        self.wrap_x
+    }
+
+    /// Returns whether Y axis wraps around
+    pub fn wraps_y(&self) -> bool {
+        self.wrap_y
+    }
+
+    /// Returns whether Z axis wraps around
+    pub fn wraps_z(&self) -> bool {
+        self.wrap_z
+    }
+
+    /// Maps a position onto the volume, wrapping it along wrapping axes.
+    ///
+    /// Returns `None` if position is outside the volume along a bounded axis.
+    pub fn normalize(&self, volume: &Volume, position: &Position) -> Option<Position> {
+        let blc = volume.bottom_left_corner();
+        let size = volume.diagonal() + Distance::new(1, 1, 1);
+        let wrap = |wraps: bool, value: Scalar, origin: Scalar, size: Scalar| {
+            if wraps {
+                origin + (value - origin).rem_euclid(size)
+            } else {
+                value
+            }
+        };
+
+        let position = Position::new(
+            wrap(self.wrap_x, position.x(), blc.x(), size.x()),
+            wrap(self.wrap_y, position.y(), blc.y(), size.y()),
+            wrap(self.wrap_z, position.z(), blc.z(), size.z()),
+        );
+        if volume.is_inside(&position) {
+            Some(position)
+        } else {
+            None
+        }
+    }
+
+    /// Returns the position reached moving from `position` by `distance` inside the volume
+    ///
+    /// Returns `None` if it is outside the volume along a bounded axis.
+    pub fn translate(
+        &self,
+        volume: &Volume,
+        position: &Position,
+        distance: &Distance,
+    ) -> Option<Position> {
+        self.normalize(volume, &(*position + *distance))
+    }
+}
+
+impl Serializable for Topology {
+    /// Type of data to be serialized
+    type Data = Topology;
+
+    /// Return data to be serialized
+    fn data_to_be_serialized(&self) -> &Self::Data {
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utilities::{constants::*, random_generator::*};
+
+    #[test]
+    /// Check if a bounded topology clips like `Volume::is_inside`
+    fn bounded_test() {
+        for _ in 0..NUMBER_OF_LOOPS_FOR_NORMAL_TEST {
+            let vol = random_volume(1, 10);
+            let pos = random_vector(-120, 120);
+            let topology = Topology::bounded();
+
+            assert_eq!(
+                topology.normalize(&vol, &pos),
+                if vol.is_inside(&pos) { Some(pos) } else { None }
+            );
+        }
+    }
+
+    #[test]
+    /// Check if positions wrap around along wrapping axes only
+    fn wrap_test() {
+        let vol = Volume::new(&Position::new(-1, -1, -1), &Position::new(2, 2, 2)).unwrap();
+        let corner = Position::new(2, 2, 2);
+        let step = Distance::new(1, 1, 1);
+
+        let torus = Topology::torus();
+        assert_eq!(
+            torus.translate(&vol, &corner, &step),
+            Some(Position::new(-1, -1, -1))
+        );
+        assert_eq!(
+            torus.normalize(&vol, &Position::new(-6, 10, 2)),
+            Some(Position::new(2, 2, 2))
+        );
+
+        let cylinder = Topology::new(true, false, false);
+        assert_eq!(
+            cylinder.translate(&vol, &corner, &Distance::new(1, 0, 0)),
+            Some(Position::new(-1, 2, 2))
+        );
+        assert_eq!(cylinder.translate(&vol, &corner, &step), None);
+
+        // Every translated position is inside the volume
+        for _ in 0..NUMBER_OF_LOOPS_FOR_NORMAL_TEST {
+            let vol = random_volume(1, 10);
+            let pos = random_vector(-120, 120);
+            let wrapped = torus.normalize(&vol, &pos).unwrap();
+            assert!(vol.is_inside(&wrapped));
+            let size = vol.diagonal() + step;
+            let diff = wrapped - pos;
+            assert_eq!(diff.x() % size.x(), 0);
+            assert_eq!(diff.y() % size.y(), 0);
+            assert_eq!(diff.z() % size.z(), 0);
+        }
+    }
+
+    #[test]
+    /// Check if serialization works
+    fn serialize_test() {
+        let json = serde_json::to_string(&Topology::new(true, false, true)).unwrap();
+        assert_eq!(json, r#"{"wrap_x":true,"wrap_y":false,"wrap_z":true}"#);
+    }
+}
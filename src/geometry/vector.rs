@@ -1,4 +1,4 @@
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 use std::cmp::Ordering;
 use std::ops::Add;
 use std::ops::Sub;
@@ -9,7 +9,7 @@ use crate::common::serializable::Serializable;
 pub type Scalar = i32;
 
 /// Represent a generic vector of integers
-#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, Serialize)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub struct Vector(Scalar, Scalar, Scalar);
 
 /// A position is a Vector
@@ -58,6 +58,11 @@ impl Vector {
     pub fn zero() -> Self {
         Self::new(0, 0, 0)
     }
+
+    /// Returns the position directly below this one (Z axis points up)
+    pub fn below(&self) -> Self {
+        Self::new(self.0, self.1, self.2 - 1)
+    }
 }
 
 impl Add<Vector> for Vector {
@@ -107,7 +112,7 @@ impl Serializable for Vector {
 
     /// Return data to be serialized
     fn data_to_be_serialized(&self) -> &Self::Data {
-        &self
+        self
     }
 }
 
@@ -201,6 +206,16 @@ mod tests {
         }
     }
 
+    #[test]
+    /// Check if below moves down on Z axis only
+    fn below_test() {
+        for _ in 0..NUMBER_OF_LOOPS_FOR_NORMAL_TEST {
+            let v0 = random_vector(-100, 100);
+
+            assert_eq!(v0.below(), v0 - Vector::new(0, 0, 1));
+        }
+    }
+
     #[test]
     /// Check if getters method work
     fn getter_test() {
@@ -3,8 +3,7 @@ use crate::common::serializable::Serializable;
 use super::vector::Position;
 use super::vector::{Distance, Scalar};
 use log::warn;
-use serde::Serialize;
-use std::cmp::Ordering;
+use serde::{de::Error, Deserialize, Deserializer, Serialize};
 
 /// Represents a bounduary box/volume
 #[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize)]
@@ -14,34 +13,48 @@ pub struct Volume {
     diagonal: Distance,
 }
 
+impl<'de> Deserialize<'de> for Volume {
+    /// Deserialize a volume, refusing invalid corners (diagonal is computed again)
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        #[derive(Deserialize)]
+        struct Data {
+            bottom_left_corner: Position,
+            top_right_corner: Position,
+        }
+
+        let data = Data::deserialize(deserializer)?;
+        Volume::new(&data.bottom_left_corner, &data.top_right_corner)
+            .ok_or_else(|| D::Error::custom("Invalid volume corners"))
+    }
+}
+
 impl Volume {
     /// Create a new volume with provided corners.
     ///
-    /// Volume must at least have size greater or equal to 1 in each dimension,
+    /// Volume must at least have size greater or equal to 1 in each dimension
+    /// (that is each component of `trc` is not less than the `blc` one),
     /// otherwise it returns a `Option::None`.
     ///
     /// * `blc` - Bottom left corner (included)
     /// * `trc` - Top right corner (included)
     pub fn new(blc: &Position, trc: &Position) -> Option<Self> {
-        match blc.partial_cmp(trc) {
-            Some(Ordering::Less) | Some(Ordering::Equal) =>
+        if (*trc - *blc).is_positive() {
             // Bottom left corner is in a valid position
-            {
-                Some(Self {
-                    bottom_left_corner: *blc,
-                    top_right_corner: *trc,
-                    diagonal: *trc - *blc,
-                })
-            }
-            _ =>
+            Some(Self {
+                bottom_left_corner: *blc,
+                top_right_corner: *trc,
+                diagonal: *trc - *blc,
+            })
+        } else {
             // Invalid relative corners position
-            {
-                warn!(
-                    "Provided corners are not valid: BLC {:?} - TRC {:?}",
-                    blc, trc
-                );
-                None
-            }
+            warn!(
+                "Provided corners are not valid: BLC {:?} - TRC {:?}",
+                blc, trc
+            );
+            None
         }
     }
 
@@ -76,6 +89,41 @@ impl Volume {
             && diff.z() <= diagonal.z()
             && diff.is_positive()
     }
+
+    /// Returns the linear index of a position inside the volume.
+    ///
+    /// Index follows the same order of the volume iterator (x, then y, then z).
+    /// `None` if position is outside the volume.
+    pub fn index_of(&self, position: &Position) -> Option<usize> {
+        if !self.is_inside(position) {
+            return None;
+        }
+        let diff = *position - self.bottom_left_corner;
+        let x_size = self.diagonal.x() + 1;
+        let y_size = self.diagonal.y() + 1;
+
+        Some((diff.x() + diff.y() * x_size + diff.z() * x_size * y_size) as usize)
+    }
+
+    /// Returns the position having the provided linear index.
+    ///
+    /// `None` if index is greater or equal than volume.
+    pub fn position_at(&self, index: usize) -> Option<Position> {
+        if index >= self.volume() as usize {
+            return None;
+        }
+        let index = index as Scalar;
+        let origin = self.bottom_left_corner;
+        // Size of volume on X and Y axis
+        let x_size = self.diagonal.x() + 1;
+        let y_size = self.diagonal.y() + 1;
+
+        Some(Position::new(
+            origin.x() + (index % x_size),
+            origin.y() + ((index / x_size) % y_size),
+            origin.z() + (index / (x_size * y_size)),
+        ))
+    }
 }
 
 impl Serializable for Volume {
@@ -84,7 +132,7 @@ impl Serializable for Volume {
 
     /// Return data to be serialized
     fn data_to_be_serialized(&self) -> &Self::Data {
-        &self
+        self
     }
 }
 
@@ -119,24 +167,13 @@ impl<'a> Iterator for VolumeIterator<'a> {
         if self.current_index >= self.size {
             None
         } else {
-            // Origin of volume
-            let origin = self.volume.bottom_left_corner();
-            // Diagonal
-            let diagonal = self.volume.diagonal();
-            // Size of volume on X and Y axis
-            let x_size = diagonal.x() + 1;
-            let y_size = diagonal.y() + 1;
-            // Current index position for iteration
-            let index = self.current_index;
             // Compute position using origin + f(index)
-            let x = origin.x() + (index as Scalar % x_size);
-            let y = origin.y() + ((index as Scalar / x_size) % y_size);
-            let z = origin.z() + (index as Scalar / (x_size * y_size));
+            let position = self.volume.position_at(self.current_index as usize);
 
             // Next position
             self.current_index += 1;
 
-            Some(Position::new(x, y, z))
+            position
         }
     }
 }
@@ -160,6 +197,7 @@ mod tests {
     use super::*;
     use crate::test_utilities::constants::*;
     use crate::test_utilities::random_generator::*;
+    use std::cmp::Ordering;
 
     #[test]
     // Test is `new` method check properly for provided arguments
@@ -190,23 +228,22 @@ mod tests {
 
             if x0 == x1 && y0 == y1 && z0 == z1 {
                 assert_eq!(v0.partial_cmp(&v1), Some(Ordering::Equal));
-                assert!(Volume::new(&v0, &v1).is_some());
+            } else if x0 < x1 && y0 < y1 && z0 < z1 {
+                assert_eq!(v0.partial_cmp(&v1), Some(Ordering::Less));
+            } else if x0 > x1 && y0 > y1 && z0 > z1 {
+                assert_eq!(v0.partial_cmp(&v1), Some(Ordering::Greater));
             } else {
-                if x0 < x1 && y0 < y1 && z0 < z1 {
-                    assert_eq!(v0.partial_cmp(&v1), Some(Ordering::Less));
-                    let vol = Volume::new(&v0, &v1);
-                    assert_ne!(vol, None);
-                    assert_eq!(vol.unwrap().top_right_corner(), v1);
-                    assert_eq!(vol.unwrap().bottom_left_corner(), v0);
-                } else {
-                    if x0 > x1 && y0 > y1 && z0 > z1 {
-                        assert_eq!(v0.partial_cmp(&v1), Some(Ordering::Greater));
-                        assert_eq!(Volume::new(&v0, &v1), None);
-                    } else {
-                        assert_eq!(v0.partial_cmp(&v1), None);
-                        assert_eq!(Volume::new(&v0, &v1), None);
-                    }
-                }
+                assert_eq!(v0.partial_cmp(&v1), None);
+            }
+
+            // Volume is valid if each component of top right corner is not less than
+            // the bottom left corner one (e.g. a single layer)
+            let vol = Volume::new(&v0, &v1);
+            if x0 <= x1 && y0 <= y1 && z0 <= z1 {
+                assert_eq!(vol.unwrap().top_right_corner(), v1);
+                assert_eq!(vol.unwrap().bottom_left_corner(), v0);
+            } else {
+                assert_eq!(vol, None);
             }
         }
 
@@ -217,7 +254,7 @@ mod tests {
 
             let vol = Volume::new(&v0, &v1);
 
-            assert_eq!(vol.is_some(), v0.partial_cmp(&v1) == Some(Ordering::Less));
+            assert_eq!(vol.is_some(), (v1 - v0).is_positive());
         }
     }
 
@@ -278,6 +315,28 @@ mod tests {
         }
     }
 
+    #[test]
+    /// Check if index_of and position_at are one the inverse of the other
+    fn index_test() {
+        for _ in 0..NUMBER_OF_LOOPS_FOR_SMALL_TEST {
+            let vol = random_volume(1, 10);
+
+            for (index, pos) in vol.into_iter().enumerate() {
+                assert_eq!(vol.index_of(&pos), Some(index));
+                assert_eq!(vol.position_at(index), Some(pos));
+            }
+            assert_eq!(vol.position_at(vol.volume() as usize), None);
+            assert_eq!(
+                vol.index_of(&(vol.top_right_corner() + Vector::new(1, 0, 0))),
+                None
+            );
+            assert_eq!(
+                vol.index_of(&(vol.bottom_left_corner() - Vector::new(0, 0, 1))),
+                None
+            );
+        }
+    }
+
     #[test]
     /// Check if volume of one unit is working correctly
     fn one_unit_volume_test() {
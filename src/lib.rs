@@ -0,0 +1,9 @@
+pub mod ai;
+pub mod classic_game;
+pub mod common;
+pub mod generic_game;
+pub mod geometry;
+pub mod network;
+
+#[cfg(test)]
+mod test_utilities;
@@ -1,9 +1 @@
-mod classic_game;
-mod common;
-mod generic_game;
-mod geometry;
-
-#[cfg(test)]
-mod test_utilities;
-
 fn main() {}
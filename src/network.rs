@@ -0,0 +1,20 @@
+// Imports ClientMessage, ServerMessage, StateUpdate, PROTOCOL_VERSION
+pub mod protocol;
+
+// Imports GameServer
+pub mod server;
+
+// Imports GameClient, SpectatorClient
+pub mod client;
+
+// Imports Lobby, InProcessTransport
+pub mod lobby;
+
+// Imports LobbyServer, TcpTransport
+pub mod lobby_server;
+
+// Imports LockstepPeer, Desync, SyncCheck
+pub mod lockstep;
+
+// Imports WebSocketGateway
+pub mod websocket;
@@ -0,0 +1,185 @@
+use std::{
+    io::{self, BufReader},
+    net::{TcpStream, ToSocketAddrs},
+    time::Duration,
+};
+
+use crate::{
+    classic_game::action::ClassicAction, generic_game::action::TurnId,
+    generic_game::player::PlayerId, geometry::vector::Position,
+};
+
+use super::protocol::{read_message, write_message, ClientMessage, ServerMessage, SessionToken};
+
+/// A TCP client playing as a player in a game hosted by a `GameServer`
+#[derive(Debug)]
+pub struct GameClient {
+    /// Player the client is bound to
+    player: PlayerId,
+    /// Token needed to resume the session
+    token: SessionToken,
+    /// Number of actions received since game start
+    received: usize,
+    /// Stream used to receive messages from the server
+    reader: BufReader<TcpStream>,
+    /// Stream used to send messages to the server
+    writer: TcpStream,
+}
+
+impl GameClient {
+    /// Connect to a server, binding the connection to provided player.
+    ///
+    /// Returns an error if the server rejects the player (e.g. another client is already bound to it).
+    pub fn connect<A: ToSocketAddrs>(address: A, player: PlayerId) -> io::Result<Self> {
+        let mut client = Self::open(address, player, 0)?;
+
+        write_message(&mut client.writer, &ClientMessage::Join(player))?;
+        match client.receive()? {
+            ServerMessage::Joined {
+                player: id,
+                token,
+                actions,
+            } if id == player => {
+                client.token = token;
+                client.received = actions;
+                Ok(client)
+            }
+            message => Err(io::Error::new(
+                io::ErrorKind::ConnectionRefused,
+                format!("Cannot join as player {}: {:?}", player, message),
+            )),
+        }
+    }
+
+    /// Connect to a server, resuming the session identified by `token` after a disconnection.
+    ///
+    /// `received` is the number of actions already received: the server sends the missed ones,
+    /// followed by current game state.
+    /// Returns an error if the server rejects the token.
+    pub fn resume<A: ToSocketAddrs>(
+        address: A,
+        token: SessionToken,
+        received: usize,
+    ) -> io::Result<Self> {
+        let mut client = Self::open(address, 0, token)?;
+        client.received = received;
+
+        write_message(
+            &mut client.writer,
+            &ClientMessage::Resume { token, received },
+        )?;
+        match client.receive()? {
+            ServerMessage::Resumed { player, actions } => {
+                client.player = player;
+                client.received = client.received.min(actions);
+                Ok(client)
+            }
+            message => Err(io::Error::new(
+                io::ErrorKind::ConnectionRefused,
+                format!("Cannot resume session: {:?}", message),
+            )),
+        }
+    }
+
+    /// Open a connection to a server
+    fn open<A: ToSocketAddrs>(
+        address: A,
+        player: PlayerId,
+        token: SessionToken,
+    ) -> io::Result<Self> {
+        let writer = TcpStream::connect(address)?;
+        Ok(Self {
+            player,
+            token,
+            received: 0,
+            reader: BufReader::new(writer.try_clone()?),
+            writer,
+        })
+    }
+
+    /// Returns the player the client is bound to
+    pub fn player(&self) -> PlayerId {
+        self.player
+    }
+
+    /// Returns the token needed to resume the session after a disconnection
+    pub fn token(&self) -> SessionToken {
+        self.token
+    }
+
+    /// Returns the number of actions received since game start
+    pub fn received(&self) -> usize {
+        self.received
+    }
+
+    /// Send an action to the server
+    pub fn send(&mut self, action: ClassicAction) -> io::Result<()> {
+        write_message(&mut self.writer, &ClientMessage::Action(action))
+    }
+
+    /// Send the action of placing an atom at provided position
+    pub fn place_atom(&mut self, turn: TurnId, position: Position) -> io::Result<()> {
+        self.send(ClassicAction::place_atom(self.player, turn, position))
+    }
+
+    /// Wait for the next message of the server.
+    ///
+    /// Returns an error if connection has been closed.
+    pub fn receive(&mut self) -> io::Result<ServerMessage> {
+        let message = read_message(&mut self.reader)?.ok_or_else(|| {
+            io::Error::new(io::ErrorKind::UnexpectedEof, "Connection closed by server")
+        })?;
+        if let ServerMessage::Actions(actions) = &message {
+            self.received += actions.len();
+        }
+        Ok(message)
+    }
+
+    /// Set how long `receive` waits for a message (`None` forever)
+    pub fn set_timeout(&mut self, timeout: Option<Duration>) -> io::Result<()> {
+        self.reader.get_ref().set_read_timeout(timeout)
+    }
+}
+
+/// A TCP client watching read-only a game hosted by a `GameServer`.
+///
+/// It receives current game state, then the actions (addressed to `Actor::UI`)
+/// and state updates of the game; it cannot send actions.
+#[derive(Debug)]
+pub struct SpectatorClient {
+    /// Stream used to receive messages from the server
+    reader: BufReader<TcpStream>,
+}
+
+impl SpectatorClient {
+    /// Connect to a server as a spectator
+    pub fn connect<A: ToSocketAddrs>(address: A) -> io::Result<Self> {
+        let mut writer = TcpStream::connect(address)?;
+        let mut client = Self {
+            reader: BufReader::new(writer.try_clone()?),
+        };
+
+        write_message(&mut writer, &ClientMessage::Spectate)?;
+        match client.receive()? {
+            ServerMessage::Spectating => Ok(client),
+            message => Err(io::Error::new(
+                io::ErrorKind::ConnectionRefused,
+                format!("Cannot spectate: {:?}", message),
+            )),
+        }
+    }
+
+    /// Wait for the next message of the server.
+    ///
+    /// Returns an error if connection has been closed.
+    pub fn receive(&mut self) -> io::Result<ServerMessage> {
+        read_message(&mut self.reader)?.ok_or_else(|| {
+            io::Error::new(io::ErrorKind::UnexpectedEof, "Connection closed by server")
+        })
+    }
+
+    /// Set how long `receive` waits for a message (`None` forever)
+    pub fn set_timeout(&mut self, timeout: Option<Duration>) -> io::Result<()> {
+        self.reader.get_ref().set_read_timeout(timeout)
+    }
+}
@@ -0,0 +1,895 @@
+use std::{
+    net::SocketAddr,
+    sync::{Arc, Mutex},
+};
+
+use log::warn;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    ai::profile::AiProfile,
+    classic_game::{
+        controller::ClassicController, game_state::ClassicGameState, rules::ClassicRules,
+        rules_config::ClassicRulesConfig,
+    },
+    common::{
+        id_generator::{new_id, Id},
+        serializable::Serializable,
+    },
+    generic_game::{
+        identity::PlayerIdentity,
+        player::{PlayerId, PlayerInfo, PlayerRage},
+    },
+    geometry::terrain::Terrain,
+};
+
+/// Type used to store the ID of a lobby client
+pub type ClientId = Id;
+
+/// Type used to store the ID of a game in the lobby
+pub type GameId = Id;
+
+/// Minimum number of seats of a game
+const MIN_SEATS: usize = 2;
+
+/// A seat of a game in the lobby
+#[derive(Debug, Clone, Serialize)]
+pub enum Seat {
+    /// Nobody is seated
+    Empty,
+    /// Seat taken by a client
+    Human {
+        client: ClientId,
+        player: PlayerInfo,
+        ready: bool,
+    },
+    /// Seat filled with a CPU player (always ready)
+    Bot {
+        player: PlayerInfo,
+        profile: AiProfile,
+    },
+}
+
+impl Seat {
+    /// Returns the player seated (`None` for empty seats)
+    pub fn player(&self) -> Option<&PlayerInfo> {
+        match self {
+            Seat::Empty => None,
+            Seat::Human { player, .. } | Seat::Bot { player, .. } => Some(player),
+        }
+    }
+
+    /// Returns configuration of the CPU player seated (`None` for empty seats and humans)
+    pub fn ai_profile(&self) -> Option<AiProfile> {
+        match self {
+            Seat::Bot { profile, .. } => Some(*profile),
+            _ => None,
+        }
+    }
+
+    /// Returns whether a player is seated and ready to play
+    pub fn is_ready(&self) -> bool {
+        match self {
+            Seat::Empty => false,
+            Seat::Human { ready, .. } => *ready,
+            Seat::Bot { .. } => true,
+        }
+    }
+
+    /// Returns whether the seat is taken by provided client
+    fn is_taken_by(&self, client: ClientId) -> bool {
+        matches!(self, Seat::Human { client: c, .. } if *c == client)
+    }
+}
+
+/// A game waiting for players in the lobby
+#[derive(Debug, Clone, Serialize)]
+pub struct OpenGame {
+    /// Id of the game
+    id: GameId,
+    /// Client that created the game (only one that can start it)
+    host: ClientId,
+    /// Terrain where the game will be played
+    terrain: Arc<Terrain>,
+    /// Rules of the game
+    rules: ClassicRulesConfig,
+    /// Seats of the game (in turn order)
+    seats: Vec<Seat>,
+    /// Whether the host started the game
+    started: bool,
+    /// Address of the server hosting the started game (`None` if not hosted yet)
+    server: Option<SocketAddr>,
+}
+
+impl OpenGame {
+    /// Returns game ID
+    pub fn id(&self) -> GameId {
+        self.id
+    }
+
+    /// Returns the client that created the game
+    pub fn host(&self) -> ClientId {
+        self.host
+    }
+
+    /// Returns terrain where the game will be played
+    pub fn terrain(&self) -> Arc<Terrain> {
+        Arc::clone(&self.terrain)
+    }
+
+    /// Returns rules of the game
+    pub fn rules(&self) -> &ClassicRulesConfig {
+        &self.rules
+    }
+
+    /// Returns seats of the game (in turn order)
+    pub fn seats(&self) -> &[Seat] {
+        &self.seats
+    }
+
+    /// Returns whether the host started the game
+    pub fn is_started(&self) -> bool {
+        self.started
+    }
+
+    /// Returns address of the server hosting the started game (`None` if not hosted yet)
+    pub fn server(&self) -> Option<SocketAddr> {
+        self.server
+    }
+
+    /// Returns seated players (in turn order)
+    pub fn players(&self) -> Vec<PlayerInfo> {
+        self.seats
+            .iter()
+            .filter_map(Seat::player)
+            .cloned()
+            .collect()
+    }
+
+    /// Returns CPU players with their profile (in turn order)
+    pub fn bots(&self) -> Vec<(PlayerId, AiProfile)> {
+        self.seats
+            .iter()
+            .filter_map(|s| Some((s.player()?.id(), s.ai_profile()?)))
+            .collect()
+    }
+
+    /// Returns the player seated by provided client (`None` if client is not seated)
+    pub fn player_of(&self, client: ClientId) -> Option<&PlayerInfo> {
+        self.seats
+            .iter()
+            .find(|s| s.is_taken_by(client))
+            .and_then(Seat::player)
+    }
+
+    /// Returns whether all seats are taken by players ready to play
+    pub fn is_ready(&self) -> bool {
+        self.seats.iter().all(Seat::is_ready)
+    }
+
+    /// Returns the controller of the game, to be hosted (e.g. by a `GameServer`, which also
+    /// plays the turns of the `bots`).
+    ///
+    /// `None` if game has not been started.
+    pub fn controller(&self) -> Option<ClassicController> {
+        if !self.started {
+            return None;
+        }
+        let game_state = ClassicGameState::new(self.terrain(), &self.players())?;
+        Some(ClassicController::new(
+            ClassicRules::new(self.rules),
+            game_state,
+        ))
+    }
+
+    /// Returns the summary shown when listing games
+    fn summary(&self) -> GameSummary {
+        GameSummary {
+            game: self.id,
+            host: self.host,
+            seats: self.seats.len(),
+            free_seats: self
+                .seats
+                .iter()
+                .filter(|s| matches!(s, Seat::Empty))
+                .count(),
+        }
+    }
+
+    /// Returns the response telling the game has started (with where it is hosted)
+    fn started(&self) -> LobbyResponse {
+        LobbyResponse::Started {
+            players: self.players(),
+            server: self.server,
+        }
+    }
+
+    /// Returns whether the rage is not used by any seat, except the one of `client` if provided
+    fn is_rage_free(&self, rage: PlayerRage, client: Option<ClientId>) -> bool {
+        !self.seats.iter().any(|s| {
+            !client.is_some_and(|c| s.is_taken_by(c))
+                && s.player().and_then(PlayerInfo::rage) == Some(rage)
+        })
+    }
+}
+
+/// Summary of an open game
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub struct GameSummary {
+    /// Id of the game
+    game: GameId,
+    /// Client that created the game
+    host: ClientId,
+    /// Number of seats
+    seats: usize,
+    /// Number of seats nobody has taken yet
+    free_seats: usize,
+}
+
+impl GameSummary {
+    /// Returns game ID
+    pub fn game(&self) -> GameId {
+        self.game
+    }
+
+    /// Returns the client that created the game
+    pub fn host(&self) -> ClientId {
+        self.host
+    }
+
+    /// Returns number of seats
+    pub fn seats(&self) -> usize {
+        self.seats
+    }
+
+    /// Returns number of seats nobody has taken yet
+    pub fn free_seats(&self) -> usize {
+        self.free_seats
+    }
+}
+
+/// Request of a client to the lobby
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum LobbyRequest {
+    /// List games waiting for players
+    ListGames,
+    /// Create a new game (the client becomes its host)
+    Create {
+        terrain: Arc<Terrain>,
+        rules: ClassicRulesConfig,
+        seats: usize,
+    },
+    /// Take the first empty seat of a game
+    Join {
+        game: GameId,
+        name: String,
+        rage: PlayerRage,
+    },
+    /// Change the rage of the seated player
+    ChooseRage { game: GameId, rage: PlayerRage },
+    /// Mark the seated player as (not) ready to play
+    SetReady { game: GameId, ready: bool },
+    /// Fill all empty seats with CPU players (host only)
+    FillWithBots { game: GameId, profile: AiProfile },
+    /// Leave the seat (host leaving closes the game)
+    Leave { game: GameId },
+    /// Start the game (host only)
+    Start { game: GameId },
+    /// Get whether a game has started (guests poll it to know where the game is hosted)
+    Status { game: GameId },
+}
+
+/// Response of the lobby to a request
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum LobbyResponse {
+    /// Games waiting for players
+    Games(Vec<GameSummary>),
+    /// Game has been created
+    Created(GameId),
+    /// Client has been seated as the player
+    Seated(PlayerId),
+    /// Request has been executed
+    Done,
+    /// Game is still waiting for players
+    Waiting(GameSummary),
+    /// Game has been started with provided players (in turn order), hosted by the server at
+    /// provided address (`None` if the lobby does not host games, see `LobbyServer`)
+    Started {
+        players: Vec<PlayerInfo>,
+        server: Option<SocketAddr>,
+    },
+}
+
+/// Why a lobby request failed
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub enum LobbyError {
+    /// No open game with provided ID
+    UnknownGame,
+    /// Number of seats not supported
+    InvalidSeats,
+    /// No empty seat left
+    GameFull,
+    /// Client has already a seat in the game
+    AlreadySeated,
+    /// Client has no seat in the game
+    NotSeated,
+    /// Name is not valid or already used in the game
+    InvalidName,
+    /// Rage is already used by another player of the game
+    RageTaken,
+    /// Only the host can do that
+    NotHost,
+    /// Some seats are empty or their players are not ready
+    NotReady,
+    /// Game has already been started
+    AlreadyStarted,
+    /// Lobby cannot be reached (transport failed)
+    Unreachable,
+}
+
+/// Lobby where players gather before starting a game
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct Lobby {
+    /// Games created by clients (in creation order)
+    games: Vec<OpenGame>,
+}
+
+impl Lobby {
+    /// Create an empty lobby
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns all games, including started ones
+    pub fn games(&self) -> &[OpenGame] {
+        &self.games
+    }
+
+    /// Returns the game with provided ID
+    pub fn game(&self, game: GameId) -> Option<&OpenGame> {
+        self.games.iter().find(|g| g.id == game)
+    }
+
+    /// Execute the request of a client
+    pub fn handle(
+        &mut self,
+        client: ClientId,
+        request: LobbyRequest,
+    ) -> Result<LobbyResponse, LobbyError> {
+        let result = match request {
+            LobbyRequest::ListGames => Ok(LobbyResponse::Games(
+                self.games
+                    .iter()
+                    .filter(|g| !g.started)
+                    .map(OpenGame::summary)
+                    .collect(),
+            )),
+            LobbyRequest::Create {
+                terrain,
+                rules,
+                seats,
+            } => self.create(client, terrain, rules, seats),
+            LobbyRequest::Join { game, name, rage } => self.join(client, game, &name, rage),
+            LobbyRequest::ChooseRage { game, rage } => self.choose_rage(client, game, rage),
+            LobbyRequest::SetReady { game, ready } => self.set_ready(client, game, ready),
+            LobbyRequest::FillWithBots { game, profile } => {
+                self.fill_with_bots(client, game, profile)
+            }
+            LobbyRequest::Leave { game } => self.leave(client, game),
+            LobbyRequest::Start { game } => self.start(client, game),
+            LobbyRequest::Status { game } => self.status(game),
+        };
+        if let Err(error) = result {
+            warn!("Lobby request of client {} failed: {:?}", client, error);
+        }
+        result
+    }
+
+    /// Remove a started game (e.g. once its hosted game is over).
+    ///
+    /// Returns `false` if game is unknown or not started.
+    pub fn remove_started(&mut self, game: GameId) -> bool {
+        let count = self.games.len();
+        self.games.retain(|g| !(g.id == game && g.started));
+        self.games.len() != count
+    }
+
+    /// Set the address of the server hosting a started game.
+    ///
+    /// Returns `false` if game is unknown or not started.
+    pub fn set_server(&mut self, game: GameId, server: SocketAddr) -> bool {
+        match self.games.iter_mut().find(|g| g.id == game && g.started) {
+            Some(game) => {
+                game.server = Some(server);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Returns a game not started yet
+    fn open_game(&mut self, game: GameId) -> Result<&mut OpenGame, LobbyError> {
+        let game = self
+            .games
+            .iter_mut()
+            .find(|g| g.id == game)
+            .ok_or(LobbyError::UnknownGame)?;
+        if game.started {
+            Err(LobbyError::AlreadyStarted)
+        } else {
+            Ok(game)
+        }
+    }
+
+    fn create(
+        &mut self,
+        client: ClientId,
+        terrain: Arc<Terrain>,
+        rules: ClassicRulesConfig,
+        seats: usize,
+    ) -> Result<LobbyResponse, LobbyError> {
+        if seats < MIN_SEATS || seats > PlayerRage::all().len() {
+            return Err(LobbyError::InvalidSeats);
+        }
+        let game = OpenGame {
+            id: new_id(),
+            host: client,
+            terrain,
+            rules,
+            seats: vec![Seat::Empty; seats],
+            started: false,
+            server: None,
+        };
+        let id = game.id;
+        self.games.push(game);
+        Ok(LobbyResponse::Created(id))
+    }
+
+    fn join(
+        &mut self,
+        client: ClientId,
+        game: GameId,
+        name: &str,
+        rage: PlayerRage,
+    ) -> Result<LobbyResponse, LobbyError> {
+        let game = self.open_game(game)?;
+        if game.player_of(client).is_some() {
+            return Err(LobbyError::AlreadySeated);
+        }
+        let seat = game
+            .seats
+            .iter()
+            .position(|s| matches!(s, Seat::Empty))
+            .ok_or(LobbyError::GameFull)?;
+        if !game.is_rage_free(rage, Some(client)) {
+            return Err(LobbyError::RageTaken);
+        }
+        let player = PlayerInfo::validated(name, PlayerIdentity::from(rage), true, &game.players())
+            .ok_or(LobbyError::InvalidName)?;
+
+        let id = player.id();
+        game.seats[seat] = Seat::Human {
+            client,
+            player,
+            ready: false,
+        };
+        Ok(LobbyResponse::Seated(id))
+    }
+
+    fn choose_rage(
+        &mut self,
+        client: ClientId,
+        game: GameId,
+        rage: PlayerRage,
+    ) -> Result<LobbyResponse, LobbyError> {
+        let game = self.open_game(game)?;
+        if !game.is_rage_free(rage, Some(client)) {
+            return Err(LobbyError::RageTaken);
+        }
+        match game.seats.iter_mut().find(|s| s.is_taken_by(client)) {
+            Some(Seat::Human { player, .. }) => {
+                player.set_identity(PlayerIdentity::from(rage));
+                Ok(LobbyResponse::Seated(player.id()))
+            }
+            _ => Err(LobbyError::NotSeated),
+        }
+    }
+
+    fn set_ready(
+        &mut self,
+        client: ClientId,
+        game: GameId,
+        is_ready: bool,
+    ) -> Result<LobbyResponse, LobbyError> {
+        let game = self.open_game(game)?;
+        match game.seats.iter_mut().find(|s| s.is_taken_by(client)) {
+            Some(Seat::Human { ready, .. }) => {
+                *ready = is_ready;
+                Ok(LobbyResponse::Done)
+            }
+            _ => Err(LobbyError::NotSeated),
+        }
+    }
+
+    fn fill_with_bots(
+        &mut self,
+        client: ClientId,
+        game: GameId,
+        profile: AiProfile,
+    ) -> Result<LobbyResponse, LobbyError> {
+        let game = self.open_game(game)?;
+        if game.host != client {
+            return Err(LobbyError::NotHost);
+        }
+
+        let mut number = 0;
+        while let Some(seat) = game.seats.iter().position(|s| matches!(s, Seat::Empty)) {
+            let rage = *PlayerRage::all()
+                .iter()
+                .find(|r| game.is_rage_free(**r, None))
+                .expect("There shall be a rage for each seat");
+            let players = game.players();
+            let bot = loop {
+                number += 1;
+                let name = format!("Bot {}", number);
+                if !players.iter().any(|p| p.name() == name) {
+                    break PlayerInfo::new(&name, rage, false);
+                }
+            };
+            game.seats[seat] = Seat::Bot {
+                player: bot,
+                profile,
+            };
+        }
+        Ok(LobbyResponse::Done)
+    }
+
+    fn leave(&mut self, client: ClientId, game: GameId) -> Result<LobbyResponse, LobbyError> {
+        let open_game = self.open_game(game)?;
+        if open_game.host == client {
+            self.games.retain(|g| g.id != game);
+            return Ok(LobbyResponse::Done);
+        }
+        match open_game.seats.iter_mut().find(|s| s.is_taken_by(client)) {
+            Some(seat) => {
+                *seat = Seat::Empty;
+                Ok(LobbyResponse::Done)
+            }
+            None => Err(LobbyError::NotSeated),
+        }
+    }
+
+    fn start(&mut self, client: ClientId, game: GameId) -> Result<LobbyResponse, LobbyError> {
+        let game = self.open_game(game)?;
+        if game.host != client {
+            return Err(LobbyError::NotHost);
+        }
+        if !game.is_ready() {
+            return Err(LobbyError::NotReady);
+        }
+        game.started = true;
+        Ok(game.started())
+    }
+
+    fn status(&self, game: GameId) -> Result<LobbyResponse, LobbyError> {
+        let game = self.game(game).ok_or(LobbyError::UnknownGame)?;
+        if game.started {
+            Ok(game.started())
+        } else {
+            Ok(LobbyResponse::Waiting(game.summary()))
+        }
+    }
+}
+
+impl Serializable for Lobby {
+    /// Type of data to be serialized
+    type Data = Lobby;
+
+    /// Return data to be serialized
+    fn data_to_be_serialized(&self) -> &Self::Data {
+        self
+    }
+}
+
+/// Channel used by a client to send requests to a lobby
+pub trait LobbyTransport {
+    /// Returns the ID of the client
+    fn client(&self) -> ClientId;
+
+    /// Send a request and wait for the response of the lobby
+    fn request(&mut self, request: LobbyRequest) -> Result<LobbyResponse, LobbyError>;
+}
+
+/// Transport to a lobby living in the same process (no sockets involved)
+#[derive(Debug, Clone)]
+pub struct InProcessTransport {
+    /// The lobby
+    lobby: Arc<Mutex<Lobby>>,
+    /// Id of the client
+    client: ClientId,
+}
+
+impl InProcessTransport {
+    /// Connect a new client to the lobby
+    pub fn connect(lobby: &Arc<Mutex<Lobby>>) -> Self {
+        Self {
+            lobby: Arc::clone(lobby),
+            client: new_id(),
+        }
+    }
+}
+
+impl LobbyTransport for InProcessTransport {
+    /// Returns the ID of the client
+    fn client(&self) -> ClientId {
+        self.client
+    }
+
+    /// Send a request and wait for the response of the lobby
+    fn request(&mut self, request: LobbyRequest) -> Result<LobbyResponse, LobbyError> {
+        self.lobby
+            .lock()
+            .expect("Lobby lock shall not be poisoned")
+            .handle(self.client, request)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        ai::profile::{Difficulty, Personality},
+        classic_game::classic_2d::{BoardSize, Classic2d},
+        generic_game::game_state::GameState,
+    };
+
+    fn create(host: &mut InProcessTransport, seats: usize) -> GameId {
+        let preset = Classic2d::new(BoardSize::Small).unwrap();
+        match host.request(LobbyRequest::Create {
+            terrain: Arc::new(preset.terrain()),
+            rules: preset.rules_config(),
+            seats,
+        }) {
+            Ok(LobbyResponse::Created(game)) => game,
+            response => panic!("Unexpected response {:?}", response),
+        }
+    }
+
+    fn join(
+        client: &mut InProcessTransport,
+        game: GameId,
+        name: &str,
+        rage: PlayerRage,
+    ) -> Result<LobbyResponse, LobbyError> {
+        client.request(LobbyRequest::Join {
+            game,
+            name: String::from(name),
+            rage,
+        })
+    }
+
+    #[test]
+    /// Check a whole lobby session: create, join, pick rages, add bots, get ready and start
+    fn session_test() {
+        let lobby = Arc::new(Mutex::new(Lobby::new()));
+        let mut host = InProcessTransport::connect(&lobby);
+        let mut guest = InProcessTransport::connect(&lobby);
+        assert_ne!(host.client(), guest.client());
+
+        let game = create(&mut host, 4);
+        match guest.request(LobbyRequest::ListGames) {
+            Ok(LobbyResponse::Games(games)) => {
+                assert_eq!(games.len(), 1);
+                assert_eq!(games[0].game(), game);
+                assert_eq!(games[0].host(), host.client());
+                assert_eq!((games[0].seats(), games[0].free_seats()), (4, 4));
+            }
+            response => panic!("Unexpected response {:?}", response),
+        }
+
+        assert!(join(&mut host, game, "Host", PlayerRage::Red).is_ok());
+        let guest_id = match join(&mut guest, game, "Guest", PlayerRage::Green) {
+            Ok(LobbyResponse::Seated(id)) => id,
+            response => panic!("Unexpected response {:?}", response),
+        };
+        let ready = LobbyRequest::SetReady { game, ready: true };
+        assert!(host.request(ready.clone()).is_ok());
+        assert!(guest.request(ready).is_ok());
+        let choose = LobbyRequest::ChooseRage {
+            game,
+            rage: PlayerRage::Blue,
+        };
+        // Player is kept when changing rage
+        assert!(matches!(guest.request(choose), Ok(LobbyResponse::Seated(id)) if id == guest_id));
+        assert!(matches!(
+            guest.request(LobbyRequest::Status { game }),
+            Ok(LobbyResponse::Waiting(summary)) if summary.free_seats() == 2
+        ));
+
+        // Empty seats
+        let start = LobbyRequest::Start { game };
+        assert_eq!(
+            host.request(start.clone()).unwrap_err(),
+            LobbyError::NotReady
+        );
+        let fill = LobbyRequest::FillWithBots {
+            game,
+            profile: AiProfile::new(Difficulty::Easy, Personality::Turtling),
+        };
+        assert_eq!(
+            guest.request(fill.clone()).unwrap_err(),
+            LobbyError::NotHost
+        );
+        assert!(host.request(fill).is_ok());
+        assert_eq!(
+            guest.request(start.clone()).unwrap_err(),
+            LobbyError::NotHost
+        );
+
+        let players = match host.request(start) {
+            Ok(LobbyResponse::Started { players, server }) => {
+                assert_eq!(server, None);
+                players
+            }
+            response => panic!("Unexpected response {:?}", response),
+        };
+        assert_eq!(players[1].id(), guest_id);
+        let names: Vec<_> = players.iter().map(|p| p.name()).collect();
+        assert_eq!(names, vec!["Host", "Guest", "Bot 1", "Bot 2"]);
+        let rages: Vec<_> = players.iter().map(|p| p.rage().unwrap()).collect();
+        assert_eq!(
+            rages,
+            vec![
+                PlayerRage::Red,
+                PlayerRage::Blue,
+                PlayerRage::Green,
+                PlayerRage::White
+            ]
+        );
+
+        // Started games are not listed anymore, but can be hosted
+        match guest.request(LobbyRequest::ListGames) {
+            Ok(LobbyResponse::Games(games)) => assert!(games.is_empty()),
+            response => panic!("Unexpected response {:?}", response),
+        }
+        let address = "127.0.0.1:4000".parse().unwrap();
+        assert!(lobby.lock().unwrap().set_server(game, address));
+        match guest.request(LobbyRequest::Status { game }) {
+            Ok(LobbyResponse::Started { players: p, server }) => {
+                assert_eq!(p.len(), 4);
+                assert_eq!(server, Some(address));
+            }
+            response => panic!("Unexpected response {:?}", response),
+        }
+        let mut lobby = lobby.lock().unwrap();
+        let open_game = lobby.game(game).unwrap();
+        let profiles: Vec<_> = open_game.seats().iter().map(Seat::ai_profile).collect();
+        assert_eq!(
+            profiles,
+            vec![
+                None,
+                None,
+                Some(AiProfile::new(Difficulty::Easy, Personality::Turtling)),
+                Some(AiProfile::new(Difficulty::Easy, Personality::Turtling))
+            ]
+        );
+        assert_eq!(
+            open_game.player_of(guest.client()).map(PlayerInfo::id),
+            Some(players[1].id())
+        );
+        let controller = open_game.controller().unwrap();
+        assert_eq!(controller.game_state().current_player(), players[0].id());
+        assert_eq!(controller.game_state().players().len(), 4);
+
+        // Started games are removed once over
+        assert!(lobby.remove_started(game));
+        assert!(lobby.game(game).is_none());
+        assert!(!lobby.remove_started(game));
+    }
+
+    #[test]
+    /// Check if invalid requests are refused
+    fn errors_test() {
+        let lobby = Arc::new(Mutex::new(Lobby::new()));
+        let mut host = InProcessTransport::connect(&lobby);
+        let mut guest = InProcessTransport::connect(&lobby);
+        let mut other = InProcessTransport::connect(&lobby);
+
+        let preset = Classic2d::default();
+        for seats in &[0, 1, 7] {
+            let request = LobbyRequest::Create {
+                terrain: Arc::new(preset.terrain()),
+                rules: preset.rules_config(),
+                seats: *seats,
+            };
+            assert_eq!(host.request(request).unwrap_err(), LobbyError::InvalidSeats);
+        }
+        let game = create(&mut host, 2);
+
+        assert_eq!(
+            join(&mut guest, new_id(), "Guest", PlayerRage::Red).unwrap_err(),
+            LobbyError::UnknownGame
+        );
+        assert!(join(&mut host, game, "Åsa", PlayerRage::Red).is_ok());
+        assert_eq!(
+            join(&mut host, game, "Again", PlayerRage::Blue).unwrap_err(),
+            LobbyError::AlreadySeated
+        );
+        for name in &["", "Åsa", "new\nline"] {
+            assert_eq!(
+                join(&mut guest, game, name, PlayerRage::Blue).unwrap_err(),
+                LobbyError::InvalidName
+            );
+        }
+        assert_eq!(
+            join(&mut guest, game, "Guest", PlayerRage::Red).unwrap_err(),
+            LobbyError::RageTaken
+        );
+        assert_eq!(
+            guest
+                .request(LobbyRequest::SetReady { game, ready: true })
+                .unwrap_err(),
+            LobbyError::NotSeated
+        );
+        assert!(join(&mut guest, game, "Guest", PlayerRage::Blue).is_ok());
+        assert_eq!(
+            join(&mut other, game, "Other", PlayerRage::Green).unwrap_err(),
+            LobbyError::GameFull
+        );
+        assert_eq!(
+            guest
+                .request(LobbyRequest::ChooseRage {
+                    game,
+                    rage: PlayerRage::Red
+                })
+                .unwrap_err(),
+            LobbyError::RageTaken
+        );
+
+        // A guest leaving frees its seat
+        assert!(guest.request(LobbyRequest::Leave { game }).is_ok());
+        assert!(join(&mut other, game, "Other", PlayerRage::Green).is_ok());
+        // Host leaving closes the game
+        assert!(host.request(LobbyRequest::Leave { game }).is_ok());
+        assert_eq!(
+            other.request(LobbyRequest::Leave { game }).unwrap_err(),
+            LobbyError::UnknownGame
+        );
+    }
+
+    #[test]
+    /// Check if lobby state is serializable
+    fn serialize_test() {
+        let lobby = Arc::new(Mutex::new(Lobby::new()));
+        let mut host = InProcessTransport::connect(&lobby);
+        let game = create(&mut host, 3);
+        assert!(join(&mut host, game, "Host", PlayerRage::Yellow).is_ok());
+
+        let json = serde_json::to_string(&*lobby.lock().unwrap()).unwrap();
+        assert!(json.contains(r#""seats":[{"Human":{"client":"#));
+        assert!(json.contains(r#""ready":false}},"Empty","Empty"]"#));
+        assert!(json.contains(r#""started":false"#));
+
+        // Requests and responses can be sent over the wire
+        let request = LobbyRequest::Create {
+            terrain: lobby.lock().unwrap().game(game).unwrap().terrain(),
+            rules: ClassicRulesConfig::default(),
+            seats: 3,
+        };
+        let json = serde_json::to_string(&request).unwrap();
+        match serde_json::from_str(&json).unwrap() {
+            LobbyRequest::Create { terrain, seats, .. } => {
+                assert_eq!(
+                    terrain.volume(),
+                    Classic2d::new(BoardSize::Small).unwrap().terrain().volume()
+                );
+                assert_eq!(seats, 3);
+            }
+            request => panic!("Unexpected request {:?}", request),
+        }
+        let response: Result<LobbyResponse, LobbyError> = Err(LobbyError::RageTaken);
+        let json = serde_json::to_string(&response).unwrap();
+        let read: Result<LobbyResponse, LobbyError> = serde_json::from_str(&json).unwrap();
+        assert_eq!(read.unwrap_err(), LobbyError::RageTaken);
+    }
+}
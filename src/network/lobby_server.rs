@@ -0,0 +1,486 @@
+use std::{
+    io::{self, BufReader},
+    net::{Shutdown, SocketAddr, TcpListener, TcpStream, ToSocketAddrs},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex,
+    },
+    thread::{self, JoinHandle},
+};
+
+use log::{info, warn};
+
+use crate::common::id_generator::new_id;
+
+use super::{
+    lobby::{ClientId, GameId, Lobby, LobbyError, LobbyRequest, LobbyResponse, LobbyTransport},
+    protocol::{read_message, write_message},
+    server::{GameServer, ServerConfig, WRITE_TIMEOUT},
+};
+
+/// Response of the lobby, as sent over the wire
+type LobbyResult = Result<LobbyResponse, LobbyError>;
+
+/// State shared between the lobby server and its connection threads
+#[derive(Debug)]
+struct Shared {
+    /// The lobby
+    lobby: Mutex<Lobby>,
+    /// Servers hosting the started games
+    games: Mutex<Vec<(GameId, GameServer)>>,
+    /// Connections of the clients (used to close them)
+    clients: Mutex<Vec<(ClientId, TcpStream)>>,
+    /// Configuration of the game servers
+    config: ServerConfig,
+    /// Address the lobby server is listening to (game servers listen to the same IP)
+    address: SocketAddr,
+    /// Whether the server is accepting connections
+    running: AtomicBool,
+}
+
+impl Shared {
+    /// Execute the request of a client, hosting the game when it is started
+    /// (finished games are collected first)
+    fn handle(&self, client: ClientId, request: LobbyRequest) -> LobbyResult {
+        let mut lobby = self.lobby.lock().expect("Lobby lock shall not be poisoned");
+        self.collect_finished(&mut lobby);
+        let start = match request {
+            LobbyRequest::Start { game } => Some(game),
+            _ => None,
+        };
+        let response = lobby.handle(client, request)?;
+
+        match (start, response) {
+            (Some(game), LobbyResponse::Started { players, .. }) => Ok(LobbyResponse::Started {
+                players,
+                server: self.host(&mut lobby, game),
+            }),
+            (_, response) => Ok(response),
+        }
+    }
+
+    /// Start a server hosting a started game.
+    ///
+    /// Returns its address (`None` if it cannot be started).
+    fn host(&self, lobby: &mut Lobby, game: GameId) -> Option<SocketAddr> {
+        let open_game = lobby.game(game)?;
+        let controller = open_game.controller()?;
+        let address = (self.address.ip(), 0);
+        match GameServer::with_bots(address, controller, self.config, open_game.bots()) {
+            Ok(server) => {
+                let address = server.local_addr();
+                lobby.set_server(game, address);
+                self.games
+                    .lock()
+                    .expect("Games lock shall not be poisoned")
+                    .push((game, server));
+                info!("Game {} hosted on {}", game, address);
+                Some(address)
+            }
+            Err(error) => {
+                warn!("Cannot host game {}: {}", game, error);
+                None
+            }
+        }
+    }
+
+    /// Stop the servers of the games that are over or abandoned by all their players,
+    /// removing the games from the lobby
+    fn collect_finished(&self, lobby: &mut Lobby) {
+        self.games
+            .lock()
+            .expect("Games lock shall not be poisoned")
+            .retain(|(game, server)| {
+                let finished = server.is_over() || server.is_abandoned();
+                if finished {
+                    lobby.remove_started(*game);
+                    info!("Game {} finished, closing its server", game);
+                }
+                !finished
+            });
+    }
+}
+
+/// A TCP server exposing a `Lobby` to remote clients (see `TcpTransport`).
+///
+/// When the host starts a game, a `GameServer` hosting it is started on the same IP,
+/// and its address is sent with `LobbyResponse::Started`. Once the game is over or all its
+/// players left, its server is stopped and the game is removed from the lobby (when the next
+/// request is handled).
+/// Messages are newline-delimited JSON (see `protocol`): the server first sends the ID of
+/// the client, then answers each `LobbyRequest` with a `Result<LobbyResponse, LobbyError>`.
+#[derive(Debug)]
+pub struct LobbyServer {
+    /// State shared with connection threads
+    shared: Arc<Shared>,
+    /// Thread accepting connections
+    acceptor: Option<JoinHandle<()>>,
+}
+
+impl LobbyServer {
+    /// Start a lobby server listening to provided address, with default game servers
+    pub fn start<A: ToSocketAddrs>(address: A) -> io::Result<Self> {
+        Self::with_config(address, ServerConfig::default())
+    }
+
+    /// Start a lobby server whose games are hosted with provided configuration
+    pub fn with_config<A: ToSocketAddrs>(address: A, config: ServerConfig) -> io::Result<Self> {
+        let listener = TcpListener::bind(address)?;
+        let address = listener.local_addr()?;
+        let shared = Arc::new(Shared {
+            lobby: Mutex::new(Lobby::new()),
+            games: Mutex::new(vec![]),
+            clients: Mutex::new(vec![]),
+            config,
+            address,
+            running: AtomicBool::new(true),
+        });
+
+        let acceptor = {
+            let shared = Arc::clone(&shared);
+            thread::spawn(move || accept(listener, shared))
+        };
+        info!("Lobby server listening on {}", address);
+
+        Ok(Self {
+            shared,
+            acceptor: Some(acceptor),
+        })
+    }
+
+    /// Returns the address the server is listening to
+    pub fn local_addr(&self) -> SocketAddr {
+        self.shared.address
+    }
+
+    /// Returns a copy of the lobby
+    pub fn lobby(&self) -> Lobby {
+        self.shared
+            .lobby
+            .lock()
+            .expect("Lobby lock shall not be poisoned")
+            .clone()
+    }
+
+    /// Returns the address of the servers hosting the started games
+    pub fn game_servers(&self) -> Vec<SocketAddr> {
+        self.shared
+            .games
+            .lock()
+            .expect("Games lock shall not be poisoned")
+            .iter()
+            .map(|(_, server)| server.local_addr())
+            .collect()
+    }
+
+    /// Stop accepting connections, close the connection of all clients and stop game servers
+    pub fn shutdown(&mut self) {
+        if !self.shared.running.swap(false, Ordering::SeqCst) {
+            return;
+        }
+        // Wake up the acceptor, so it can see the server is not running anymore
+        let _ = TcpStream::connect(self.shared.address);
+        if let Some(acceptor) = self.acceptor.take() {
+            let _ = acceptor.join();
+        }
+
+        let mut clients = self
+            .shared
+            .clients
+            .lock()
+            .expect("Clients lock shall not be poisoned");
+        for (_, stream) in clients.drain(..) {
+            let _ = stream.shutdown(Shutdown::Both);
+        }
+        self.shared
+            .games
+            .lock()
+            .expect("Games lock shall not be poisoned")
+            .clear();
+        info!("Lobby server on {} stopped", self.shared.address);
+    }
+}
+
+impl Drop for LobbyServer {
+    fn drop(&mut self) {
+        self.shutdown();
+    }
+}
+
+/// Accept connections, serving each one on its own thread
+fn accept(listener: TcpListener, shared: Arc<Shared>) {
+    for stream in listener.incoming() {
+        if !shared.running.load(Ordering::SeqCst) {
+            break;
+        }
+        match stream {
+            Ok(stream) => {
+                let shared = Arc::clone(&shared);
+                thread::spawn(move || {
+                    if let Err(error) = serve(stream, &shared) {
+                        warn!("Lobby connection closed: {}", error);
+                    }
+                });
+            }
+            Err(error) => warn!("Cannot accept lobby connection: {}", error),
+        }
+    }
+}
+
+/// Serve the requests of a client until its connection is closed
+fn serve(stream: TcpStream, shared: &Shared) -> io::Result<()> {
+    stream.set_write_timeout(Some(WRITE_TIMEOUT))?;
+    let client = new_id();
+    shared
+        .clients
+        .lock()
+        .expect("Clients lock shall not be poisoned")
+        .push((client, stream.try_clone()?));
+    let mut writer = stream.try_clone()?;
+    let mut reader = BufReader::new(stream);
+
+    let result = serve_requests(&mut reader, &mut writer, shared, client);
+    shared
+        .clients
+        .lock()
+        .expect("Clients lock shall not be poisoned")
+        .retain(|(c, _)| *c != client);
+    result
+}
+
+/// Send the ID of a client, then answer its requests until the connection is closed
+fn serve_requests(
+    reader: &mut BufReader<TcpStream>,
+    writer: &mut TcpStream,
+    shared: &Shared,
+    client: ClientId,
+) -> io::Result<()> {
+    write_message(writer, &client)?;
+    while let Some(request) = read_message(reader)? {
+        write_message(writer, &shared.handle(client, request))?;
+    }
+    Ok(())
+}
+
+/// Transport to a lobby hosted by a `LobbyServer`
+#[derive(Debug)]
+pub struct TcpTransport {
+    /// Id of the client (given by the server)
+    client: ClientId,
+    /// Stream used to receive responses from the server
+    reader: BufReader<TcpStream>,
+    /// Stream used to send requests to the server
+    writer: TcpStream,
+}
+
+impl TcpTransport {
+    /// Connect a new client to the lobby server at provided address
+    pub fn connect<A: ToSocketAddrs>(address: A) -> io::Result<Self> {
+        let writer = TcpStream::connect(address)?;
+        let mut reader = BufReader::new(writer.try_clone()?);
+        let client = read_message(&mut reader)?.ok_or_else(|| {
+            io::Error::new(io::ErrorKind::UnexpectedEof, "Connection closed by lobby")
+        })?;
+
+        Ok(Self {
+            client,
+            reader,
+            writer,
+        })
+    }
+
+    /// Send a request and read the response of the server
+    fn exchange(&mut self, request: &LobbyRequest) -> io::Result<LobbyResult> {
+        write_message(&mut self.writer, request)?;
+        read_message(&mut self.reader)?.ok_or_else(|| {
+            io::Error::new(io::ErrorKind::UnexpectedEof, "Connection closed by lobby")
+        })
+    }
+}
+
+impl LobbyTransport for TcpTransport {
+    /// Returns the ID of the client
+    fn client(&self) -> ClientId {
+        self.client
+    }
+
+    /// Send a request and wait for the response of the lobby
+    fn request(&mut self, request: LobbyRequest) -> Result<LobbyResponse, LobbyError> {
+        self.exchange(&request).unwrap_or_else(|error| {
+            warn!("Cannot reach the lobby: {}", error);
+            Err(LobbyError::Unreachable)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+
+    use super::*;
+    use crate::{
+        ai::profile::{AiProfile, Difficulty, Personality},
+        classic_game::{
+            action::ClassicAction,
+            classic_2d::{BoardSize, Classic2d},
+        },
+        generic_game::{
+            action::Action,
+            actor::Actor,
+            player::{PlayerId, PlayerRage},
+        },
+        geometry::vector::Position,
+        network::{client::GameClient, protocol::ServerMessage},
+    };
+
+    /// Take a seat in a game, returning the ID of the player
+    fn join(client: &mut TcpTransport, game: GameId, name: &str, rage: PlayerRage) -> PlayerId {
+        let request = LobbyRequest::Join {
+            game,
+            name: String::from(name),
+            rage,
+        };
+        match client.request(request) {
+            Ok(LobbyResponse::Seated(player)) => player,
+            response => panic!("Unexpected response {:?}", response),
+        }
+    }
+
+    /// Receive messages until the state update, returning the executed actions
+    fn receive_update(client: &mut GameClient) -> Vec<ClassicAction> {
+        let mut actions = vec![];
+        loop {
+            match client.receive().unwrap() {
+                ServerMessage::Actions(received) => actions.extend(received),
+                ServerMessage::State(_) => return actions,
+                message => panic!("Unexpected message {:?}", message),
+            }
+        }
+    }
+
+    #[test]
+    /// Check if a game gathered over TCP is hosted by a game server once started,
+    /// the server playing the turns of CPU players
+    fn hosting_test() {
+        let mut lobby = LobbyServer::start("127.0.0.1:0").unwrap();
+        let mut host = TcpTransport::connect(lobby.local_addr()).unwrap();
+        let mut guest = TcpTransport::connect(lobby.local_addr()).unwrap();
+        assert_ne!(host.client(), guest.client());
+
+        let preset = Classic2d::new(BoardSize::Small).unwrap();
+        let game = match host.request(LobbyRequest::Create {
+            terrain: Arc::new(preset.terrain()),
+            rules: preset.rules_config(),
+            seats: 3,
+        }) {
+            Ok(LobbyResponse::Created(game)) => game,
+            response => panic!("Unexpected response {:?}", response),
+        };
+        let players = vec![
+            join(&mut host, game, "Host", PlayerRage::Red),
+            join(&mut guest, game, "Guest", PlayerRage::Green),
+        ];
+        let profile = AiProfile::new(Difficulty::Hard, Personality::Aggressive);
+        assert!(host
+            .request(LobbyRequest::FillWithBots { game, profile })
+            .is_ok());
+        let bots = lobby.lobby().game(game).unwrap().bots();
+        assert_eq!(bots.len(), 1);
+        assert_eq!(bots[0].1, profile);
+        assert_eq!(
+            guest.request(LobbyRequest::Start { game }).unwrap_err(),
+            LobbyError::NotHost
+        );
+        for client in &mut [&mut host, &mut guest] {
+            assert!(client
+                .request(LobbyRequest::SetReady { game, ready: true })
+                .is_ok());
+        }
+
+        let server = match host.request(LobbyRequest::Start { game }) {
+            Ok(LobbyResponse::Started { server, .. }) => server.unwrap(),
+            response => panic!("Unexpected response {:?}", response),
+        };
+        assert_eq!(lobby.game_servers(), vec![server]);
+        assert_eq!(lobby.lobby().game(game).unwrap().server(), Some(server));
+        match guest.request(LobbyRequest::Status { game }) {
+            Ok(LobbyResponse::Started { server: s, .. }) => assert_eq!(s, Some(server)),
+            response => panic!("Unexpected response {:?}", response),
+        }
+
+        // Seated players can join the hosted game, but not the seat of the CPU player
+        let mut clients = vec![];
+        for player in &players {
+            let mut client = GameClient::connect(server, *player).unwrap();
+            client.set_timeout(Some(Duration::from_secs(5))).unwrap();
+            match client.receive().unwrap() {
+                ServerMessage::State(state) => assert_eq!(state.current_player(), players[0]),
+                message => panic!("Unexpected message {:?}", message),
+            }
+            clients.push(client);
+        }
+        assert!(GameClient::connect(server, bots[0].0).is_err());
+
+        // Server plays the turn of the CPU player after the humans played
+        clients[0].place_atom(0, Position::new(0, 0, 0)).unwrap();
+        receive_update(&mut clients[1]);
+        clients[1].place_atom(1, Position::new(1, 1, 0)).unwrap();
+        receive_update(&mut clients[1]);
+        let actions = receive_update(&mut clients[1]);
+        assert_eq!(actions[0].source(), Actor::Player(bots[0].0));
+        assert_eq!(actions[0].turn(), 2);
+
+        lobby.shutdown();
+        assert!(lobby.game_servers().is_empty());
+        assert_eq!(
+            guest.request(LobbyRequest::ListGames).unwrap_err(),
+            LobbyError::Unreachable
+        );
+    }
+
+    #[test]
+    /// Check if the server of a game abandoned by all its players is stopped
+    fn abandoned_test() {
+        let lobby = LobbyServer::start("127.0.0.1:0").unwrap();
+        let mut host = TcpTransport::connect(lobby.local_addr()).unwrap();
+        let preset = Classic2d::new(BoardSize::Small).unwrap();
+        let game = match host.request(LobbyRequest::Create {
+            terrain: Arc::new(preset.terrain()),
+            rules: preset.rules_config(),
+            seats: 2,
+        }) {
+            Ok(LobbyResponse::Created(game)) => game,
+            response => panic!("Unexpected response {:?}", response),
+        };
+        let player = join(&mut host, game, "Host", PlayerRage::Red);
+        let profile = AiProfile::default();
+        for request in [
+            LobbyRequest::FillWithBots { game, profile },
+            LobbyRequest::SetReady { game, ready: true },
+        ] {
+            assert!(host.request(request).is_ok());
+        }
+        let server = match host.request(LobbyRequest::Start { game }) {
+            Ok(LobbyResponse::Started { server, .. }) => server.unwrap(),
+            response => panic!("Unexpected response {:?}", response),
+        };
+
+        // Game is kept while its player is connected
+        let client = GameClient::connect(server, player).unwrap();
+        assert!(host.request(LobbyRequest::Status { game }).is_ok());
+        assert_eq!(lobby.game_servers(), vec![server]);
+
+        drop(client);
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while !lobby.game_servers().is_empty() {
+            assert!(Instant::now() < deadline, "Game server not stopped");
+            thread::sleep(Duration::from_millis(10));
+            let _ = host.request(LobbyRequest::ListGames);
+        }
+        assert!(lobby.lobby().game(game).is_none());
+        assert_eq!(
+            host.request(LobbyRequest::Status { game }).unwrap_err(),
+            LobbyError::UnknownGame
+        );
+    }
+}
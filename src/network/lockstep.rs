@@ -0,0 +1,569 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    sync::Arc,
+};
+
+use log::warn;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    classic_game::{
+        action::ClassicAction, cell_state::ClassicCellState, controller::ClassicController,
+        game_state::ClassicGameState, player::ClassicPlayerState, rules::ClassicRules,
+        rules_config::ClassicRulesConfig,
+    },
+    generic_game::{
+        action::{Action, TurnId},
+        game_state::GameState,
+        player::{PlayerId, PlayerState},
+        zobrist::StateHash,
+    },
+    geometry::vector::Position,
+};
+
+use super::protocol::StateUpdate;
+
+/// Number of turns whose hash and game state are kept to check and diagnose a desync
+const MAX_HISTORY_TURNS: usize = 64;
+
+/// Hash of the game state of a peer after the action of a turn
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub struct TurnHash {
+    /// Turn of the action
+    turn: TurnId,
+    /// Hash of the game state after the action
+    hash: StateHash,
+}
+
+impl TurnHash {
+    /// Create a new turn hash
+    pub fn new(turn: TurnId, hash: StateHash) -> Self {
+        Self { turn, hash }
+    }
+
+    /// Returns turn of the action
+    pub fn turn(&self) -> TurnId {
+        self.turn
+    }
+
+    /// Returns hash of the game state after the action
+    pub fn hash(&self) -> StateHash {
+        self.hash
+    }
+}
+
+/// Message exchanged by lockstep peers (directly or through a relay)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum LockstepMessage {
+    /// Rules and current game state of the sender, to start a peer joining the game
+    /// (see `LockstepPeer::join`)
+    Bootstrap(ClassicRulesConfig, Box<ClassicGameState>),
+    /// Action of a player, to be executed by all peers in the same order
+    Action(ClassicAction),
+    /// Hash of the game state of the sender after a turn
+    Hash(TurnHash),
+    /// Game state of the sender after a turn (sent after a desync)
+    Snapshot(TurnId, StateUpdate),
+}
+
+/// A cell whose state is different between two peers
+#[derive(Debug, Clone, Serialize, PartialEq, Eq)]
+pub struct CellDiff {
+    /// Position of the cell
+    position: Position,
+    /// State of the cell for local peer
+    local: ClassicCellState,
+    /// State of the cell for remote peer
+    remote: ClassicCellState,
+}
+
+impl CellDiff {
+    /// Returns position of the cell
+    pub fn position(&self) -> Position {
+        self.position
+    }
+
+    /// Returns state of the cell for local peer
+    pub fn local(&self) -> &ClassicCellState {
+        &self.local
+    }
+
+    /// Returns state of the cell for remote peer
+    pub fn remote(&self) -> &ClassicCellState {
+        &self.remote
+    }
+}
+
+/// A value that is different between two peers
+#[derive(Debug, Clone, Copy, Serialize, PartialEq, Eq)]
+pub struct Diff<T> {
+    /// Value for local peer
+    local: T,
+    /// Value for remote peer
+    remote: T,
+}
+
+impl<T: PartialEq> Diff<T> {
+    /// Returns the difference between two values (`None` if they are equal)
+    fn between(local: T, remote: T) -> Option<Self> {
+        if local == remote {
+            None
+        } else {
+            Some(Self { local, remote })
+        }
+    }
+
+    /// Returns value for local peer
+    pub fn local(&self) -> &T {
+        &self.local
+    }
+
+    /// Returns value for remote peer
+    pub fn remote(&self) -> &T {
+        &self.remote
+    }
+}
+
+/// Report of two peers whose game states diverged
+#[derive(Debug, Clone, Serialize, PartialEq, Eq)]
+pub struct Desync {
+    /// Turn after which the game states are different
+    turn: TurnId,
+    /// Hash of local game state
+    local_hash: StateHash,
+    /// Hash of remote game state
+    remote_hash: StateHash,
+    /// Divergent current turn (`None` if the same)
+    current_turn: Option<Diff<TurnId>>,
+    /// Divergent current player (`None` if the same)
+    current_player: Option<Diff<PlayerId>>,
+    /// Divergent player states, e.g. scores or clocks (in turn order of local peer)
+    players: Vec<Diff<ClassicPlayerState>>,
+    /// Divergent cells (in volume order)
+    cells: Vec<CellDiff>,
+}
+
+impl Desync {
+    /// Returns turn after which the game states are different
+    pub fn turn(&self) -> TurnId {
+        self.turn
+    }
+
+    /// Returns hash of local game state
+    pub fn local_hash(&self) -> StateHash {
+        self.local_hash
+    }
+
+    /// Returns hash of remote game state
+    pub fn remote_hash(&self) -> StateHash {
+        self.remote_hash
+    }
+
+    /// Returns divergent current turn (`None` if the same)
+    pub fn current_turn(&self) -> Option<Diff<TurnId>> {
+        self.current_turn
+    }
+
+    /// Returns divergent current player (`None` if the same)
+    pub fn current_player(&self) -> Option<Diff<PlayerId>> {
+        self.current_player
+    }
+
+    /// Returns divergent player states (in turn order of local peer)
+    pub fn players(&self) -> &[Diff<ClassicPlayerState>] {
+        &self.players
+    }
+
+    /// Returns divergent cells (in volume order)
+    pub fn cells(&self) -> &[CellDiff] {
+        &self.cells
+    }
+}
+
+/// Result of the comparison of a remote snapshot with the local game state after a turn
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SyncCheck {
+    /// Both peers have the same game state
+    InSync,
+    /// Game states are different
+    Desync(Desync),
+    /// Local game state is not available (turn not executed yet, or too old)
+    Unknown,
+}
+
+/// A peer of a lockstep game.
+///
+/// Each peer runs its own rules engine, executing the same stream of actions.
+/// Since rules are deterministic, all peers shall reach the same game state after each turn:
+/// peers exchange the hash of their state after each turn and, if hashes are different,
+/// a snapshot of the state to find the divergent cells.
+///
+/// All peers shall start from the same game state (same terrain and players, with same IDs):
+/// a peer joining a started game gets it from another peer (see `LockstepPeer::bootstrap`).
+/// Only the last `MAX_HISTORY_TURNS` turns can be checked.
+#[derive(Debug, Clone)]
+pub struct LockstepPeer {
+    /// Controller running the local rules engine
+    controller: ClassicController,
+    /// Hash after the last executed turns (oldest first)
+    hashes: VecDeque<TurnHash>,
+    /// Game state after the last executed turns (oldest first)
+    history: VecDeque<(TurnId, Arc<ClassicGameState>)>,
+}
+
+impl LockstepPeer {
+    /// Create a peer running the game of provided controller (undo is disabled)
+    pub fn new(mut controller: ClassicController) -> Self {
+        controller.set_undo_enabled(false);
+        Self {
+            controller,
+            hashes: VecDeque::new(),
+            history: VecDeque::new(),
+        }
+    }
+
+    /// Create a peer joining a started game, from the `Bootstrap` message of another peer
+    pub fn join(rules: ClassicRulesConfig, game_state: ClassicGameState) -> Self {
+        Self::new(ClassicController::new(ClassicRules::new(rules), game_state))
+    }
+
+    /// Returns the message to be sent to a peer joining the game
+    pub fn bootstrap(&self) -> LockstepMessage {
+        LockstepMessage::Bootstrap(
+            *self.controller.rules().config(),
+            Box::new(self.controller.game_state().as_ref().clone()),
+        )
+    }
+
+    /// Returns the controller running the local rules engine
+    pub fn controller(&self) -> &ClassicController {
+        &self.controller
+    }
+
+    /// Returns the hash after the last executed turns (oldest first)
+    pub fn hashes(&self) -> &VecDeque<TurnHash> {
+        &self.hashes
+    }
+
+    /// Execute an action of the stream.
+    ///
+    /// Returns the hash of the game state after the action, to be sent to other peers,
+    /// or `None` if action is not valid (game state does not change).
+    pub fn apply(&mut self, action: ClassicAction) -> Option<TurnHash> {
+        self.controller.play(action)?;
+
+        let game_state = self.controller.game_state();
+        let turn_hash = TurnHash::new(action.turn(), game_state.state_hash());
+        self.hashes.push_back(turn_hash);
+        self.history.push_back((action.turn(), game_state));
+        if self.history.len() > MAX_HISTORY_TURNS {
+            self.hashes.pop_front();
+            self.history.pop_front();
+        }
+        Some(turn_hash)
+    }
+
+    /// Returns the hash after provided turn (`None` if turn has not been executed yet,
+    /// or it is too old)
+    pub fn turn_hash(&self, turn: TurnId) -> Option<TurnHash> {
+        self.hashes.iter().find(|h| h.turn == turn).copied()
+    }
+
+    /// Check the hash of a remote peer against the local one.
+    ///
+    /// Returns whether peers are in sync after that turn, `None` if local peer has not
+    /// executed the turn yet or it is too old.
+    pub fn check(&self, remote: &TurnHash) -> Option<bool> {
+        let local = self.turn_hash(remote.turn)?;
+        if local.hash != remote.hash {
+            warn!(
+                "Desync at turn {}: {:x} != {:x}",
+                remote.turn, local.hash, remote.hash
+            );
+        }
+        Some(local.hash == remote.hash)
+    }
+
+    /// Returns the snapshot of the game state after provided turn, to be sent to peers
+    /// to diagnose a desync.
+    ///
+    /// `None` if turn has not been executed yet or it is too old.
+    pub fn snapshot(&self, turn: TurnId) -> Option<StateUpdate> {
+        self.state_after(turn).map(StateUpdate::new)
+    }
+
+    /// Compare the snapshot of a remote peer with the local game state after the same turn
+    pub fn desync(&self, turn: TurnId, remote: &StateUpdate) -> SyncCheck {
+        let local = match self.state_after(turn) {
+            Some(local) => local,
+            None => return SyncCheck::Unknown,
+        };
+        if local.state_hash() == remote.hash() {
+            return SyncCheck::InSync;
+        }
+
+        let volume = local.terrain().volume();
+        let remote_cells: HashMap<_, _> = remote.cells().iter().map(|(p, c)| (*p, c)).collect();
+        let mut positions: Vec<_> = local
+            .occupied_cells()
+            .map(|(p, _)| p)
+            .chain(remote_cells.keys().copied())
+            .collect();
+        positions.sort_by_key(|p| volume.index_of(p));
+        positions.dedup();
+
+        let cells = positions
+            .into_iter()
+            .filter_map(|position| {
+                let local = local
+                    .cell_state(&position)
+                    .unwrap_or(ClassicCellState::Empty);
+                let remote = remote_cells
+                    .get(&position)
+                    .map_or(ClassicCellState::Empty, |c| (*c).clone());
+                if local == remote {
+                    None
+                } else {
+                    Some(CellDiff {
+                        position,
+                        local,
+                        remote,
+                    })
+                }
+            })
+            .collect();
+
+        let players = local
+            .players()
+            .iter()
+            .filter_map(|player| {
+                let other = remote.players().iter().find(|p| p.id() == player.id())?;
+                Diff::between(*player, *other)
+            })
+            .collect();
+
+        SyncCheck::Desync(Desync {
+            turn,
+            local_hash: local.state_hash(),
+            remote_hash: remote.hash(),
+            current_turn: Diff::between(local.current_turn(), remote.turn()),
+            current_player: Diff::between(local.current_player(), remote.current_player()),
+            players,
+            cells,
+        })
+    }
+
+    /// Returns game state after provided turn, if still in history
+    fn state_after(&self, turn: TurnId) -> Option<&ClassicGameState> {
+        self.history
+            .iter()
+            .find(|(t, _)| *t == turn)
+            .map(|(_, gs)| gs.as_ref())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        classic_game::rules::ClassicRules,
+        generic_game::player::PlayerState,
+        test_utilities::{game::ground_game, random_generator::random_number},
+    };
+
+    /// Create the initial game state of a 4x4 game between 3 players
+    fn game_state() -> ClassicGameState {
+        ground_game(Position::new(3, 3, 0), 3)
+    }
+
+    /// Returns a random legal action for current player
+    fn random_action(peer: &LockstepPeer) -> ClassicAction {
+        let gs = peer.controller().game_state();
+        let legal = peer.controller().rules().legal_positions(&gs);
+        let position = legal[random_number(0, legal.len())];
+        ClassicAction::place_atom(gs.current_player(), gs.current_turn(), position)
+    }
+
+    /// Send a message through JSON, as a relay would do
+    fn relay(message: &LockstepMessage) -> LockstepMessage {
+        serde_json::from_str(&serde_json::to_string(message).unwrap()).unwrap()
+    }
+
+    #[test]
+    /// Check if peers executing the same actions stay in sync
+    fn in_sync_test() {
+        let gs = game_state();
+        let mut peers: Vec<_> = (0..3)
+            .map(|_| LockstepPeer::new(ClassicController::new(ClassicRules::default(), gs.clone())))
+            .collect();
+
+        for _ in 0..60 {
+            let action = match relay(&LockstepMessage::Action(random_action(&peers[0]))) {
+                LockstepMessage::Action(action) => action,
+                _ => unreachable!(),
+            };
+            let hashes: Vec<_> = peers.iter_mut().filter_map(|p| p.apply(action)).collect();
+            if hashes.is_empty() {
+                // Game is over
+                break;
+            }
+            assert_eq!(hashes.len(), peers.len());
+
+            for (i, peer) in peers.iter().enumerate() {
+                for hash in &hashes {
+                    let hash = match relay(&LockstepMessage::Hash(*hash)) {
+                        LockstepMessage::Hash(hash) => hash,
+                        _ => unreachable!(),
+                    };
+                    assert_eq!(peer.check(&hash), Some(true), "Peer {}", i);
+                }
+            }
+        }
+        assert_eq!(peers[0].hashes(), peers[2].hashes());
+        assert!(peers[0].hashes().len() <= MAX_HISTORY_TURNS);
+        assert_eq!(
+            peers[1].check(&TurnHash::new(1000, 0)),
+            None,
+            "Turn not executed yet"
+        );
+    }
+
+    #[test]
+    /// Check if a desync is detected and reported with the divergent cells
+    fn desync_test() {
+        let gs = game_state();
+        let mut corrupted = gs.clone();
+        let intruder = gs.players()[2].id();
+        corrupted.set_cell_state(
+            &Position::new(3, 3, 0),
+            &ClassicCellState::Occupied(intruder, 1),
+        );
+
+        let mut local = LockstepPeer::new(ClassicController::new(ClassicRules::default(), gs));
+        let mut remote =
+            LockstepPeer::new(ClassicController::new(ClassicRules::default(), corrupted));
+        let action = ClassicAction::place_atom(
+            local.controller().game_state().current_player(),
+            0,
+            Position::zero(),
+        );
+        let local_hash = local.apply(action).unwrap();
+        let remote_hash = remote.apply(action).unwrap();
+
+        assert_eq!(local.check(&remote_hash), Some(false));
+        assert_eq!(remote.check(&local_hash), Some(false));
+
+        let snapshot = match relay(&LockstepMessage::Snapshot(
+            remote_hash.turn(),
+            remote.snapshot(remote_hash.turn()).unwrap(),
+        )) {
+            LockstepMessage::Snapshot(turn, snapshot) => {
+                assert_eq!(turn, remote_hash.turn());
+                snapshot
+            }
+            _ => unreachable!(),
+        };
+        let desync = match local.desync(remote_hash.turn(), &snapshot) {
+            SyncCheck::Desync(desync) => desync,
+            check => panic!("Unexpected check {:?}", check),
+        };
+        assert_eq!(desync.turn(), 0);
+        assert_eq!(desync.local_hash(), local_hash.hash());
+        assert_eq!(desync.remote_hash(), remote_hash.hash());
+        assert_eq!(
+            desync.cells(),
+            &[CellDiff {
+                position: Position::new(3, 3, 0),
+                local: ClassicCellState::Empty,
+                remote: ClassicCellState::Occupied(intruder, 1),
+            }]
+        );
+        assert_eq!(desync.current_turn(), None);
+        assert_eq!(desync.current_player(), None);
+
+        // Divergence outside the cells is reported too
+        let mut skipped = (*local.controller().game_state()).clone();
+        let next = skipped.current_player();
+        skipped.set_current_player(intruder);
+        let mut player = skipped.player_state(intruder).unwrap();
+        player.set_score(42);
+        skipped.set_player_state(intruder, &player);
+        let desync = match local.desync(0, &StateUpdate::new(&skipped)) {
+            SyncCheck::Desync(desync) => desync,
+            check => panic!("Unexpected check {:?}", check),
+        };
+        assert!(desync.cells().is_empty());
+        assert_eq!(desync.current_turn(), None);
+        let current_player = desync.current_player().unwrap();
+        assert_eq!(
+            (*current_player.local(), *current_player.remote()),
+            (next, intruder)
+        );
+        assert!(desync
+            .players()
+            .iter()
+            .any(|d| d.remote().id() == intruder && d.remote().score() == 42));
+
+        // No desync with itself
+        let own = local.snapshot(0).unwrap();
+        assert_eq!(local.desync(0, &own), SyncCheck::InSync);
+        assert_eq!(local.desync(1, &own), SyncCheck::Unknown);
+    }
+
+    #[test]
+    /// Check if only the last turns are kept
+    fn history_test() {
+        let mut peer = LockstepPeer::new(ClassicController::new(
+            ClassicRules::default(),
+            ground_game(Position::new(9, 9, 0), 2),
+        ));
+        // Each atom is placed in a new cell, so there is no explosion
+        let volume = peer.controller().game_state().terrain().volume();
+        for (turn, position) in volume.into_iter().enumerate().take(MAX_HISTORY_TURNS + 6) {
+            let player = peer.controller().game_state().current_player();
+            let action = ClassicAction::place_atom(player, turn as TurnId, position);
+            assert!(peer.apply(action).is_some());
+        }
+
+        assert_eq!(peer.hashes().len(), MAX_HISTORY_TURNS);
+        assert_eq!(peer.history.len(), MAX_HISTORY_TURNS);
+        let last = (MAX_HISTORY_TURNS + 5) as TurnId;
+        let snapshot = peer.snapshot(last).unwrap();
+        assert_eq!(peer.desync(last, &snapshot), SyncCheck::InSync);
+        assert_eq!(peer.turn_hash(0), None);
+        assert_eq!(peer.check(&TurnHash::new(0, snapshot.hash())), None);
+        assert_eq!(peer.snapshot(0), None);
+        assert_eq!(peer.desync(0, &snapshot), SyncCheck::Unknown);
+    }
+
+    #[test]
+    /// Check if a peer can join a started game and stay in sync
+    fn bootstrap_test() {
+        let mut peer = LockstepPeer::new(ClassicController::new(
+            ClassicRules::default(),
+            game_state(),
+        ));
+        for _ in 0..5 {
+            peer.apply(random_action(&peer)).unwrap();
+        }
+
+        let mut joined = match relay(&peer.bootstrap()) {
+            LockstepMessage::Bootstrap(rules, game_state) => LockstepPeer::join(rules, *game_state),
+            _ => unreachable!(),
+        };
+        assert_eq!(
+            joined.controller().game_state().state_hash(),
+            peer.controller().game_state().state_hash()
+        );
+        assert_eq!(
+            joined.controller().rules().config(),
+            peer.controller().rules().config()
+        );
+
+        let action = random_action(&peer);
+        let hash = peer.apply(action).unwrap();
+        let joined_hash = joined.apply(action).unwrap();
+        assert_eq!(joined.check(&hash), Some(true));
+        assert_eq!(peer.check(&joined_hash), Some(true));
+    }
+}
@@ -0,0 +1,256 @@
+use std::io::{self, BufRead, Read, Write};
+
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+
+use crate::{
+    classic_game::{
+        action::ClassicAction, cell_state::ClassicCellState, game_state::ClassicGameState,
+        player::ClassicPlayerState,
+    },
+    generic_game::{action::TurnId, game_state::GameState, player::PlayerId, zobrist::StateHash},
+    geometry::vector::Position,
+};
+
+/// Version of the protocol (increased on breaking changes of the messages)
+pub const PROTOCOL_VERSION: u32 = 1;
+
+/// Oldest version of the protocol still supported
+pub const MIN_PROTOCOL_VERSION: u32 = 1;
+
+/// Maximal length of a message line, in bytes (longer lines are reported as errors)
+pub const MAX_MESSAGE_LENGTH: u64 = 1 << 20;
+
+/// Secret given to a client when it joins, used to resume its session after a disconnection
+pub type SessionToken = u64;
+
+/// Message sent by a client to the server
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub enum ClientMessage {
+    /// Bind the connection to the slot of a player (`Actor::Player`).
+    ///
+    /// It shall be the first message of a connection.
+    Join(PlayerId),
+    /// Action performed by the bound player
+    Action(ClassicAction),
+    /// Watch the game read-only (as first message of a connection instead of `Join`)
+    Spectate,
+    /// Resume the session of a player after a disconnection (as first message of a connection
+    /// instead of `Join`), providing the number of actions already received
+    Resume {
+        token: SessionToken,
+        received: usize,
+    },
+}
+
+/// Why the server rejected a client
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub enum RejectReason {
+    /// Player is not part of the game
+    UnknownPlayer,
+    /// Another client is already bound to the player
+    SlotTaken,
+    /// Client sent an action before joining
+    NotJoined,
+    /// Session token does not match any player
+    InvalidToken,
+}
+
+/// Message sent by the server to its clients
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub enum ServerMessage {
+    /// Client is now bound to the player, `actions` have been executed since game start
+    Joined {
+        player: PlayerId,
+        token: SessionToken,
+        actions: usize,
+    },
+    /// Client is bound again to the player; missed actions and current state follow
+    Resumed { player: PlayerId, actions: usize },
+    /// Client is now watching the game (actions it sends are refused)
+    Spectating,
+    /// Client request rejected (connection is closed)
+    Rejected(RejectReason),
+    /// Action refused by the rules (sent only to the client that performed it)
+    Refused(ClassicAction),
+    /// Executed action followed by its reactions (sent to all clients).
+    ///
+    /// Spectators receive them addressed to `Actor::UI`.
+    Actions(Vec<ClassicAction>),
+    /// Current state of the game (sent to all clients after each action)
+    State(StateUpdate),
+}
+
+/// Snapshot of the dynamic part of a game state.
+///
+/// Terrain is not included: it never changes during a game, so clients are expected to know it
+/// (lockstep peers joining a started game get it with `LockstepMessage::Bootstrap`).
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct StateUpdate {
+    /// Current turn
+    turn: TurnId,
+    /// Substep of current turn
+    turn_substep: TurnId,
+    /// Current active player
+    current_player: PlayerId,
+    /// State of each player (in turn order)
+    players: Vec<ClassicPlayerState>,
+    /// Non empty cells with their position
+    cells: Vec<(Position, ClassicCellState)>,
+    /// Hash of the game state
+    hash: StateHash,
+}
+
+impl StateUpdate {
+    /// Returns the snapshot of provided game state
+    pub fn new(game_state: &ClassicGameState) -> Self {
+        Self {
+            turn: game_state.current_turn(),
+            turn_substep: game_state.current_turn_substep(),
+            current_player: game_state.current_player(),
+            players: game_state.players().to_vec(),
+            cells: game_state
+                .occupied_cells()
+                .map(|(position, cell)| (position, cell.clone()))
+                .collect(),
+            hash: game_state.state_hash(),
+        }
+    }
+
+    /// Returns current turn
+    pub fn turn(&self) -> TurnId {
+        self.turn
+    }
+
+    /// Returns substep of current turn
+    pub fn turn_substep(&self) -> TurnId {
+        self.turn_substep
+    }
+
+    /// Returns current active player
+    pub fn current_player(&self) -> PlayerId {
+        self.current_player
+    }
+
+    /// Returns the state of each player (in turn order)
+    pub fn players(&self) -> &[ClassicPlayerState] {
+        &self.players
+    }
+
+    /// Returns non empty cells with their position
+    pub fn cells(&self) -> &[(Position, ClassicCellState)] {
+        &self.cells
+    }
+
+    /// Returns hash of the game state
+    pub fn hash(&self) -> StateHash {
+        self.hash
+    }
+}
+
+/// Write a message as a single line of JSON
+pub fn write_message<W: Write, T: Serialize>(writer: &mut W, message: &T) -> io::Result<()> {
+    let mut line = serde_json::to_string(message)?;
+    line.push('\n');
+    writer.write_all(line.as_bytes())?;
+    writer.flush()
+}
+
+/// Read a message from a line of JSON (of at most `MAX_MESSAGE_LENGTH` bytes).
+///
+/// Returns `None` when the connection has been closed.
+pub fn read_message<R: BufRead, T: DeserializeOwned>(reader: &mut R) -> io::Result<Option<T>> {
+    let mut line = String::new();
+    let read = reader
+        .by_ref()
+        .take(MAX_MESSAGE_LENGTH)
+        .read_line(&mut line)?;
+    if read == 0 {
+        return Ok(None);
+    }
+    if read as u64 == MAX_MESSAGE_LENGTH && !line.ends_with('\n') {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "Message exceeds maximal length",
+        ));
+    }
+    Ok(Some(serde_json::from_str(&line)?))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{io::Cursor, sync::Arc};
+
+    use super::*;
+    use crate::{
+        geometry::volume::Volume,
+        test_utilities::game::{ground_terrain, players},
+    };
+
+    #[test]
+    /// Check if messages are sent as newline-delimited JSON
+    fn round_trip_test() {
+        let players = players(2);
+        let vol = Volume::new(&Position::zero(), &Position::new(2, 2, 0)).unwrap();
+        let mut gs = ClassicGameState::new(Arc::new(ground_terrain(&vol)), &players).unwrap();
+        gs.set_cell_state(
+            &Position::new(1, 1, 0),
+            &ClassicCellState::Occupied(players[0].id(), 2),
+        );
+
+        let action = ClassicAction::place_atom(players[1].id(), 3, Position::new(2, 0, 0));
+        let messages = vec![
+            ServerMessage::Joined {
+                player: players[0].id(),
+                token: 42,
+                actions: 0,
+            },
+            ServerMessage::Resumed {
+                player: players[0].id(),
+                actions: 3,
+            },
+            ServerMessage::Spectating,
+            ServerMessage::Rejected(RejectReason::SlotTaken),
+            ServerMessage::Refused(action),
+            ServerMessage::Actions(vec![action, action]),
+            ServerMessage::State(StateUpdate::new(&gs)),
+        ];
+        let mut buffer = vec![];
+        for message in &messages {
+            write_message(&mut buffer, message).unwrap();
+        }
+        write_message(&mut buffer, &ClientMessage::Action(action)).unwrap();
+        assert_eq!(buffer.iter().filter(|b| **b == b'\n').count(), 8);
+
+        let mut reader = Cursor::new(buffer);
+        for message in &messages {
+            let read: ServerMessage = read_message(&mut reader).unwrap().unwrap();
+            assert_eq!(read, *message);
+        }
+        let read: ClientMessage = read_message(&mut reader).unwrap().unwrap();
+        assert_eq!(read, ClientMessage::Action(action));
+        assert_eq!(read_message::<_, ClientMessage>(&mut reader).unwrap(), None);
+
+        let update = StateUpdate::new(&gs);
+        assert_eq!(update.current_player(), players[0].id());
+        assert_eq!(
+            update.cells(),
+            &[(
+                Position::new(1, 1, 0),
+                ClassicCellState::Occupied(players[0].id(), 2)
+            )]
+        );
+        assert_eq!(update.hash(), gs.state_hash());
+    }
+
+    #[test]
+    /// Check if malformed lines are reported as errors
+    fn malformed_test() {
+        let mut reader = Cursor::new(b"{\"Join\":}\n".to_vec());
+        let error = read_message::<_, ClientMessage>(&mut reader).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+
+        let mut reader = Cursor::new(vec![b' '; MAX_MESSAGE_LENGTH as usize + 1]);
+        let error = read_message::<_, ClientMessage>(&mut reader).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+    }
+}
@@ -0,0 +1,1090 @@
+use std::{
+    io::{self, BufReader},
+    net::{Shutdown, SocketAddr, TcpListener, TcpStream, ToSocketAddrs},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc::{self, Receiver, SyncSender, TrySendError},
+        Arc, Mutex,
+    },
+    thread::{self, JoinHandle},
+    time::{Duration, Instant},
+};
+
+use log::{info, warn};
+use rand::{rngs::StdRng, SeedableRng};
+
+use crate::{
+    ai::{classic_runner, profile::AiProfile},
+    classic_game::{
+        action::ClassicAction, basic_rules::ClassicBasicRules, controller::ClassicController,
+        game_state::ClassicGameState,
+    },
+    common::id_generator::{new_id, Id},
+    generic_game::{
+        action::Action, actor::Actor, clock::Millis, game_rule::BasicRules, game_state::GameState,
+        player::PlayerId,
+    },
+};
+
+use super::protocol::{
+    read_message, write_message, ClientMessage, RejectReason, ServerMessage, SessionToken,
+    StateUpdate,
+};
+
+/// Maximal time spent sending a message to a client before its connection is closed
+pub const WRITE_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Maximal number of messages queued for a spectator (slower spectators are disconnected)
+pub const SPECTATOR_QUEUE_LENGTH: usize = 1024;
+
+/// Configuration of a `GameServer`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ServerConfig {
+    /// Delay of the messages sent to spectators (to prevent ghosting)
+    spectator_delay: Duration,
+    /// Whether the server plays on behalf of disconnected players
+    stand_in: bool,
+}
+
+impl ServerConfig {
+    /// Returns delay of the messages sent to spectators
+    pub fn spectator_delay(&self) -> Duration {
+        self.spectator_delay
+    }
+
+    /// Set delay of the messages sent to spectators (to prevent ghosting)
+    pub fn set_spectator_delay(&mut self, spectator_delay: Duration) {
+        self.spectator_delay = spectator_delay;
+    }
+
+    /// Returns whether the server plays on behalf of disconnected players
+    pub fn stand_in(&self) -> bool {
+        self.stand_in
+    }
+
+    /// Set whether the server plays on behalf of disconnected players
+    /// (see `ClassicRules::fallback_position`) until they resume their session
+    pub fn set_stand_in(&mut self, stand_in: bool) {
+        self.stand_in = stand_in;
+    }
+}
+
+/// The seat of a player that joined the game.
+///
+/// Seat is held when the client disconnects, until it resumes its session.
+#[derive(Debug)]
+struct Slot {
+    /// Id of the current connection
+    id: Id,
+    /// Player the connection is bound to
+    player: PlayerId,
+    /// Token needed to resume the session
+    token: SessionToken,
+    /// Stream used to send messages to the client (`None` while disconnected)
+    stream: Option<TcpStream>,
+}
+
+impl Slot {
+    /// Send a message to the client, if connected.
+    ///
+    /// The connection is closed if the message cannot be sent (within `WRITE_TIMEOUT`),
+    /// so a stalled client does not block the others.
+    fn send(&mut self, message: &ServerMessage) {
+        if let Some(stream) = &mut self.stream {
+            if let Err(error) = write_message(stream, message) {
+                warn!("Cannot send message to player {}: {}", self.player, error);
+                let _ = stream.shutdown(Shutdown::Both);
+                self.stream = None;
+            }
+        }
+    }
+}
+
+/// Content queued for a spectator
+#[derive(Debug, Clone)]
+enum Queued {
+    /// Message sent as is
+    Message(ServerMessage),
+    /// Game state, sent as a `ServerMessage::State` built by the thread of the spectator
+    State(Arc<ClassicGameState>),
+}
+
+/// A read-only client watching the game.
+///
+/// Messages are queued and sent by a dedicated thread once they are due,
+/// so spectators can see the game with a delay.
+#[derive(Debug)]
+struct Spectator {
+    /// Id of the connection
+    id: Id,
+    /// Stream of the connection (used to close it)
+    stream: TcpStream,
+    /// Queue of contents with the instant they are due (at most `SPECTATOR_QUEUE_LENGTH`)
+    queue: SyncSender<(Instant, Queued)>,
+}
+
+impl Spectator {
+    /// Create a spectator, starting the thread sending its messages
+    fn new(stream: TcpStream) -> io::Result<Self> {
+        let (queue, receiver) = mpsc::sync_channel(SPECTATOR_QUEUE_LENGTH);
+        let writer = stream.try_clone()?;
+        thread::spawn(move || forward(receiver, writer));
+
+        Ok(Self {
+            id: new_id(),
+            stream,
+            queue,
+        })
+    }
+
+    /// Queue a content to be sent after provided delay.
+    ///
+    /// Returns `false` if the connection has been closed, or if the queue is full
+    /// (the connection is then closed).
+    fn send(&self, queued: Queued, delay: Duration) -> bool {
+        match self.queue.try_send((Instant::now() + delay, queued)) {
+            Ok(()) => true,
+            Err(TrySendError::Full(_)) => {
+                warn!("Spectator {} is too slow, closing its connection", self.id);
+                let _ = self.stream.shutdown(Shutdown::Both);
+                false
+            }
+            Err(TrySendError::Disconnected(_)) => false,
+        }
+    }
+}
+
+/// Send queued contents once they are due, until the connection or the queue is closed
+fn forward(receiver: Receiver<(Instant, Queued)>, mut stream: TcpStream) {
+    for (due, queued) in receiver {
+        let now = Instant::now();
+        if due > now {
+            thread::sleep(due - now);
+        }
+        let message = match queued {
+            Queued::Message(message) => message,
+            Queued::State(game_state) => ServerMessage::State(StateUpdate::new(&game_state)),
+        };
+        if let Err(error) = write_message(&mut stream, &message) {
+            warn!("Cannot send message to spectator: {}", error);
+            break;
+        }
+    }
+}
+
+/// State shared between the server and its connection threads
+#[derive(Debug)]
+struct Shared {
+    /// Controller of the hosted game
+    controller: Mutex<ClassicController>,
+    /// Seats of the players that joined the game
+    slots: Mutex<Vec<Slot>>,
+    /// Connections watching the game
+    spectators: Mutex<Vec<Spectator>>,
+    /// All actions executed since game start (used to resync resumed sessions)
+    actions: Mutex<Vec<ClassicAction>>,
+    /// CPU players of the game (played by the server) with their profile
+    bots: Vec<(PlayerId, AiProfile)>,
+    /// Configuration of the server
+    config: ServerConfig,
+    /// Whether the server is accepting connections
+    running: AtomicBool,
+}
+
+impl Shared {
+    /// Send a message to all connected clients
+    fn broadcast(&self, message: &ServerMessage) {
+        let mut slots = self.slots.lock().expect("Slots lock shall not be poisoned");
+        for slot in slots.iter_mut() {
+            slot.send(message);
+        }
+    }
+
+    /// Queue a content for all spectators, dropping the ones that closed their connection
+    fn broadcast_to_spectators(&self, queued: &Queued) {
+        self.spectators
+            .lock()
+            .expect("Spectators lock shall not be poisoned")
+            .retain(|s| s.send(queued.clone(), self.config.spectator_delay));
+    }
+
+    /// Send executed actions and new game state to all clients
+    fn broadcast_actions(&self, actions: Vec<ClassicAction>, game_state: &Arc<ClassicGameState>) {
+        if actions.is_empty() {
+            return;
+        }
+        self.actions
+            .lock()
+            .expect("Actions lock shall not be poisoned")
+            .extend(&actions);
+        let state = ServerMessage::State(StateUpdate::new(game_state));
+
+        self.broadcast_to_spectators(&Queued::Message(ServerMessage::Actions(
+            actions.iter().map(ClassicAction::broadcast).collect(),
+        )));
+        self.broadcast_to_spectators(&Queued::State(Arc::clone(game_state)));
+        self.broadcast(&ServerMessage::Actions(actions));
+        self.broadcast(&state);
+    }
+
+    /// Add a spectator, sending it current game state (after the spectator delay)
+    fn spectate(&self, stream: TcpStream) -> io::Result<Id> {
+        // Controller is locked, so no update is sent before current state
+        let controller = self
+            .controller
+            .lock()
+            .expect("Controller lock shall not be poisoned");
+        let spectator = Spectator::new(stream)?;
+        spectator.send(
+            Queued::Message(ServerMessage::Spectating),
+            Duration::from_secs(0),
+        );
+        spectator.send(
+            Queued::State(controller.game_state()),
+            self.config.spectator_delay,
+        );
+
+        let id = spectator.id;
+        self.spectators
+            .lock()
+            .expect("Spectators lock shall not be poisoned")
+            .push(spectator);
+        Ok(id)
+    }
+
+    /// Remove a spectator
+    fn stop_spectating(&self, id: Id) {
+        self.spectators
+            .lock()
+            .expect("Spectators lock shall not be poisoned")
+            .retain(|s| s.id != id);
+    }
+
+    /// Send a message to a spectator (queued after the messages already due)
+    fn send_to_spectator(&self, id: Id, message: ServerMessage) {
+        let spectators = self
+            .spectators
+            .lock()
+            .expect("Spectators lock shall not be poisoned");
+        if let Some(spectator) = spectators.iter().find(|s| s.id == id) {
+            spectator.send(Queued::Message(message), Duration::from_secs(0));
+        }
+    }
+
+    /// Send a message to the client of a connection
+    fn send(&self, id: Id, message: &ServerMessage) {
+        let mut slots = self.slots.lock().expect("Slots lock shall not be poisoned");
+        if let Some(slot) = slots.iter_mut().find(|s| s.id == id) {
+            slot.send(message);
+        }
+    }
+
+    /// Bind a connection to a player (`stream` is used to send messages to the client).
+    ///
+    /// Returns the ID of the connection.
+    fn join(&self, stream: TcpStream, player: PlayerId) -> Result<Id, RejectReason> {
+        let mut controller = self
+            .controller
+            .lock()
+            .expect("Controller lock shall not be poisoned");
+        let game_state = controller.game_state();
+        if game_state.player_state(player).is_none() {
+            return Err(RejectReason::UnknownPlayer);
+        }
+
+        let mut slots = self.slots.lock().expect("Slots lock shall not be poisoned");
+        // Seats of CPU players are taken by the server
+        if slots.iter().any(|s| s.player == player) || self.bots.iter().any(|b| b.0 == player) {
+            return Err(RejectReason::SlotTaken);
+        }
+        let mut slot = Slot {
+            id: new_id(),
+            player,
+            token: rand::random(),
+            stream: Some(stream),
+        };
+        // Welcome messages are sent while holding the locks, so no update is missed
+        slot.send(&ServerMessage::Joined {
+            player,
+            token: slot.token,
+            actions: self
+                .actions
+                .lock()
+                .expect("Actions lock shall not be poisoned")
+                .len(),
+        });
+        slot.send(&ServerMessage::State(StateUpdate::new(&game_state)));
+
+        let id = slot.id;
+        slots.push(slot);
+        drop(slots);
+
+        // CPU players may have been waiting for a connected seat
+        self.stand_in(&mut controller);
+        Ok(id)
+    }
+
+    /// Bind a connection to the held seat of the player having provided session token,
+    /// sending the actions missed by the client and current game state.
+    ///
+    /// Returns the ID of the connection and the player.
+    fn resume(
+        &self,
+        stream: TcpStream,
+        token: SessionToken,
+        received: usize,
+    ) -> Result<(Id, PlayerId), RejectReason> {
+        let mut controller = self
+            .controller
+            .lock()
+            .expect("Controller lock shall not be poisoned");
+        let resumed = {
+            let mut slots = self.slots.lock().expect("Slots lock shall not be poisoned");
+            let slot = slots
+                .iter_mut()
+                .find(|s| s.token == token)
+                .ok_or(RejectReason::InvalidToken)?;
+
+            // A previous connection not closed yet is replaced
+            if let Some(previous) = slot.stream.replace(stream) {
+                let _ = previous.shutdown(Shutdown::Both);
+            }
+            slot.id = new_id();
+
+            let (count, missed) = {
+                let actions = self
+                    .actions
+                    .lock()
+                    .expect("Actions lock shall not be poisoned");
+                (actions.len(), actions.get(received..).map(<[_]>::to_vec))
+            };
+            slot.send(&ServerMessage::Resumed {
+                player: slot.player,
+                actions: count,
+            });
+            if let Some(missed) = missed.filter(|m| !m.is_empty()) {
+                slot.send(&ServerMessage::Actions(missed));
+            }
+            slot.send(&ServerMessage::State(StateUpdate::new(
+                &controller.game_state(),
+            )));
+            (slot.id, slot.player)
+        };
+
+        // Current player may have been waiting for a connected seat
+        self.stand_in(&mut controller);
+        Ok(resumed)
+    }
+
+    /// Close a connection, holding the seat of its player
+    fn leave(&self, id: Id) {
+        let mut controller = self
+            .controller
+            .lock()
+            .expect("Controller lock shall not be poisoned");
+        {
+            let mut slots = self.slots.lock().expect("Slots lock shall not be poisoned");
+            if let Some(slot) = slots.iter_mut().find(|s| s.id == id) {
+                slot.stream = None;
+            }
+        }
+        self.stand_in(&mut controller);
+    }
+
+    /// Execute an action of a player, notifying all clients.
+    ///
+    /// Returns `false` if action is refused.
+    fn play(&self, player: PlayerId, action: ClassicAction) -> bool {
+        if action.source() != Actor::Player(player) {
+            warn!("Player {} cannot act as {:?}", player, action.source());
+            return false;
+        }
+
+        let mut controller = self
+            .controller
+            .lock()
+            .expect("Controller lock shall not be poisoned");
+        let played = self.execute(&mut controller, action);
+        self.stand_in(&mut controller);
+        played
+    }
+
+    /// Execute an action, notifying all clients.
+    ///
+    /// Returns `false` if action is refused.
+    fn execute(&self, controller: &mut ClassicController, action: ClassicAction) -> bool {
+        match controller.play(action) {
+            Some(reactions) => {
+                let actions = std::iter::once(action)
+                    .chain(reactions.iter().map(|r| **r))
+                    .collect();
+                self.broadcast_actions(actions, &controller.game_state());
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Play on behalf of current player while it is a CPU player, or while it is disconnected
+    /// (if enabled), until the game is over.
+    ///
+    /// Nothing is played while no seat is connected, so the game is not played to the end
+    /// without anybody watching it.
+    /// *Note*: CPU players search their move holding the controller (see `AiProfile::time_budget`).
+    fn stand_in(&self, controller: &mut ClassicController) {
+        loop {
+            let game_state = controller.game_state();
+            let player = game_state.current_player();
+            let (disconnected, connected_seats) = {
+                let slots = self.slots.lock().expect("Slots lock shall not be poisoned");
+                (
+                    slots
+                        .iter()
+                        .any(|s| s.player == player && s.stream.is_none()),
+                    slots.iter().any(|s| s.stream.is_some()),
+                )
+            };
+            if !connected_seats || is_over(controller) {
+                return;
+            }
+
+            let action = match self.bots.iter().find(|b| b.0 == player) {
+                Some((_, profile)) => {
+                    // Random moves are drawn from the state hash, so replays are deterministic
+                    let mut rng = StdRng::seed_from_u64(game_state.state_hash());
+                    classic_runner::choose(controller.rules(), &game_state, profile, &mut rng)
+                }
+                None if disconnected && self.config.stand_in => {
+                    info!("Playing on behalf of disconnected player {}", player);
+                    controller
+                        .rules()
+                        .fallback_position(&game_state)
+                        .map(|p| ClassicAction::place_atom(player, game_state.current_turn(), p))
+                }
+                None => None,
+            };
+            match action {
+                Some(action) if self.execute(controller, action) => {}
+                _ => return,
+            }
+        }
+    }
+}
+
+/// Returns whether the game of a controller is over
+fn is_over(controller: &ClassicController) -> bool {
+    ClassicBasicRules::new(*controller.rules().config())
+        .outcome(&controller.game_state())
+        .is_some()
+}
+
+/// A TCP server hosting a classic game.
+///
+/// Each client binds its connection to the slot of a player, then sends the actions of that
+/// player. Executed actions (with their reactions) and the new game state are sent to all
+/// clients; refused actions only to the client that performed them.
+/// Any number of spectators can watch the game read-only, optionally with a delay
+/// to prevent ghosting.
+/// When a client disconnects its seat is held: it can resume the session with the token it got
+/// when joining, while the server optionally plays on its behalf.
+/// The server plays the turns of CPU players, once a client is connected.
+/// Messages are newline-delimited JSON (see `protocol`).
+#[derive(Debug)]
+pub struct GameServer {
+    /// State shared with connection threads
+    shared: Arc<Shared>,
+    /// Address the server is listening to
+    address: SocketAddr,
+    /// Thread accepting connections
+    acceptor: Option<JoinHandle<()>>,
+}
+
+impl GameServer {
+    /// Start a server listening to provided address and hosting the game of `controller`
+    pub fn start<A: ToSocketAddrs>(address: A, controller: ClassicController) -> io::Result<Self> {
+        Self::with_config(address, controller, ServerConfig::default())
+    }
+
+    /// Start a server with provided configuration (undo is disabled on the controller)
+    pub fn with_config<A: ToSocketAddrs>(
+        address: A,
+        controller: ClassicController,
+        config: ServerConfig,
+    ) -> io::Result<Self> {
+        Self::with_bots(address, controller, config, vec![])
+    }
+
+    /// Start a server with provided configuration, playing the turns of provided CPU players
+    /// with their profile (their seats cannot be joined)
+    pub fn with_bots<A: ToSocketAddrs>(
+        address: A,
+        mut controller: ClassicController,
+        config: ServerConfig,
+        bots: Vec<(PlayerId, AiProfile)>,
+    ) -> io::Result<Self> {
+        controller.set_undo_enabled(false);
+        let listener = TcpListener::bind(address)?;
+        let address = listener.local_addr()?;
+        let shared = Arc::new(Shared {
+            controller: Mutex::new(controller),
+            slots: Mutex::new(vec![]),
+            spectators: Mutex::new(vec![]),
+            actions: Mutex::new(vec![]),
+            bots,
+            config,
+            running: AtomicBool::new(true),
+        });
+
+        let acceptor = {
+            let shared = Arc::clone(&shared);
+            thread::spawn(move || accept(listener, shared))
+        };
+        info!("Game server listening on {}", address);
+
+        Ok(Self {
+            shared,
+            address,
+            acceptor: Some(acceptor),
+        })
+    }
+
+    /// Returns the address the server is listening to
+    pub fn local_addr(&self) -> SocketAddr {
+        self.address
+    }
+
+    /// Returns current game state
+    pub fn game_state(&self) -> Arc<ClassicGameState> {
+        self.shared
+            .controller
+            .lock()
+            .expect("Controller lock shall not be poisoned")
+            .game_state()
+    }
+
+    /// Returns whether the game is over
+    pub fn is_over(&self) -> bool {
+        is_over(
+            &self
+                .shared
+                .controller
+                .lock()
+                .expect("Controller lock shall not be poisoned"),
+        )
+    }
+
+    /// Returns whether players joined the game and all of them left (their seats are held)
+    pub fn is_abandoned(&self) -> bool {
+        let slots = self
+            .shared
+            .slots
+            .lock()
+            .expect("Slots lock shall not be poisoned");
+        !slots.is_empty() && slots.iter().all(|s| s.stream.is_none())
+    }
+
+    /// Returns players having a connected client
+    pub fn bound_players(&self) -> Vec<PlayerId> {
+        self.players(true)
+    }
+
+    /// Returns players whose client disconnected (their seats are held)
+    pub fn disconnected_players(&self) -> Vec<PlayerId> {
+        self.players(false)
+    }
+
+    /// Returns players that joined the game and are (not) connected
+    fn players(&self, connected: bool) -> Vec<PlayerId> {
+        self.shared
+            .slots
+            .lock()
+            .expect("Slots lock shall not be poisoned")
+            .iter()
+            .filter(|s| s.stream.is_some() == connected)
+            .map(|s| s.player)
+            .collect()
+    }
+
+    /// Returns number of spectators watching the game
+    pub fn spectators(&self) -> usize {
+        self.shared
+            .spectators
+            .lock()
+            .expect("Spectators lock shall not be poisoned")
+            .len()
+    }
+
+    /// Consume time on the clock of current player (see `ClassicController::tick`).
+    ///
+    /// Timeout actions are sent to all clients, and returned.
+    pub fn tick(&self, elapsed: Millis) -> Vec<ClassicAction> {
+        let mut controller = self
+            .shared
+            .controller
+            .lock()
+            .expect("Controller lock shall not be poisoned");
+        let actions: Vec<_> = controller.tick(elapsed).iter().map(|a| **a).collect();
+        self.shared
+            .broadcast_actions(actions.clone(), &controller.game_state());
+        self.shared.stand_in(&mut controller);
+        actions
+    }
+
+    /// Stop accepting connections and close the connection of all clients
+    pub fn shutdown(&mut self) {
+        if !self.shared.running.swap(false, Ordering::SeqCst) {
+            return;
+        }
+        // Wake up the acceptor, so it can see the server is not running anymore
+        let _ = TcpStream::connect(self.address);
+        if let Some(acceptor) = self.acceptor.take() {
+            let _ = acceptor.join();
+        }
+
+        let mut slots = self
+            .shared
+            .slots
+            .lock()
+            .expect("Slots lock shall not be poisoned");
+        for stream in slots.drain(..).filter_map(|s| s.stream) {
+            let _ = stream.shutdown(Shutdown::Both);
+        }
+        let mut spectators = self
+            .shared
+            .spectators
+            .lock()
+            .expect("Spectators lock shall not be poisoned");
+        for spectator in spectators.drain(..) {
+            let _ = spectator.stream.shutdown(Shutdown::Both);
+        }
+        info!("Game server on {} stopped", self.address);
+    }
+}
+
+impl Drop for GameServer {
+    fn drop(&mut self) {
+        self.shutdown();
+    }
+}
+
+/// Accept connections, serving each one on its own thread
+fn accept(listener: TcpListener, shared: Arc<Shared>) {
+    for stream in listener.incoming() {
+        if !shared.running.load(Ordering::SeqCst) {
+            break;
+        }
+        match stream {
+            Ok(stream) => {
+                let shared = Arc::clone(&shared);
+                thread::spawn(move || {
+                    if let Err(error) = serve(stream, &shared) {
+                        warn!("Connection closed: {}", error);
+                    }
+                });
+            }
+            Err(error) => warn!("Cannot accept connection: {}", error),
+        }
+    }
+}
+
+/// Serve a client connection until it is closed
+fn serve(stream: TcpStream, shared: &Shared) -> io::Result<()> {
+    stream.set_write_timeout(Some(WRITE_TIMEOUT))?;
+    let mut writer = stream.try_clone()?;
+    let mut reader = BufReader::new(stream.try_clone()?);
+
+    let (slot, player) = match read_message(&mut reader)? {
+        Some(ClientMessage::Join(player)) => match shared.join(stream, player) {
+            Ok(slot) => (slot, player),
+            Err(reason) => return write_message(&mut writer, &ServerMessage::Rejected(reason)),
+        },
+        Some(ClientMessage::Resume { token, received }) => {
+            match shared.resume(stream, token, received) {
+                Ok((slot, player)) => {
+                    info!("Player {} resumed its session", player);
+                    (slot, player)
+                }
+                Err(reason) => return write_message(&mut writer, &ServerMessage::Rejected(reason)),
+            }
+        }
+        Some(ClientMessage::Spectate) => {
+            let spectator = shared.spectate(stream)?;
+            info!("Spectator {} joined", spectator);
+            let result = serve_spectator(&mut reader, shared, spectator);
+            shared.stop_spectating(spectator);
+            info!("Spectator {} left", spectator);
+            return result;
+        }
+        Some(ClientMessage::Action(_)) => {
+            return write_message(
+                &mut writer,
+                &ServerMessage::Rejected(RejectReason::NotJoined),
+            )
+        }
+        None => return Ok(()),
+    };
+    info!("Player {} joined", player);
+
+    let result = serve_player(&mut reader, shared, player, slot);
+    shared.leave(slot);
+    info!("Player {} left", player);
+    result
+}
+
+/// Execute the actions of a bound player until the connection is closed
+fn serve_player(
+    reader: &mut BufReader<TcpStream>,
+    shared: &Shared,
+    player: PlayerId,
+    slot: Id,
+) -> io::Result<()> {
+    while let Some(message) = read_message(reader)? {
+        match message {
+            ClientMessage::Action(action) => {
+                if !shared.play(player, action) {
+                    shared.send(slot, &ServerMessage::Refused(action));
+                }
+            }
+            ClientMessage::Join(_) | ClientMessage::Spectate | ClientMessage::Resume { .. } => {
+                warn!("Player {} is already bound", player)
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Refuse the actions of a spectator until the connection is closed
+fn serve_spectator(
+    reader: &mut BufReader<TcpStream>,
+    shared: &Shared,
+    spectator: Id,
+) -> io::Result<()> {
+    while let Some(message) = read_message(reader)? {
+        match message {
+            ClientMessage::Action(action) => {
+                warn!("Spectator {} cannot play", spectator);
+                shared.send_to_spectator(spectator, ServerMessage::Refused(action));
+            }
+            ClientMessage::Join(_) | ClientMessage::Spectate | ClientMessage::Resume { .. } => {
+                warn!("Spectator {} is already watching", spectator)
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+    use crate::{
+        classic_game::rules::ClassicRules,
+        geometry::vector::Position,
+        network::client::{GameClient, SpectatorClient},
+        test_utilities::game::{ground_game, ids},
+    };
+
+    /// Start a server on localhost hosting a 3x3 game between two players
+    fn server() -> (GameServer, Vec<PlayerId>) {
+        configured_server(ServerConfig::default())
+    }
+
+    /// Start a server on localhost hosting a 3x3 game between two players, with a configuration
+    fn configured_server(config: ServerConfig) -> (GameServer, Vec<PlayerId>) {
+        let gs = ground_game(Position::new(2, 2, 0), 2);
+        let ids = ids(&gs);
+        let controller = ClassicController::new(ClassicRules::default(), gs);
+
+        (
+            GameServer::with_config("127.0.0.1:0", controller, config).unwrap(),
+            ids,
+        )
+    }
+
+    /// Connect a client, skipping the initial state
+    fn connect(server: &GameServer, player: PlayerId) -> GameClient {
+        let mut client = GameClient::connect(server.local_addr(), player).unwrap();
+        client.set_timeout(Some(Duration::from_secs(5))).unwrap();
+        assert!(matches!(client.receive().unwrap(), ServerMessage::State(_)));
+        client
+    }
+
+    #[test]
+    /// Check if actions and state updates are sent to all clients over localhost
+    fn play_test() {
+        let (server, ids) = server();
+        let mut a = connect(&server, ids[0]);
+        let mut b = connect(&server, ids[1]);
+
+        a.place_atom(0, Position::new(1, 1, 0)).unwrap();
+        for client in &mut [&mut a, &mut b] {
+            match client.receive().unwrap() {
+                ServerMessage::Actions(actions) => assert_eq!(
+                    actions,
+                    vec![ClassicAction::place_atom(ids[0], 0, Position::new(1, 1, 0))]
+                ),
+                message => panic!("Unexpected message {:?}", message),
+            }
+            match client.receive().unwrap() {
+                ServerMessage::State(state) => {
+                    assert_eq!(state.current_player(), ids[1]);
+                    assert_eq!(state.hash(), server.game_state().state_hash());
+                }
+                message => panic!("Unexpected message {:?}", message),
+            }
+        }
+
+        // Playing out of turn is refused, only to the player that tried
+        let action = ClassicAction::place_atom(ids[0], 1, Position::zero());
+        a.send(action).unwrap();
+        assert_eq!(a.receive().unwrap(), ServerMessage::Refused(action));
+
+        b.place_atom(1, Position::zero()).unwrap();
+        assert!(matches!(b.receive().unwrap(), ServerMessage::Actions(_)));
+        assert!(matches!(a.receive().unwrap(), ServerMessage::Actions(_)));
+        assert_eq!(server.game_state().current_player(), ids[0]);
+    }
+
+    #[test]
+    /// Check if a client cannot act as another player
+    fn impersonation_test() {
+        let (server, ids) = server();
+        let mut b = connect(&server, ids[1]);
+
+        let action = ClassicAction::place_atom(ids[0], 0, Position::zero());
+        b.send(action).unwrap();
+        assert_eq!(b.receive().unwrap(), ServerMessage::Refused(action));
+        assert_eq!(server.game_state().current_turn(), 0);
+    }
+
+    /// Wait until the server notices that the client of a player disconnected
+    fn wait_disconnection(server: &GameServer, player: PlayerId) {
+        for _ in 0..100 {
+            if server.disconnected_players().contains(&player) {
+                return;
+            }
+            thread::sleep(Duration::from_millis(10));
+        }
+        panic!("Disconnection of player {} not noticed", player);
+    }
+
+    #[test]
+    /// Check if slots are bound to a single client and held on disconnection
+    fn join_test() {
+        let (mut server, ids) = server();
+        let a = connect(&server, ids[0]);
+        let token = a.token();
+
+        assert!(GameClient::connect(server.local_addr(), ids[0]).is_err());
+        assert!(GameClient::connect(server.local_addr(), new_id()).is_err());
+        assert_eq!(server.bound_players(), vec![ids[0]]);
+        assert!(server.disconnected_players().is_empty());
+
+        drop(a);
+        wait_disconnection(&server, ids[0]);
+        assert!(server.bound_players().is_empty());
+        assert!(GameClient::connect(server.local_addr(), ids[0]).is_err());
+
+        let mut a = GameClient::resume(server.local_addr(), token, 0).unwrap();
+        assert_eq!(a.player(), ids[0]);
+        assert!(matches!(a.receive().unwrap(), ServerMessage::State(_)));
+        assert_eq!(server.bound_players(), vec![ids[0]]);
+
+        server.shutdown();
+        assert!(a.receive().is_err());
+        assert!(GameClient::connect(server.local_addr(), ids[1]).is_err());
+    }
+
+    /// Connect a spectator, checking the initial state
+    fn spectate(server: &GameServer) -> SpectatorClient {
+        let mut spectator = SpectatorClient::connect(server.local_addr()).unwrap();
+        spectator.set_timeout(Some(Duration::from_secs(5))).unwrap();
+        match spectator.receive().unwrap() {
+            ServerMessage::State(state) => {
+                assert_eq!(state.hash(), server.game_state().state_hash())
+            }
+            message => panic!("Unexpected message {:?}", message),
+        }
+        spectator
+    }
+
+    #[test]
+    /// Check if spectators receive the actions addressed to UI, and cannot play
+    fn spectator_test() {
+        let (server, ids) = server();
+        let mut a = connect(&server, ids[0]);
+        let mut spectators = [spectate(&server), spectate(&server), spectate(&server)];
+        assert_eq!(server.spectators(), 3);
+
+        let action = ClassicAction::place_atom(ids[0], 0, Position::zero());
+        a.send(action).unwrap();
+        for spectator in &mut spectators {
+            match spectator.receive().unwrap() {
+                ServerMessage::Actions(actions) => {
+                    assert_eq!(actions, vec![action.broadcast()]);
+                    assert_eq!(actions[0].destination(), Actor::UI);
+                    assert_eq!((actions[0].turn(), actions[0].turn_substep()), (0, 0));
+                }
+                message => panic!("Unexpected message {:?}", message),
+            }
+            assert!(matches!(
+                spectator.receive().unwrap(),
+                ServerMessage::State(_)
+            ));
+        }
+
+        // Actions sent on a spectator connection are refused, even if valid
+        let mut stream = TcpStream::connect(server.local_addr()).unwrap();
+        let mut reader = BufReader::new(stream.try_clone().unwrap());
+        write_message(&mut stream, &ClientMessage::Spectate).unwrap();
+        let action = ClassicAction::place_atom(ids[1], 1, Position::new(2, 2, 0));
+        write_message(&mut stream, &ClientMessage::Action(action)).unwrap();
+        let messages: Vec<ServerMessage> = (0..3)
+            .map(|_| read_message(&mut reader).unwrap().unwrap())
+            .collect();
+        assert_eq!(messages[0], ServerMessage::Spectating);
+        assert!(matches!(messages[1], ServerMessage::State(_)));
+        assert_eq!(messages[2], ServerMessage::Refused(action));
+        assert_eq!(server.game_state().current_turn(), 1);
+    }
+
+    #[test]
+    /// Check if a resumed session receives the missed actions and current state
+    fn resume_test() {
+        let (server, ids) = server();
+        let mut a = connect(&server, ids[0]);
+        let mut b = connect(&server, ids[1]);
+        let token = a.token();
+
+        a.place_atom(0, Position::zero()).unwrap();
+        assert!(matches!(a.receive().unwrap(), ServerMessage::Actions(_)));
+        assert_eq!(a.received(), 1);
+        let received = a.received();
+
+        // Simulate a dropped socket, missing the action of the other player
+        drop(a);
+        wait_disconnection(&server, ids[0]);
+        let action = ClassicAction::place_atom(ids[1], 1, Position::new(2, 2, 0));
+        b.send(action).unwrap();
+        assert!(matches!(b.receive().unwrap(), ServerMessage::Actions(_)));
+
+        let error = GameClient::resume(server.local_addr(), token + 1, received).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::ConnectionRefused);
+        let mut a = GameClient::resume(server.local_addr(), token, received).unwrap();
+        a.set_timeout(Some(Duration::from_secs(5))).unwrap();
+        assert_eq!(a.receive().unwrap(), ServerMessage::Actions(vec![action]));
+        match a.receive().unwrap() {
+            ServerMessage::State(state) => {
+                assert_eq!(state.current_player(), ids[0]);
+                assert_eq!(state.hash(), server.game_state().state_hash());
+            }
+            message => panic!("Unexpected message {:?}", message),
+        }
+        assert_eq!(a.received(), 2);
+
+        // Resumed session plays as before
+        a.place_atom(2, Position::new(1, 1, 0)).unwrap();
+        assert!(matches!(a.receive().unwrap(), ServerMessage::Actions(_)));
+        assert_eq!(server.game_state().current_turn(), 3);
+    }
+
+    #[test]
+    /// Check if the server plays on behalf of disconnected players, when enabled
+    fn stand_in_test() {
+        let mut config = ServerConfig::default();
+        config.set_stand_in(true);
+        let (server, ids) = configured_server(config);
+        let a = connect(&server, ids[0]);
+        let mut b = connect(&server, ids[1]);
+
+        // Disconnected current player is replaced immediately
+        drop(a);
+        wait_disconnection(&server, ids[0]);
+        assert!(matches!(b.receive().unwrap(), ServerMessage::Actions(_)));
+        assert!(matches!(b.receive().unwrap(), ServerMessage::State(_)));
+        assert_eq!(server.game_state().current_player(), ids[1]);
+
+        // And again after each action of the connected players
+        let position = ClassicRules::default()
+            .fallback_position(&server.game_state())
+            .expect("Game shall not be over");
+        b.place_atom(1, position).unwrap();
+        for _ in 0..2 {
+            assert!(matches!(b.receive().unwrap(), ServerMessage::Actions(_)));
+            assert!(matches!(b.receive().unwrap(), ServerMessage::State(_)));
+        }
+        assert_eq!(server.game_state().current_turn(), 3);
+        assert_eq!(server.game_state().current_player(), ids[1]);
+    }
+
+    #[test]
+    /// Check if the server waits for a connected seat before playing on behalf of players
+    fn stand_in_all_disconnected_test() {
+        let mut config = ServerConfig::default();
+        config.set_stand_in(true);
+        let (server, ids) = configured_server(config);
+        let a = connect(&server, ids[0]);
+        let b = connect(&server, ids[1]);
+        let token = a.token();
+
+        // Disconnected current player is replaced while the other one is connected
+        drop(a);
+        wait_disconnection(&server, ids[0]);
+        assert_eq!(server.game_state().current_turn(), 1);
+        drop(b);
+        wait_disconnection(&server, ids[1]);
+        assert_eq!(server.game_state().current_turn(), 1);
+        assert_eq!(server.game_state().current_player(), ids[1]);
+
+        // Game goes on once a seat is connected again
+        let mut a = GameClient::resume(server.local_addr(), token, 0).unwrap();
+        a.set_timeout(Some(Duration::from_secs(5))).unwrap();
+        assert!(matches!(a.receive().unwrap(), ServerMessage::Actions(_)));
+        assert!(matches!(a.receive().unwrap(), ServerMessage::State(_)));
+        match a.receive().unwrap() {
+            ServerMessage::Actions(actions) => assert_eq!(actions[0].turn(), 1),
+            message => panic!("Unexpected message {:?}", message),
+        }
+        assert_eq!(server.game_state().current_turn(), 2);
+        assert_eq!(server.game_state().current_player(), ids[0]);
+    }
+
+    #[test]
+    /// Check if spectators see the game with a delay
+    fn spectator_delay_test() {
+        let delay = Duration::from_millis(300);
+        let mut config = ServerConfig::default();
+        config.set_spectator_delay(delay);
+        let (server, ids) = configured_server(config);
+        let mut a = connect(&server, ids[0]);
+
+        let start = Instant::now();
+        let mut spectator = spectate(&server);
+        assert!(start.elapsed() >= delay);
+
+        let played = Instant::now();
+        a.place_atom(0, Position::zero()).unwrap();
+        assert!(matches!(a.receive().unwrap(), ServerMessage::Actions(_)));
+        assert!(matches!(
+            spectator.receive().unwrap(),
+            ServerMessage::Actions(_)
+        ));
+        assert!(played.elapsed() >= delay);
+    }
+
+    #[test]
+    /// Check if spectators not keeping up with the game are disconnected
+    fn spectator_queue_test() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let _client = TcpStream::connect(listener.local_addr().unwrap()).unwrap();
+        let (stream, _) = listener.accept().unwrap();
+        let spectator = Spectator::new(stream).unwrap();
+
+        // Messages are not due before the queue is full
+        let message = Queued::Message(ServerMessage::Spectating);
+        let delay = Duration::from_secs(10);
+        assert!(!(0..SPECTATOR_QUEUE_LENGTH + 2).all(|_| spectator.send(message.clone(), delay)));
+        assert!(!spectator.send(message, delay));
+    }
+}
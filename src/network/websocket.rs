@@ -0,0 +1,367 @@
+use std::{
+    io::{self, BufReader},
+    net::{Shutdown, SocketAddr, TcpListener, TcpStream, ToSocketAddrs},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc::{self, Receiver, TryRecvError},
+        Arc,
+    },
+    thread::{self, JoinHandle},
+    time::{Duration, Instant},
+};
+
+use log::{info, warn};
+use tungstenite::{
+    handshake::server::{ErrorResponse, Request, Response},
+    http::{header::SEC_WEBSOCKET_PROTOCOL, HeaderValue, StatusCode},
+    protocol::{frame::coding::CloseCode, CloseFrame},
+    Message, WebSocket,
+};
+
+use super::protocol::{
+    read_message, write_message, ClientMessage, ServerMessage, MIN_PROTOCOL_VERSION,
+    PROTOCOL_VERSION,
+};
+
+/// Prefix of the WebSocket subprotocols, followed by the protocol version (e.g. `rustamoz.v1`)
+pub const SUBPROTOCOL_PREFIX: &str = "rustamoz.v";
+
+/// How long a connection waits for a frame before checking for server messages and keepalive
+const POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+/// Maximal time given to a client to complete the WebSocket handshake
+const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Returns the WebSocket subprotocol of a protocol version
+pub fn subprotocol(version: u32) -> String {
+    format!("{}{}", SUBPROTOCOL_PREFIX, version)
+}
+
+/// Returns the highest supported protocol version among the subprotocols requested by a client
+fn negotiate(request: &Request) -> Option<u32> {
+    request
+        .headers()
+        .get_all(SEC_WEBSOCKET_PROTOCOL)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .filter_map(|name| name.trim().strip_prefix(SUBPROTOCOL_PREFIX))
+        .filter_map(|version| version.parse().ok())
+        .filter(|version| (MIN_PROTOCOL_VERSION..=PROTOCOL_VERSION).contains(version))
+        .max()
+}
+
+/// Configuration of a `WebSocketGateway`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GatewayConfig {
+    /// Interval between the pings sent to clients
+    ping_interval: Duration,
+    /// How long a silent client is kept connected
+    pong_timeout: Duration,
+}
+
+impl Default for GatewayConfig {
+    fn default() -> Self {
+        Self {
+            ping_interval: Duration::from_secs(5),
+            pong_timeout: Duration::from_secs(15),
+        }
+    }
+}
+
+impl GatewayConfig {
+    /// Returns interval between the pings sent to clients
+    pub fn ping_interval(&self) -> Duration {
+        self.ping_interval
+    }
+
+    /// Set interval between the pings sent to clients
+    pub fn set_ping_interval(&mut self, ping_interval: Duration) {
+        self.ping_interval = ping_interval;
+    }
+
+    /// Returns how long a silent client is kept connected
+    pub fn pong_timeout(&self) -> Duration {
+        self.pong_timeout
+    }
+
+    /// Set how long a client is kept connected without sending any frame (pongs included)
+    pub fn set_pong_timeout(&mut self, pong_timeout: Duration) {
+        self.pong_timeout = pong_timeout;
+    }
+}
+
+/// Exposes the protocol of a `GameServer` over WebSocket, for browser clients.
+///
+/// Protocol version is negotiated on connect with the WebSocket subprotocol
+/// (see `subprotocol`): the highest version supported by both sides is chosen,
+/// and the handshake fails if there is none.
+/// Then each text message holds one JSON `ClientMessage` or `ServerMessage`, relayed
+/// to a connection of the game server: a dropped WebSocket is a dropped client,
+/// which can resume its session.
+/// Clients are pinged regularly, and disconnected when they stop answering.
+#[derive(Debug)]
+pub struct WebSocketGateway {
+    /// Address the gateway is listening to
+    address: SocketAddr,
+    /// Whether the gateway is accepting connections
+    running: Arc<AtomicBool>,
+    /// Thread accepting connections
+    acceptor: Option<JoinHandle<()>>,
+}
+
+impl WebSocketGateway {
+    /// Start a gateway listening to provided address, relaying to the game server at `server`
+    pub fn start<A: ToSocketAddrs>(address: A, server: SocketAddr) -> io::Result<Self> {
+        Self::with_config(address, server, GatewayConfig::default())
+    }
+
+    /// Start a gateway with provided configuration
+    pub fn with_config<A: ToSocketAddrs>(
+        address: A,
+        server: SocketAddr,
+        config: GatewayConfig,
+    ) -> io::Result<Self> {
+        let listener = TcpListener::bind(address)?;
+        let address = listener.local_addr()?;
+        let running = Arc::new(AtomicBool::new(true));
+
+        let acceptor = {
+            let running = Arc::clone(&running);
+            thread::spawn(move || accept(listener, server, config, running))
+        };
+        info!("WebSocket gateway listening on {}", address);
+
+        Ok(Self {
+            address,
+            running,
+            acceptor: Some(acceptor),
+        })
+    }
+
+    /// Returns the address the gateway is listening to
+    pub fn local_addr(&self) -> SocketAddr {
+        self.address
+    }
+
+    /// Stop accepting connections and close current ones
+    pub fn shutdown(&mut self) {
+        if !self.running.swap(false, Ordering::SeqCst) {
+            return;
+        }
+        // Wake up the acceptor, so it can see the gateway is not running anymore
+        let _ = TcpStream::connect(self.address);
+        if let Some(acceptor) = self.acceptor.take() {
+            let _ = acceptor.join();
+        }
+        info!("WebSocket gateway on {} stopped", self.address);
+    }
+}
+
+impl Drop for WebSocketGateway {
+    fn drop(&mut self) {
+        self.shutdown();
+    }
+}
+
+/// Accept connections until the gateway is stopped
+fn accept(
+    listener: TcpListener,
+    server: SocketAddr,
+    config: GatewayConfig,
+    running: Arc<AtomicBool>,
+) {
+    for stream in listener.incoming() {
+        if !running.load(Ordering::SeqCst) {
+            break;
+        }
+        match stream {
+            Ok(stream) => {
+                let running = Arc::clone(&running);
+                thread::spawn(move || {
+                    if let Err(error) = relay(stream, server, config, &running) {
+                        warn!("WebSocket connection closed: {}", error);
+                    }
+                });
+            }
+            Err(error) => warn!("Cannot accept WebSocket connection: {}", error),
+        }
+    }
+}
+
+/// Convert a WebSocket error to an IO one
+fn io_error(error: tungstenite::Error) -> io::Error {
+    match error {
+        tungstenite::Error::Io(error) => error,
+        error => io::Error::other(error),
+    }
+}
+
+/// Perform the WebSocket handshake, negotiating protocol version
+// Error response type is imposed by tungstenite
+#[allow(clippy::result_large_err)]
+fn handshake(stream: TcpStream) -> io::Result<(WebSocket<TcpStream>, u32)> {
+    let mut version = None;
+    let callback = |request: &Request, mut response: Response| {
+        version = negotiate(request);
+        match version {
+            Some(version) => {
+                let protocol = HeaderValue::from_str(&subprotocol(version))
+                    .expect("Subprotocol shall be a valid header");
+                response
+                    .headers_mut()
+                    .insert(SEC_WEBSOCKET_PROTOCOL, protocol);
+                Ok(response)
+            }
+            None => {
+                let mut error = ErrorResponse::new(Some(format!(
+                    "Unsupported protocol version (expected {}{}..={})",
+                    SUBPROTOCOL_PREFIX, MIN_PROTOCOL_VERSION, PROTOCOL_VERSION
+                )));
+                *error.status_mut() = StatusCode::BAD_REQUEST;
+                Err(error)
+            }
+        }
+    };
+    // A client that never completes the handshake shall not hold its thread forever
+    stream.set_read_timeout(Some(HANDSHAKE_TIMEOUT))?;
+    let websocket = tungstenite::accept_hdr(stream, callback)
+        .map_err(|error| io::Error::new(io::ErrorKind::ConnectionRefused, error.to_string()))?;
+    let version = version.expect("Version shall be negotiated on successful handshake");
+    Ok((websocket, version))
+}
+
+/// Read the messages of the game server connection, until it is closed
+fn forward_server_messages(stream: TcpStream) -> Receiver<ServerMessage> {
+    let (sender, receiver) = mpsc::channel();
+    thread::spawn(move || {
+        let mut reader = BufReader::new(stream);
+        while let Ok(Some(message)) = read_message(&mut reader) {
+            if sender.send(message).is_err() {
+                break;
+            }
+        }
+    });
+    receiver
+}
+
+/// Relay a WebSocket connection to the game server until either side closes it
+fn relay(
+    stream: TcpStream,
+    server: SocketAddr,
+    config: GatewayConfig,
+    running: &AtomicBool,
+) -> io::Result<()> {
+    let peer = stream.peer_addr()?;
+    let (mut websocket, version) = handshake(stream)?;
+    websocket.get_ref().set_read_timeout(Some(POLL_INTERVAL))?;
+    info!(
+        "WebSocket client {} connected (protocol v{})",
+        peer, version
+    );
+
+    let mut game = TcpStream::connect(server)?;
+    let messages = forward_server_messages(game.try_clone()?);
+    let result = relay_messages(&mut websocket, &mut game, &messages, config, running);
+
+    // Game server sees the client disconnect, holding its seat
+    let _ = game.shutdown(Shutdown::Both);
+    info!("WebSocket client {} disconnected", peer);
+    result
+}
+
+/// Exchange messages between a WebSocket client and the game server, keeping connection alive
+fn relay_messages(
+    websocket: &mut WebSocket<TcpStream>,
+    game: &mut TcpStream,
+    messages: &Receiver<ServerMessage>,
+    config: GatewayConfig,
+    running: &AtomicBool,
+) -> io::Result<()> {
+    let mut last_ping = Instant::now();
+    let mut last_seen = Instant::now();
+    let mut closing = false;
+
+    loop {
+        match websocket.read() {
+            Ok(Message::Text(text)) => {
+                last_seen = Instant::now();
+                match serde_json::from_str::<ClientMessage>(&text) {
+                    Ok(message) => write_message(game, &message)?,
+                    Err(error) => {
+                        warn!("Malformed WebSocket message: {}", error);
+                        close(
+                            websocket,
+                            CloseCode::Invalid,
+                            "Malformed message",
+                            &mut closing,
+                        );
+                    }
+                }
+            }
+            Ok(Message::Binary(_)) => {
+                last_seen = Instant::now();
+                close(
+                    websocket,
+                    CloseCode::Unsupported,
+                    "Expected text message",
+                    &mut closing,
+                );
+            }
+            Ok(_) => last_seen = Instant::now(),
+            Err(tungstenite::Error::Io(error))
+                if error.kind() == io::ErrorKind::WouldBlock
+                    || error.kind() == io::ErrorKind::TimedOut => {}
+            Err(tungstenite::Error::ConnectionClosed) | Err(tungstenite::Error::AlreadyClosed) => {
+                return Ok(())
+            }
+            Err(error) => return Err(io_error(error)),
+        }
+
+        loop {
+            match messages.try_recv() {
+                Ok(message) if !closing => {
+                    let text = serde_json::to_string(&message)?;
+                    websocket.send(Message::Text(text)).map_err(io_error)?;
+                }
+                Ok(_) | Err(TryRecvError::Empty) => break,
+                Err(TryRecvError::Disconnected) => {
+                    close(
+                        websocket,
+                        CloseCode::Normal,
+                        "Game server closed connection",
+                        &mut closing,
+                    );
+                    break;
+                }
+            }
+        }
+
+        if !running.load(Ordering::SeqCst) {
+            close(websocket, CloseCode::Away, "Gateway stopped", &mut closing);
+        }
+        if last_seen.elapsed() > config.pong_timeout {
+            warn!("WebSocket client did not answer pings");
+            return Ok(());
+        }
+        if !closing && last_ping.elapsed() >= config.ping_interval {
+            websocket.send(Message::Ping(vec![])).map_err(io_error)?;
+            last_ping = Instant::now();
+        }
+    }
+}
+
+/// Start the closing handshake (once), then wait for the client to confirm
+fn close(websocket: &mut WebSocket<TcpStream>, code: CloseCode, reason: &str, closing: &mut bool) {
+    if *closing {
+        return;
+    }
+    *closing = true;
+    let frame = CloseFrame {
+        code,
+        reason: reason.to_owned().into(),
+    };
+    if let Err(error) = websocket.close(Some(frame)) {
+        warn!("Cannot close WebSocket connection: {}", error);
+    }
+}
@@ -3,4 +3,5 @@
 
 pub mod common;
 pub mod constants;
+pub mod game;
 pub mod random_generator;
@@ -10,12 +10,12 @@ pub fn nearly_equal(a: f32, b: f32) -> bool {
     if a == b {
         // Handle infinities.
         true
-    } else if a == 0.0 || b == 0.0 || diff < std::f32::MIN_POSITIVE {
+    } else if a == 0.0 || b == 0.0 || diff < f32::MIN_POSITIVE {
         // One of a or b is zero (or both are extremely close to it,) use absolute error.
-        diff < (std::f32::EPSILON * std::f32::MIN_POSITIVE)
+        diff < (f32::EPSILON * f32::MIN_POSITIVE)
     } else {
         // Use relative error.
-        (diff / f32::min(abs_a + abs_b, std::f32::MAX)) < std::f32::EPSILON
+        (diff / f32::min(abs_a + abs_b, f32::MAX)) < f32::EPSILON
     }
 }
 
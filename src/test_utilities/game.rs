@@ -0,0 +1,58 @@
+#![cfg(test)]
+#![allow(dead_code)]
+
+use std::sync::Arc;
+
+use crate::{
+    classic_game::{game_state::ClassicGameState, player::ClassicPlayerState},
+    generic_game::{
+        game_state::GameState,
+        player::{PlayerId, PlayerInfo, PlayerRage, PlayerState},
+    },
+    geometry::{
+        terrain::{CellMaterial, CellType, Terrain},
+        vector::Position,
+        volume::Volume,
+    },
+};
+
+/// Create a terrain with all positions of the volume filled with `Ground` cells
+pub fn ground_terrain(volume: &Volume) -> Terrain {
+    let mut terrain = Terrain::new(volume);
+    for p in volume {
+        terrain.set_cell_at(&p, CellType::Fill(CellMaterial::Ground));
+    }
+    terrain
+}
+
+/// Create `n` human players named `P0`, `P1`... (rages are reused after the sixth player)
+pub fn players(n: usize) -> Vec<PlayerInfo> {
+    (0..n)
+        .map(|i| {
+            let rage = PlayerRage::all()[i % PlayerRage::all().len()];
+            PlayerInfo::new(&format!("P{}", i), rage, true)
+        })
+        .collect()
+}
+
+/// Create a game between `n` players, on a board of ground cells from origin to `corner`
+pub fn ground_game(corner: Position, n: usize) -> ClassicGameState {
+    let vol = Volume::new(&Position::zero(), &corner).unwrap();
+    ClassicGameState::new(Arc::new(ground_terrain(&vol)), &players(n)).unwrap()
+}
+
+/// Returns the ID of the players of a game (in turn order)
+pub fn ids(gs: &ClassicGameState) -> Vec<PlayerId> {
+    gs.players().iter().map(|p| p.id()).collect()
+}
+
+/// Update the state of a player
+pub fn update_player<F: FnOnce(&mut ClassicPlayerState)>(
+    gs: &mut ClassicGameState,
+    id: PlayerId,
+    f: F,
+) {
+    let mut state = gs.player_state(id).unwrap();
+    f(&mut state);
+    gs.set_player_state(id, &state);
+}
@@ -0,0 +1,235 @@
+use std::{
+    io,
+    net::{SocketAddr, TcpStream},
+    sync::Arc,
+    thread,
+    time::{Duration, Instant},
+};
+
+use rustamoz::{
+    classic_game::{
+        action::ClassicAction, controller::ClassicController, game_state::ClassicGameState,
+        rules::ClassicRules,
+    },
+    generic_game::{
+        game_state::GameState,
+        player::{PlayerId, PlayerInfo, PlayerRage},
+    },
+    geometry::{
+        terrain::{CellMaterial, CellType, Terrain},
+        vector::Position,
+        volume::Volume,
+    },
+    network::{
+        client::GameClient,
+        protocol::{ClientMessage, ServerMessage, PROTOCOL_VERSION},
+        server::GameServer,
+        websocket::{subprotocol, GatewayConfig, WebSocketGateway},
+    },
+};
+use tungstenite::{
+    client::IntoClientRequest,
+    http::{header::SEC_WEBSOCKET_PROTOCOL, HeaderValue},
+    Message, WebSocket,
+};
+
+/// Start a server on localhost hosting a 3x3 game between two players, behind a gateway
+fn gateway(config: GatewayConfig) -> (GameServer, WebSocketGateway, Vec<PlayerId>) {
+    let vol = Volume::new(&Position::zero(), &Position::new(2, 2, 0)).unwrap();
+    let mut terrain = Terrain::new(&vol);
+    for p in &vol {
+        terrain.set_cell_at(&p, CellType::Fill(CellMaterial::Ground));
+    }
+    let players = [
+        PlayerInfo::new("A", PlayerRage::Red, true),
+        PlayerInfo::new("B", PlayerRage::Blue, true),
+    ];
+    let gs = ClassicGameState::new(Arc::new(terrain), &players).unwrap();
+    let controller = ClassicController::new(ClassicRules::default(), gs);
+
+    let server = GameServer::start("127.0.0.1:0", controller).unwrap();
+    let gateway =
+        WebSocketGateway::with_config("127.0.0.1:0", server.local_addr(), config).unwrap();
+    (server, gateway, players.iter().map(|p| p.id()).collect())
+}
+
+/// Open a WebSocket connection requesting provided subprotocols.
+///
+/// Returns the connection and the subprotocol chosen by the gateway.
+// Error type is imposed by tungstenite
+#[allow(clippy::result_large_err)]
+fn open(
+    address: SocketAddr,
+    protocols: &str,
+) -> tungstenite::Result<(WebSocket<TcpStream>, Option<String>)> {
+    let mut request = format!("ws://{}/", address).into_client_request()?;
+    request.headers_mut().insert(
+        SEC_WEBSOCKET_PROTOCOL,
+        HeaderValue::from_str(protocols).unwrap(),
+    );
+    let stream = TcpStream::connect(address)?;
+    stream.set_read_timeout(Some(Duration::from_secs(5)))?;
+    let (websocket, response) = tungstenite::client(request, stream).map_err(|e| match e {
+        tungstenite::HandshakeError::Failure(error) => error,
+        tungstenite::HandshakeError::Interrupted(_) => {
+            tungstenite::Error::Io(io::ErrorKind::WouldBlock.into())
+        }
+    })?;
+    let protocol = response
+        .headers()
+        .get(SEC_WEBSOCKET_PROTOCOL)
+        .map(|value| value.to_str().unwrap().to_owned());
+    Ok((websocket, protocol))
+}
+
+/// Wait until the seat of provided player is held (its client disconnected)
+fn wait_disconnection(server: &GameServer, player: PlayerId) {
+    let deadline = Instant::now() + Duration::from_secs(5);
+    while !server.disconnected_players().contains(&player) {
+        assert!(
+            Instant::now() < deadline,
+            "Disconnection of player {} not noticed",
+            player
+        );
+        thread::sleep(Duration::from_millis(10));
+    }
+}
+
+/// Send a message as a JSON text frame
+fn send(websocket: &mut WebSocket<TcpStream>, message: &ClientMessage) {
+    let text = serde_json::to_string(message).unwrap();
+    websocket.send(Message::Text(text)).unwrap();
+}
+
+/// Wait for the next game message, skipping control frames
+fn receive(websocket: &mut WebSocket<TcpStream>) -> ServerMessage {
+    loop {
+        match websocket.read().unwrap() {
+            Message::Text(text) => return serde_json::from_str(&text).unwrap(),
+            Message::Ping(_) | Message::Pong(_) => {}
+            message => panic!("Unexpected message {:?}", message),
+        }
+    }
+}
+
+#[test]
+/// Check if protocol version is negotiated on connect
+fn negotiation_test() {
+    let (_server, gateway, _) = gateway(GatewayConfig::default());
+    let current = subprotocol(PROTOCOL_VERSION);
+
+    let (_, protocol) = open(gateway.local_addr(), &current).unwrap();
+    assert_eq!(protocol, Some(current.clone()));
+
+    // Highest common version is chosen, unknown protocols are ignored
+    let requested = format!("chat, {}, {}", subprotocol(PROTOCOL_VERSION + 1), current);
+    let (_, protocol) = open(gateway.local_addr(), &requested).unwrap();
+    assert_eq!(protocol, Some(current));
+
+    match open(gateway.local_addr(), &subprotocol(PROTOCOL_VERSION + 1)) {
+        Err(tungstenite::Error::Http(response)) => assert_eq!(response.status(), 400),
+        result => panic!("Unexpected handshake result {:?}", result.map(|r| r.1)),
+    }
+}
+
+#[test]
+/// Check if the game is played over WebSocket along with TCP clients
+fn play_test() {
+    let (server, gateway, ids) = gateway(GatewayConfig::default());
+    let (mut websocket, _) = open(gateway.local_addr(), &subprotocol(PROTOCOL_VERSION)).unwrap();
+    let mut b = GameClient::connect(server.local_addr(), ids[1]).unwrap();
+    b.set_timeout(Some(Duration::from_secs(5))).unwrap();
+    assert!(matches!(b.receive().unwrap(), ServerMessage::State(_)));
+
+    send(&mut websocket, &ClientMessage::Join(ids[0]));
+    assert!(matches!(
+        receive(&mut websocket),
+        ServerMessage::Joined { player, .. } if player == ids[0]
+    ));
+    assert!(matches!(receive(&mut websocket), ServerMessage::State(_)));
+
+    let action = ClassicAction::place_atom(ids[0], 0, Position::zero());
+    send(&mut websocket, &ClientMessage::Action(action));
+    assert_eq!(
+        receive(&mut websocket),
+        ServerMessage::Actions(vec![action])
+    );
+    assert_eq!(b.receive().unwrap(), ServerMessage::Actions(vec![action]));
+
+    b.place_atom(1, Position::new(2, 2, 0)).unwrap();
+    assert!(matches!(receive(&mut websocket), ServerMessage::State(_)));
+    assert!(matches!(receive(&mut websocket), ServerMessage::Actions(_)));
+    match receive(&mut websocket) {
+        ServerMessage::State(state) => {
+            assert_eq!(state.current_player(), ids[0]);
+            assert_eq!(state.hash(), server.game_state().state_hash());
+        }
+        message => panic!("Unexpected message {:?}", message),
+    }
+
+    // Binary frames are not part of the protocol
+    websocket.send(Message::Binary(vec![1, 2, 3])).unwrap();
+    loop {
+        match websocket.read() {
+            Ok(Message::Close(frame)) => {
+                assert_eq!(u16::from(frame.unwrap().code), 1003);
+                break;
+            }
+            Ok(_) => {}
+            Err(error) => panic!("Unexpected error {:?}", error),
+        }
+    }
+}
+
+#[test]
+/// Check if clients are pinged, and disconnected when they stop answering
+fn keepalive_test() {
+    let pong_timeout = Duration::from_millis(300);
+    let mut config = GatewayConfig::default();
+    config.set_ping_interval(Duration::from_millis(50));
+    config.set_pong_timeout(pong_timeout);
+    let (server, gateway, ids) = gateway(config);
+    let (mut websocket, _) = open(gateway.local_addr(), &subprotocol(PROTOCOL_VERSION)).unwrap();
+
+    send(&mut websocket, &ClientMessage::Join(ids[0]));
+    let token = match receive(&mut websocket) {
+        ServerMessage::Joined { token, .. } => token,
+        message => panic!("Unexpected message {:?}", message),
+    };
+    assert!(matches!(receive(&mut websocket), ServerMessage::State(_)));
+
+    // Reading answers pings automatically, keeping connection alive past the pong timeout
+    let start = Instant::now();
+    let deadline = start + Duration::from_secs(5);
+    let mut pings = 0;
+    while pings < 5 || start.elapsed() < pong_timeout * 2 {
+        assert!(Instant::now() < deadline, "Only {} pings received", pings);
+        if let Message::Ping(_) = websocket.read().unwrap() {
+            pings += 1;
+        }
+    }
+    assert_eq!(server.bound_players(), vec![ids[0]]);
+
+    // A silent client is dropped, its seat is held
+    wait_disconnection(&server, ids[0]);
+    assert!(server.bound_players().is_empty());
+    assert_eq!(server.disconnected_players(), vec![ids[0]]);
+    while let Ok(message) = websocket.read() {
+        assert!(matches!(message, Message::Ping(_)));
+    }
+
+    // Session can be resumed with a new WebSocket connection
+    let (mut websocket, _) = open(gateway.local_addr(), &subprotocol(PROTOCOL_VERSION)).unwrap();
+    send(
+        &mut websocket,
+        &ClientMessage::Resume { token, received: 0 },
+    );
+    assert_eq!(
+        receive(&mut websocket),
+        ServerMessage::Resumed {
+            player: ids[0],
+            actions: 0
+        }
+    );
+    assert!(matches!(receive(&mut websocket), ServerMessage::State(_)));
+}